@@ -0,0 +1,132 @@
+//! Node.js bindings for razee, every call runs in `process.cwd()` of the node process.
+//!
+//! ```js
+//! const { install } = require('@razee/node')
+//!
+//! const report = await install({ registry: 'https://registry.npmjs.org' })
+//! console.log(report.added, report.warnings)
+//! ```
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use razee::{registry::Registry, InstallOptions, Installer, ResolutionGraph};
+use std::path::PathBuf;
+
+#[napi(object)]
+pub struct JsOptions {
+    /// remote registry url
+    pub registry: Option<String>,
+    /// directory of fixture registry, takes precedence over `registry`
+    pub fixture_registry: Option<String>,
+}
+
+#[napi(object)]
+pub struct JsPackage {
+    pub name: String,
+    pub version: String,
+    pub tarball: String,
+    pub integrity: String,
+}
+
+#[napi(object)]
+pub struct JsInstallReport {
+    pub packages: Vec<JsPackage>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub warnings: Vec<String>,
+    pub total_ms: f64,
+    pub resolution_ms: f64,
+    pub fetching_ms: f64,
+}
+
+/// resolves dependency graph of package.json without touching node_modules
+#[napi]
+pub async fn resolve(options: Option<JsOptions>) -> Result<Vec<JsPackage>> {
+    let registry = to_registry(options);
+
+    return run_local(move || async move {
+        let installer = Installer::new(razee::parse_root_package());
+
+        to_js_packages(&installer.registry(registry).resolve().await)
+    })
+    .await;
+}
+
+/// installs dependencies of package.json, same as running `razee`
+#[napi]
+pub async fn install(options: Option<JsOptions>) -> Result<JsInstallReport> {
+    let registry = to_registry(options);
+
+    return run_local(move || async move {
+        let report = razee::install(InstallOptions {
+            manifest: None,
+            registry: Some(registry),
+            ..Default::default()
+        })
+        .await;
+
+        JsInstallReport {
+            packages: to_js_packages(&report.graph),
+            added: report.added,
+            removed: report.removed,
+            warnings: report.warnings,
+            total_ms: report.timings.total.as_secs_f64() * 1000.0,
+            resolution_ms: report.timings.resolution.as_secs_f64() * 1000.0,
+            fetching_ms: report.timings.fetching.as_secs_f64() * 1000.0,
+        }
+    })
+    .await;
+}
+
+/// razee's install futures are not `Send`, so they are driven by their own
+/// current thread runtime on a blocking thread, instead of napi's runtime
+async fn run_local<F, Fut, T>(task: F) -> Result<T>
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = T>,
+    T: Send + 'static,
+{
+    return tokio::task::spawn_blocking(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("cannot create runtime");
+
+        runtime.block_on(task())
+    })
+    .await
+    // razee panics on errors, panic message ends up here
+    .map_err(|error| Error::from_reason(error.to_string()));
+}
+
+fn to_registry(options: Option<JsOptions>) -> Registry {
+    let Some(options) = options else {
+        return Registry::from_env();
+    };
+
+    if let Some(dir) = options.fixture_registry {
+        return Registry::Fixture(PathBuf::from(dir));
+    }
+
+    return options
+        .registry
+        .map(|url| Registry::Remote(url.trim_end_matches("/").to_string()))
+        .unwrap_or_else(Registry::from_env);
+}
+
+fn to_js_packages(graph: &ResolutionGraph) -> Vec<JsPackage> {
+    let mut packages: Vec<JsPackage> = graph
+        .packages
+        .values()
+        .map(|package| JsPackage {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            tarball: package.dist.tarball.clone(),
+            integrity: package.dist.integrity.clone(),
+        })
+        .collect();
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    return packages;
+}
@@ -0,0 +1,65 @@
+use crate::{parse_root_package, registry::Registry, registry_api::RegistryApi};
+
+/// `razee access get status [pkg]`, `set status=public|restricted [pkg]`,
+/// `grant <read-only|read-write> <scope:team> [pkg]` and `revoke <scope:team> [pkg]`
+pub async fn access(args: &[String]) {
+    let usage =
+        "usage: razee access get status [package] | set status=<public|restricted> [package] \
+        | grant <read-only|read-write> <scope:team> [package] | revoke <scope:team> [package]";
+    let api = RegistryApi::new(&Registry::from_env());
+
+    let package_arg = |index: usize| match args.get(index) {
+        Some(name) => name.to_owned(),
+        None => parse_root_package().name,
+    };
+
+    match args.first().map(|command| command.as_str()) {
+        Some("get") => {
+            if args.get(1).map(|what| what.as_str()) != Some("status") {
+                panic!("{usage}");
+            }
+
+            let package_name = package_arg(2);
+
+            println!("{package_name}: {}", api.visibility(&package_name).await);
+        }
+        Some("set") => {
+            let status = args
+                .get(1)
+                .and_then(|setting| setting.strip_prefix("status="))
+                .filter(|status| *status == "public" || *status == "restricted")
+                .expect(usage);
+            let package_name = package_arg(2);
+
+            // unscoped packages are always public
+            if !package_name.starts_with("@") {
+                panic!("cannot change access of {package_name}: only scoped packages can be restricted");
+            }
+
+            api.set_access(&package_name, status).await;
+
+            println!("{package_name}: {status}");
+        }
+        Some("grant") => {
+            let permissions = args
+                .get(1)
+                .filter(|permissions| *permissions == "read-only" || *permissions == "read-write")
+                .expect(usage);
+            let team = args.get(2).expect(usage);
+            let package_name = package_arg(3);
+
+            api.grant_access(&package_name, team, permissions).await;
+
+            println!("+ {team} ({permissions}): {package_name}");
+        }
+        Some("revoke") => {
+            let team = args.get(1).expect(usage);
+            let package_name = package_arg(2);
+
+            api.revoke_access(&package_name, team).await;
+
+            println!("- {team}: {package_name}");
+        }
+        _ => panic!("{usage}"),
+    }
+}
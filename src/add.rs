@@ -0,0 +1,209 @@
+use node_semver::{Range, Version};
+use serde_json::Value;
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    health,
+    http_client::HttpClient,
+    install, local_tarball,
+    lockfile::{self, LockedPackage, Lockfile, LOCKFILE},
+    manifest::{self, PACKAGE_JSON},
+    parse_root_package,
+    prompt::confirm,
+    resolver, workspaces, Dependency, InstallOptions, RegistryPackage, NODE_MODULES,
+};
+
+/// `razee add <pkg>[@range]... [-D] [-E] [-y] [-w <workspace>]`: saves packages into
+/// package.json and installs them, packages that are new to the project get health report and
+/// confirmation prompt first, local tarballs (`razee add ./vendor/pkg-1.2.3.tgz`) are locked
+/// with their content hash. Inside workspace (or with `--workspace`) packages are saved into
+/// workspace's package.json, but installed and locked from the root. Packages that are already
+/// dev dependencies stay there, unless `-D` moves them
+pub async fn add(args: &[String]) {
+    let usage = "usage: razee add <package>[@<range>]... [--save-dev|-D] [--save-exact|-E] \
+        [--yes|-y] [--workspace|-w <workspace>]";
+    let dev = args.iter().any(|arg| arg == "--save-dev" || arg == "-D");
+    let exact = args.iter().any(|arg| arg == "--save-exact" || arg == "-E");
+    let yes = args.iter().any(|arg| arg == "--yes" || arg == "-y");
+
+    let workspace_flag = args
+        .iter()
+        .position(|arg| arg == "--workspace" || arg == "-w");
+    let workspace = workspace_flag.map(|index| args.get(index + 1).expect(usage));
+
+    let specs: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(index, arg)| {
+            !arg.starts_with("-") && workspace_flag.map(|flag| flag + 1) != Some(*index)
+        })
+        .map(|(_, arg)| arg)
+        .collect();
+
+    if specs.is_empty() {
+        panic!("{usage}");
+    }
+
+    let current_dir = env::current_dir().expect("cannot get current dir");
+    let root = workspaces::find_root(&current_dir).unwrap_or(current_dir.clone());
+
+    let target_dir = match workspace {
+        Some(workspace) => workspaces::find_workspace(&root, workspace)
+            .expect(format!("no workspace {workspace} in {}", root.display()).as_str()),
+        None => current_dir.clone(),
+    };
+
+    // local tarballs are read during install, which runs from the root
+    let relative_dir = current_dir
+        .strip_prefix(&root)
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+
+    let manifest_path = target_dir.join(PACKAGE_JSON);
+    let mut manifest = manifest::read_raw(&manifest_path);
+    env::set_current_dir(&root).expect("cannot enter workspace root");
+
+    // shared with install, so its packuments and connections are reused
+    let client = Arc::new(HttpClient::new());
+    let mut local_tarballs = vec![];
+
+    for spec in specs {
+        let tarball_path = relative_dir.join(spec.strip_prefix("file:").unwrap_or(spec));
+
+        if local_tarball::is_local_tarball(spec) && tarball_path.is_file() {
+            let tarball_spec = local_tarball::to_spec(&tarball_path.to_string_lossy());
+            let dependency = local_tarball::read_dependency(&tarball_spec);
+
+            save(&mut manifest, dev, &dependency.name, &tarball_spec);
+            println!(
+                "+ {}@{} ({tarball_spec})",
+                dependency.name, dependency.version
+            );
+
+            local_tarballs.push(dependency);
+            continue;
+        }
+
+        let (package_name, range) = parse_spec(spec);
+        let packument = client.fetch_packument(package_name).await;
+
+        let is_new = ["dependencies", "devDependencies"].iter().all(|section| {
+            manifest
+                .get(section)
+                .and_then(|deps| deps.get(package_name))
+                .is_none()
+        });
+
+        if is_new {
+            let report = health::check(&packument, &client).await;
+
+            report.print();
+
+            if !yes && !report.warnings().is_empty() && !confirm(&format!("Add {package_name}?")) {
+                println!("skipped {package_name}");
+                continue;
+            }
+        }
+
+        let version = resolve(&packument, package_name, range);
+        let is_tag = packument.pointer(&format!("/dist-tags/{range}")).is_some();
+
+        // like npm, dist tags (e.g. `latest`) are saved as caret range of resolved version
+        let saved_range = match (exact, is_tag || range == "latest") {
+            (true, _) => version.to_string(),
+            (false, true) => format!("^{version}"),
+            (false, false) => range.to_string(),
+        };
+
+        save(&mut manifest, dev, package_name, &saved_range);
+        println!("+ {package_name}@{version}");
+    }
+
+    manifest::write_raw(&manifest_path, &manifest);
+
+    // locked before install, so install checks tarballs against new hashes
+    if !local_tarballs.is_empty() {
+        lock_local_tarballs(&local_tarballs);
+    }
+
+    install(InstallOptions {
+        interactive: io::stdin().is_terminal(),
+        client: Some(client),
+        ..Default::default()
+    })
+    .await;
+}
+
+/// package is kept only in one of sections, dev dependencies stay there, `dev` moves
+/// dependencies there
+fn save(manifest: &mut Value, dev: bool, package_name: &str, range: &str) {
+    let is_dev_dependency = manifest
+        .get("devDependencies")
+        .and_then(|deps| deps.get(package_name))
+        .is_some();
+    let section = match dev || is_dev_dependency {
+        true => "devDependencies",
+        false => "dependencies",
+    };
+
+    for other_section in ["dependencies", "devDependencies"] {
+        if other_section != section {
+            manifest::remove_entry(manifest, other_section, package_name);
+        }
+    }
+
+    manifest::set_entry(manifest, section, package_name, Value::from(range));
+}
+
+fn lock_local_tarballs(dependencies: &[Dependency]) {
+    let root = parse_root_package();
+    let lockfile_path = Path::new(LOCKFILE);
+    let mut lockfile = Lockfile::read(lockfile_path).unwrap_or_else(|| Lockfile::new(&root));
+
+    // root entry has to list newly added dependencies
+    lockfile.packages.extend(Lockfile::new(&root).packages);
+
+    for dependency in dependencies {
+        lockfile.packages.insert(
+            format!("{NODE_MODULES}/{}", dependency.name),
+            LockedPackage {
+                version: Some(dependency.version.clone()),
+                resolved: Some(dependency.dist.tarball.clone()),
+                integrity: Some(dependency.dist.integrity.clone()),
+                dependencies: lockfile::to_locked_dependencies(&dependency.dependencies),
+                ..Default::default()
+            },
+        );
+    }
+
+    lockfile.write(lockfile_path);
+}
+
+/// `name@range`, where name can be scoped, range defaults to `latest`
+pub(crate) fn parse_spec(spec: &str) -> (&str, &str) {
+    return match spec.rsplit_once("@") {
+        Some((name, range)) if !name.is_empty() => (name, range),
+        _ => (spec, "latest"),
+    };
+}
+
+/// same version install would pick for `range`: `latest` (which skips prereleases), highest
+/// satisfying one or dist tag
+fn resolve(packument: &Value, package_name: &str, range: &str) -> Version {
+    let package: RegistryPackage = serde_json::from_value(packument.clone())
+        .expect(format!("invalid packument of {package_name}").as_str());
+
+    if resolver::is_latest_spec(range) {
+        return resolver::resolve_latest(&package);
+    }
+
+    return match Range::parse(range) {
+        Ok(requested) => resolver::resolve_version(&package, &requested),
+        Err(_) => resolver::resolve_tag(&package, range),
+    };
+}
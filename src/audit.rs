@@ -0,0 +1,286 @@
+use chrono::{NaiveDate, Utc};
+use node_semver::{Range, Version};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path, process, sync::Arc};
+
+use crate::{github_actions, http_client::HttpClient, parse_root_package, Installer};
+
+const BULK_ADVISORIES: &str = "-/npm/v1/security/advisories/bulk";
+pub const AUDIT_EXCEPTIONS: &str = "audit-exceptions.json";
+/// severities of advisories, from the lowest one
+pub const SEVERITIES: [&str; 5] = ["info", "low", "moderate", "high", "critical"];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Advisory {
+    pub id: u64,
+    pub title: String,
+    pub severity: String,
+    pub url: String,
+    pub vulnerable_versions: String,
+}
+
+impl Advisory {
+    /// whether `version` is in `vulnerable_versions`, advisory with range, that can't be
+    /// parsed, affects every version, so it is not missed
+    pub fn affects(&self, version: &str) -> bool {
+        let Ok(range) = Range::parse(&self.vulnerable_versions) else {
+            return true;
+        };
+
+        return Version::parse(version).is_ok_and(|version| range.satisfies(&version));
+    }
+}
+
+/// advisory that affects installed version of a package
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub package: String,
+    pub version: String,
+    pub advisory: Advisory,
+}
+
+/// accepted advisory from `audit-exceptions.json`, e.g.
+/// `[{ "id": 1096727, "expires": "2025-01-31", "reason": "not reachable from our code" }]`
+#[derive(Debug, Deserialize)]
+pub struct AuditException {
+    pub id: u64,
+    /// `YYYY-MM-DD`, after that date advisory is reported again
+    pub expires: NaiveDate,
+    /// why finding is accepted, required so exceptions don't become silent ignores
+    pub reason: String,
+}
+
+/// `razee audit [--audit-level <severity>]`, prints findings grouped by severity and exits
+/// with 1 when there are findings of at least `--audit-level` (any finding by default), that
+/// are not accepted in `audit-exceptions.json`
+pub async fn audit(args: &[String]) {
+    let usage = format!(
+        "usage: razee audit [--audit-level {}]",
+        SEVERITIES.join("|")
+    );
+    let audit_level = match args.iter().position(|arg| arg == "--audit-level") {
+        Some(index) => {
+            let level = args.get(index + 1).expect(&usage);
+
+            severity_rank(level).expect(&usage)
+        }
+        None => 0,
+    };
+
+    github_actions::group("Resolve dependencies");
+
+    let client = Arc::new(HttpClient::new());
+    let graph = Installer::new(parse_root_package())
+        .client(client.clone())
+        .resolve()
+        .await;
+
+    github_actions::end_group();
+
+    let mut versions: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+    for package in graph.packages.values() {
+        versions
+            .entry(package.registry_name())
+            .or_default()
+            .push(&package.version);
+    }
+
+    // the same version can be installed at several paths
+    for package_versions in versions.values_mut() {
+        package_versions.sort();
+        package_versions.dedup();
+    }
+
+    let advisories = client
+        .post_registry_json(BULK_ADVISORIES, &serde_json::to_value(&versions).unwrap())
+        .await;
+
+    let mut findings = vec![];
+
+    for package in graph.packages.values() {
+        let Some(package_advisories) = advisories.get(package.registry_name()).cloned() else {
+            continue;
+        };
+
+        let package_advisories: Vec<Advisory> = serde_json::from_value(package_advisories)
+            .expect(format!("cannot parse advisories of {}", package.registry_name()).as_str());
+
+        for advisory in package_advisories {
+            // registry answers with every advisory of the package, fixed versions too
+            if !advisory.affects(&package.version) {
+                continue;
+            }
+
+            findings.push(Finding {
+                package: package.registry_name().to_string(),
+                version: package.version.clone(),
+                advisory,
+            });
+        }
+    }
+
+    findings.sort_by(|a, b| {
+        severity_rank(&b.advisory.severity)
+            .cmp(&severity_rank(&a.advisory.severity))
+            .then(a.package.cmp(&b.package))
+            .then(a.version.cmp(&b.version))
+            .then(a.advisory.id.cmp(&b.advisory.id))
+    });
+    findings.dedup_by(|a, b| {
+        a.package == b.package && a.version == b.version && a.advisory.id == b.advisory.id
+    });
+
+    let exceptions = read_exceptions(Path::new(AUDIT_EXCEPTIONS));
+    let (reported, suppressed) = apply_exceptions(findings, &exceptions);
+
+    let mut counts: Vec<(&str, usize)> = vec![];
+
+    for finding in reported.iter() {
+        let severity = finding.advisory.severity.as_str();
+
+        match counts.last_mut() {
+            Some((last, count)) if *last == severity => *count += 1,
+            _ => {
+                println!("{severity}");
+                counts.push((severity, 1));
+            }
+        }
+
+        println!(
+            "  {} ({}@{}, {})",
+            finding.advisory.title, finding.package, finding.version, finding.advisory.id
+        );
+        println!("    {}", finding.advisory.url);
+
+        // finding is already in the report, annotation only adds it to checks
+        if !github_actions::is_enabled() {
+            continue;
+        }
+
+        github_actions::error(&format!(
+            "{} ({}@{}): {} {}",
+            finding.advisory.title,
+            finding.package,
+            finding.version,
+            finding.advisory.severity,
+            finding.advisory.url
+        ));
+    }
+
+    if !suppressed.is_empty() {
+        println!(
+            "{} accepted by {AUDIT_EXCEPTIONS}",
+            plural(suppressed.len())
+        );
+    }
+
+    if reported.is_empty() {
+        println!("found 0 vulnerabilities");
+        return;
+    }
+
+    let summary: Vec<String> = counts
+        .iter()
+        .map(|(severity, count)| format!("{count} {severity}"))
+        .collect();
+
+    println!("found {} ({})", plural(reported.len()), summary.join(", "));
+
+    let fails = reported.iter().any(|finding| {
+        // unknown severities are not skipped
+        severity_rank(&finding.advisory.severity).unwrap_or(SEVERITIES.len()) >= audit_level
+    });
+
+    if fails {
+        process::exit(1);
+    }
+}
+
+/// position in [`SEVERITIES`]
+fn severity_rank(severity: &str) -> Option<usize> {
+    return SEVERITIES.iter().position(|known| *known == severity);
+}
+
+/// splits findings into reported and suppressed by not yet expired exceptions
+pub fn apply_exceptions(
+    findings: Vec<Finding>,
+    exceptions: &[AuditException],
+) -> (Vec<Finding>, Vec<Finding>) {
+    let today = Utc::now().date_naive();
+    let mut reported = vec![];
+    let mut suppressed = vec![];
+
+    for exception in exceptions {
+        let matches = findings
+            .iter()
+            .any(|finding| finding.advisory.id == exception.id);
+
+        if exception.expires < today && matches {
+            github_actions::warning(&format!(
+                "audit exception for {} expired on {}, it is reported again",
+                exception.id, exception.expires
+            ));
+        } else if !matches {
+            github_actions::warning(&format!(
+                "audit exception for {} matches nothing and can be removed",
+                exception.id
+            ));
+        }
+    }
+
+    for finding in findings {
+        let accepted = exceptions
+            .iter()
+            .any(|exception| exception.id == finding.advisory.id && exception.expires >= today);
+
+        if accepted {
+            suppressed.push(finding);
+        } else {
+            reported.push(finding);
+        }
+    }
+
+    return (reported, suppressed);
+}
+
+pub fn read_exceptions(path: &Path) -> Vec<AuditException> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return vec![];
+    };
+
+    let exceptions: Vec<AuditException> =
+        serde_json::from_str(&content).expect(format!("cannot parse {}", path.display()).as_str());
+
+    if let Some(exception) = exceptions.iter().find(|e| e.reason.trim().is_empty()) {
+        panic!("audit exception for {} has no reason", exception.id);
+    }
+
+    return exceptions;
+}
+
+fn plural(count: usize) -> String {
+    match count {
+        1 => "1 vulnerability".to_string(),
+        _ => format!("{count} vulnerabilities"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advisory_affects_only_vulnerable_versions() {
+        let advisory = Advisory {
+            id: 1,
+            title: "Prototype pollution".to_string(),
+            severity: "high".to_string(),
+            url: "https://github.com/advisories/GHSA-1".to_string(),
+            vulnerable_versions: "<4.17.21".to_string(),
+        };
+
+        assert!(advisory.affects("4.17.20"));
+        assert!(!advisory.affects("4.17.21"));
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{fs_retry::retry, installer::InstallContext, permissions, PackageBin, NODE_MODULES};
+
+/// links package bins into node_modules/.bin and makes their targets executable
+pub fn link_bins(dep_name: &str, bin: &PackageBin, context: &InstallContext) {
+    let bin_dir = format!("{NODE_MODULES}/.bin");
+
+    if let Err(error) = retry(Path::new(&bin_dir), || fs::create_dir_all(&bin_dir)) {
+        context.warn(format!("cannot link bins of {dep_name}: {error}"));
+        return;
+    }
+
+    for (bin_name, bin_path) in bin.entries(dep_name) {
+        if !is_bin_name(&bin_name) {
+            context.warn(format!(
+                "{dep_name} has invalid bin name {bin_name}, skipped"
+            ));
+            continue;
+        }
+
+        let bin_path = bin_path.trim_start_matches("./");
+        let target = format!("{NODE_MODULES}/{dep_name}/{bin_path}");
+
+        if !Path::new(&target).exists() {
+            context.warn(format!("bin target for {bin_name} is missing: {target}"));
+            continue;
+        }
+
+        let target_path = Path::new(&target);
+
+        if !is_inside(target_path, &Path::new(NODE_MODULES).join(dep_name)) {
+            context.warn(format!(
+                "bin {bin_name} of {dep_name} points outside of the package: {bin_path}, skipped"
+            ));
+            continue;
+        }
+
+        if let Err(error) = retry(target_path, || permissions::make_executable(target_path)) {
+            context.warn(format!("cannot make {bin_name} executable: {error}"));
+            continue;
+        }
+
+        let link = format!("{bin_dir}/{bin_name}");
+        let relative_target = format!("../{dep_name}/{bin_path}");
+
+        // symlinks need privileges on windows and don't run in cmd anyway
+        let linked = match cfg!(windows) {
+            true => retry(Path::new(&link), || {
+                write_shims(target_path, &relative_target, &link)
+            }),
+            false => retry(Path::new(&link), || symlink(&relative_target, &link)),
+        };
+
+        if let Err(error) = linked {
+            context.warn(format!("cannot link {bin_name}: {error}"));
+        }
+    }
+}
+
+/// bin is a file right in .bin, so its name can't lead anywhere else
+fn is_bin_name(name: &str) -> bool {
+    return !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..");
+}
+
+/// whether `path` (symlinks resolved) is in `dir`, package can't make files of other
+/// packages (or outside of project) executable
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    let (Ok(path), Ok(dir)) = (fs::canonicalize(path), fs::canonicalize(dir)) else {
+        return false;
+    };
+
+    return path.starts_with(dir);
+}
+
+/// `<link>` for git bash, `<link>.cmd` and `<link>.ps1`, that run `target` (relative to
+/// .bin) with interpreter from its shebang, e.g. `node`, like npm's cmd-shim does
+fn write_shims(target_path: &Path, target: &str, link: &str) -> io::Result<()> {
+    let (program, args) = read_shebang(target_path)?.unwrap_or_default();
+
+    for path in [
+        link.to_string(),
+        format!("{link}.cmd"),
+        format!("{link}.ps1"),
+    ] {
+        // writing through old symlink would overwrite the bin itself
+        if Path::new(&path).symlink_metadata().is_ok() {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    fs::write(link, sh_shim(&program, &args, target))?;
+    fs::write(format!("{link}.cmd"), cmd_shim(&program, &args, target))?;
+    fs::write(format!("{link}.ps1"), ps1_shim(&program, &args, target))?;
+
+    return permissions::make_executable(Path::new(link));
+}
+
+/// interpreter and its arguments, `#!/usr/bin/env node` and `#!/usr/bin/env -S node --flag`
+/// are the same as `#!node`, `None` when file has no shebang and can run by itself
+fn read_shebang(path: &Path) -> io::Result<Option<(String, String)>> {
+    let mut head = vec![0; 512];
+    let read = fs::File::open(path)?.read(&mut head)?;
+    let head = String::from_utf8_lossy(&head[..read]).to_string();
+
+    let Some(line) = head.lines().next().and_then(|line| line.strip_prefix("#!")) else {
+        return Ok(None);
+    };
+
+    let mut words = line.split_whitespace();
+    let mut program = words.next().unwrap_or_default();
+
+    if program.ends_with("/env") {
+        program = words.next().unwrap_or_default();
+
+        if program == "-S" {
+            program = words.next().unwrap_or_default();
+        }
+    }
+
+    if program.is_empty() {
+        return Ok(None);
+    }
+
+    // unix paths, like `/bin/sh`, don't exist on windows, program is found in PATH
+    let program = program.rsplit("/").next().unwrap_or(program).to_string();
+
+    return Ok(Some((program, words.collect::<Vec<_>>().join(" "))));
+}
+
+/// bin, that shim in .bin runs, windows shims are not symlinks, so removed packages leave
+/// only them behind
+pub(crate) fn shim_target(shim: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(shim).ok()?;
+    let (_, target) = content.rsplit_once("\"$basedir/")?;
+    let (target, _) = target.split_once("\"")?;
+
+    return Some(shim.parent()?.join(target));
+}
+
+fn sh_shim(program: &str, args: &str, target: &str) -> String {
+    let command = match program.is_empty() {
+        true => format!("exec \"$basedir/{target}\" \"$@\""),
+        false => format!(
+            "if [ -x \"$basedir/{program}\" ]; then\n  \
+            exec \"$basedir/{program}\" {args} \"$basedir/{target}\" \"$@\"\n\
+            else\n  \
+            exec {program} {args} \"$basedir/{target}\" \"$@\"\n\
+            fi"
+        ),
+    };
+
+    return format!(
+        "#!/bin/sh\n\
+        basedir=$(dirname \"$(echo \"$0\" | sed -e 's,\\\\,/,g')\")\n\n\
+        case `uname` in\n    \
+        *CYGWIN*|*MINGW*|*MSYS*) basedir=`cygpath -w \"$basedir\"`;;\n\
+        esac\n\n\
+        {command}\n"
+    );
+}
+
+fn cmd_shim(program: &str, args: &str, target: &str) -> String {
+    let target = target.replace("/", "\\");
+
+    if program.is_empty() {
+        return format!("@ECHO off\r\n\"%~dp0\\{target}\" %*\r\n");
+    }
+
+    return format!(
+        "@ECHO off\r\n\
+        SETLOCAL\r\n\
+        IF EXIST \"%~dp0\\{program}.exe\" (\r\n  \
+        SET \"_prog=%~dp0\\{program}.exe\"\r\n\
+        ) ELSE (\r\n  \
+        SET \"_prog={program}\"\r\n  \
+        SET PATHEXT=%PATHEXT:;.JS;=;%\r\n\
+        )\r\n\
+        ENDLOCAL & \"%_prog%\" {args} \"%~dp0\\{target}\" %*\r\n"
+    );
+}
+
+fn ps1_shim(program: &str, args: &str, target: &str) -> String {
+    let command = match program.is_empty() {
+        true => format!("& \"$basedir/{target}\" $args"),
+        false => format!(
+            "$exe = if (Test-Path \"$basedir/{program}.exe\") {{ \"$basedir/{program}.exe\" }} \
+            else {{ \"{program}\" }}\n\
+            if ($MyInvocation.ExpectingInput) {{\n  \
+            $input | & $exe {args} \"$basedir/{target}\" $args\n\
+            }} else {{\n  \
+            & $exe {args} \"$basedir/{target}\" $args\n\
+            }}"
+        ),
+    };
+
+    return format!(
+        "#!/usr/bin/env pwsh\n\
+        $basedir = Split-Path $MyInvocation.MyCommand.Definition -Parent\n\
+        {command}\n\
+        exit $LASTEXITCODE\n"
+    );
+}
+
+fn symlink(target: &str, link: &str) -> io::Result<()> {
+    let link_path = Path::new(link);
+
+    if link_path.symlink_metadata().is_ok() {
+        fs::remove_file(link_path)?;
+    }
+
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link_path);
+
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_file(target, link_path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn shims_run_shebang_interpreter() {
+        let dir = env::temp_dir().join(format!("razee-shims-{}", process::id()));
+        let target = dir.join("cli.js");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &target,
+            "#!/usr/bin/env -S node --no-warnings\nconsole.log(1)\n",
+        )
+        .unwrap();
+
+        let (program, args) = read_shebang(&target).unwrap().unwrap();
+        let cmd = cmd_shim(&program, &args, "../pkg/cli.js");
+
+        fs::write(dir.join("cli"), sh_shim(&program, &args, "cli.js")).unwrap();
+        let shim_target = shim_target(&dir.join("cli"));
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(shim_target, Some(target));
+
+        assert_eq!((program.as_str(), args.as_str()), ("node", "--no-warnings"));
+        assert!(cmd.contains("\"%_prog%\" --no-warnings \"%~dp0\\..\\pkg\\cli.js\" %*"));
+    }
+
+    #[test]
+    fn bins_stay_in_their_dirs() {
+        let dir = env::temp_dir().join(format!("razee-bin-dirs-{}", process::id()));
+        let package_dir = dir.join("pkg");
+
+        fs::create_dir_all(package_dir.join("bin")).unwrap();
+        fs::write(package_dir.join("bin/cli.js"), "").unwrap();
+        fs::write(dir.join("outside.js"), "").unwrap();
+
+        let inside = is_inside(&package_dir.join("bin/cli.js"), &package_dir);
+        let outside = is_inside(&package_dir.join("../outside.js"), &package_dir);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(inside);
+        assert!(!outside);
+        assert!(is_bin_name("tsc"));
+        assert!(!is_bin_name("../../x"));
+        assert!(!is_bin_name("..\\x"));
+        assert!(!is_bin_name("@scope/cli"));
+    }
+}
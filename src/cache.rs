@@ -0,0 +1,29 @@
+use crate::metadata_cache::MetadataCache;
+
+/// `razee cache clean` and `razee cache verify`, for cache of registry metadata (see
+/// `razee store` for extracted packages)
+pub fn cache(args: &[String]) {
+    let usage = "usage: razee cache clean | verify";
+    let cache = MetadataCache::from_env()
+        .expect("metadata cache is disabled (RAZEE_CACHE=false) or its dir is unknown");
+
+    match args.first().map(|command| command.as_str()) {
+        Some("clean") => {
+            let removed = cache.clean();
+
+            println!(
+                "Removed {removed} metadata entries from {}",
+                cache.dir().display()
+            );
+        }
+        Some("verify") => {
+            let (checked, removed) = cache.verify();
+
+            println!(
+                "Verified {checked} metadata entries in {}, removed {removed} corrupted",
+                cache.dir().display()
+            );
+        }
+        _ => panic!("{usage}"),
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, Ordering},
+        Mutex,
+    },
+};
+
+static LISTENING: AtomicBool = AtomicBool::new(false);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+/// exit code of cancelled process, same as shells use for processes killed by signal
+static EXIT_CODE: AtomicI32 = AtomicI32::new(130);
+/// temp dirs of work in progress, removed when process exits before it is finished
+static IN_PROGRESS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// turns first SIGINT (Ctrl-C) or SIGTERM into cancellation, so packages that are in
+/// progress can be finished, second signal exits right away
+pub(crate) fn listen() {
+    if LISTENING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(async {
+        let exit_code = wait_for_signal().await;
+
+        EXIT_CODE.store(exit_code, Ordering::SeqCst);
+        CANCELLED.store(true, Ordering::SeqCst);
+
+        eprintln!("\ncancelling, waiting for packages in progress (repeat to exit right away)");
+
+        wait_for_signal().await;
+        exit();
+    });
+}
+
+pub(crate) fn is_cancelled() -> bool {
+    return CANCELLED.load(Ordering::SeqCst);
+}
+
+/// removes temp dirs of unfinished work and exits with code of the signal
+pub(crate) fn exit() -> ! {
+    let in_progress: Vec<PathBuf> = IN_PROGRESS
+        .lock()
+        .map(|mut in_progress| in_progress.drain(..).collect())
+        .unwrap_or_default();
+
+    for path in in_progress {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    process::exit(EXIT_CODE.load(Ordering::SeqCst));
+}
+
+/// `path` is removed, if process is cancelled before [`finish`] is called for it
+pub(crate) fn track(path: &Path) {
+    if let Ok(mut in_progress) = IN_PROGRESS.lock() {
+        in_progress.push(path.to_path_buf());
+    }
+}
+
+pub(crate) fn finish(path: &Path) {
+    if let Ok(mut in_progress) = IN_PROGRESS.lock() {
+        in_progress.retain(|tracked| tracked != path);
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> i32 {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("cannot listen for SIGTERM");
+
+    return tokio::select! {
+        _ = tokio::signal::ctrl_c() => 130,
+        _ = terminate.recv() => 143,
+    };
+}
+
+#[cfg(windows)]
+async fn wait_for_signal() -> i32 {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("cannot listen for Ctrl-C");
+
+    return 130;
+}
@@ -0,0 +1,173 @@
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tar::Archive;
+
+use crate::{
+    http_client::HttpClient,
+    import, integrity,
+    lockfile::{LockedPackage, Lockfile, LOCKFILE},
+    parse_root_package, DependencyDist, NODE_MODULES,
+};
+
+/// how many packages get their files compared with tarball, unless `--all` is passed
+const SPOT_CHECKED_PACKAGES: usize = 10;
+
+/// `razee check [--all]`: compares node_modules with lockfile without changing anything,
+/// exits with 1 when they differ
+pub async fn check(args: &[String]) {
+    let check_all = args.iter().any(|arg| arg == "--all");
+
+    let lockfile = Lockfile::read(Path::new(LOCKFILE))
+        .expect(format!("{LOCKFILE} is missing, nothing to check against").as_str());
+    let installed = import::from_node_modules(&parse_root_package());
+
+    let mut problems = vec![];
+    let mut present = vec![];
+
+    for (path, locked) in lockfile
+        .packages
+        .iter()
+        // root and workspace packages are not installed into node_modules
+        .filter(|(path, _)| path.starts_with(&format!("{NODE_MODULES}/")))
+    {
+        let Some(installed) = installed.packages.get(path) else {
+            problems.push(format!("{path}: missing"));
+            continue;
+        };
+
+        if installed.version != locked.version {
+            problems.push(format!(
+                "{path}: version {} is installed, but {} is locked",
+                installed.version.as_deref().unwrap_or("?"),
+                locked.version.as_deref().unwrap_or("?")
+            ));
+            continue;
+        }
+
+        present.push((path, locked));
+    }
+
+    for path in installed.packages.keys().filter(|path| !path.is_empty()) {
+        if !lockfile.packages.contains_key(path) {
+            problems.push(format!("{path}: not in {LOCKFILE}"));
+        }
+    }
+
+    // rotate spot checked packages between runs, so every package is checked eventually
+    let offset = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as usize)
+        .unwrap_or(0);
+
+    let spot_checked: Vec<_> = if check_all || present.len() <= SPOT_CHECKED_PACKAGES {
+        present
+    } else {
+        present
+            .iter()
+            .cycle()
+            .skip(offset % present.len())
+            .take(SPOT_CHECKED_PACKAGES)
+            .cloned()
+            .collect()
+    };
+
+    let client = HttpClient::new();
+
+    for (path, locked) in spot_checked.iter() {
+        problems.extend(check_files(path, locked, &client).await);
+    }
+
+    if problems.is_empty() {
+        println!(
+            "node_modules match {LOCKFILE} ({} packages, files of {} checked)",
+            lockfile.packages.len() - 1,
+            spot_checked.len()
+        );
+        return;
+    }
+
+    for problem in problems.iter() {
+        println!("{problem}");
+    }
+
+    println!("node_modules differ from {LOCKFILE}, run razee to reinstall");
+    process::exit(1);
+}
+
+/// compares installed files with ones in locked tarball
+async fn check_files(path: &str, locked: &LockedPackage, client: &HttpClient) -> Vec<String> {
+    let Some(resolved) = &locked.resolved else {
+        return vec![];
+    };
+
+    let dist = DependencyDist {
+        integrity: locked.integrity.clone().unwrap_or_default(),
+        shasum: None,
+        tarball: resolved.to_owned(),
+        file_count: None,
+        unpacked_size: None,
+        signatures: vec![],
+        attestations: None,
+    };
+
+    let tarball = client.fetch_tarball(&dist).await;
+
+    if let Some(integrity) = &locked.integrity {
+        if integrity::check(tarball.as_ref(), integrity).is_err() {
+            return vec![format!("{path}: tarball does not match locked integrity")];
+        }
+    }
+
+    let mut problems = vec![];
+
+    for (file, content) in tarball_files(tarball) {
+        let installed_path = Path::new(path).join(&file);
+
+        match fs::read(&installed_path) {
+            Ok(installed) if installed == content => {}
+            Ok(_) => problems.push(format!("{}: modified", installed_path.display())),
+            Err(_) => problems.push(format!("{}: missing", installed_path.display())),
+        }
+    }
+
+    return problems;
+}
+
+/// regular files of tarball, with tarball's root folder stripped
+fn tarball_files(tarball: &Bytes) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut archive = Archive::new(GzDecoder::new(Cursor::new(tarball)));
+    let mut files = vec![];
+
+    let Ok(entries) = archive.entries() else {
+        return files;
+    };
+
+    for mut entry in entries.flatten() {
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let Some(path) = entry
+            .path()
+            .ok()
+            .map(|path| path.components().skip(1).collect::<PathBuf>())
+        else {
+            continue;
+        };
+
+        let mut content = vec![];
+
+        if entry.read_to_end(&mut content).is_ok() {
+            files.push((path, content));
+        }
+    }
+
+    return files;
+}
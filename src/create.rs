@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    process::{self, Command},
+};
+
+use crate::{dlx::InstallDir, install, InstallOptions, Manifest, NODE_MODULES};
+
+/// `razee create vite@latest my-app`: installs `create-vite` into temp dir and
+/// runs its bin in current directory with the rest of arguments
+pub async fn create(template: &str, args: &[String]) {
+    let (package_name, range) = to_create_package(template);
+    let project_dir = env::current_dir().expect("cannot get current dir");
+
+    let install_dir = InstallDir(
+        env::temp_dir()
+            .join("razee-create")
+            .join(process::id().to_string()),
+    );
+
+    fs::create_dir_all(&install_dir.0).expect("cannot create temp dir for template");
+    env::set_current_dir(&install_dir.0).expect("cannot enter temp dir for template");
+
+    let manifest = Manifest {
+        name: "razee-create".to_string(),
+        dependencies: Some(HashMap::from([(package_name.clone(), range)])),
+        ..Default::default()
+    };
+
+    let report = install(InstallOptions {
+        manifest: Some(manifest),
+        ..Default::default()
+    })
+    .await;
+
+    env::set_current_dir(&project_dir).expect("cannot go back to project dir");
+
+    let package = report
+        .graph
+        .get(&package_name)
+        .expect(format!("{package_name} was not installed").as_str());
+
+    let bins = package
+        .bin
+        .as_ref()
+        .map(|bin| bin.entries(&package.name))
+        .unwrap_or_default();
+
+    // packages with several bins usually have one named after the package
+    let bin_name = package_name.split("/").last().unwrap_or(&package_name);
+    let (_, bin_path) = bins
+        .iter()
+        .find(|(name, _)| name == bin_name)
+        .or(bins.first())
+        .expect(format!("{package_name} has no bin to run").as_str());
+
+    let bin_path = install_dir
+        .0
+        .join(NODE_MODULES)
+        .join(&package_name)
+        .join(bin_path.trim_start_matches("./"));
+
+    let status = Command::new("node")
+        .arg(&bin_path)
+        .args(args)
+        .current_dir(&project_dir)
+        .status()
+        .expect("cannot run node, is it installed?");
+
+    drop(install_dir);
+
+    if !status.success() {
+        process::exit(status.code().unwrap_or(1));
+    }
+}
+
+/// maps template to package the same way npm does:
+/// `vite` -> `create-vite`, `@scope` -> `@scope/create`, `@scope/app` -> `@scope/create-app`
+fn to_create_package(template: &str) -> (String, String) {
+    let (name, range) = match template.rsplit_once("@") {
+        Some((name, range)) if !name.is_empty() => (name, range),
+        _ => (template, "latest"),
+    };
+
+    let package_name = match name.split_once("/") {
+        Some((scope, name)) => format!("{scope}/create-{name}"),
+        None if name.starts_with("@") => format!("{name}/create"),
+        None => format!("create-{name}"),
+    };
+
+    return (package_name, range.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_dist_tags_of_template() {
+        assert_eq!(
+            to_create_package("vite"),
+            ("create-vite".to_string(), "latest".to_string())
+        );
+        assert_eq!(
+            to_create_package("@scope@next"),
+            ("@scope/create".to_string(), "next".to_string())
+        );
+        assert_eq!(
+            to_create_package("@scope/app@^2"),
+            ("@scope/create-app".to_string(), "^2".to_string())
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use node_semver::{Range, Version};
+use std::{
+    collections::BTreeMap,
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use crate::{
+    import,
+    lockfile::{Lockfile, LOCKFILE},
+    parse_root_package, remove, Installer, NODE_MODULES,
+};
+
+/// installed versions of a package and ranges, it is requested with
+#[derive(Debug, Default)]
+struct Duplicate {
+    versions: Vec<Version>,
+    ranges: Vec<Range>,
+}
+
+/// `razee dedupe [--dry-run]`: collapses packages, that are installed in several versions,
+/// to as few of them as ranges allow, e.g. `^1.0.0` and `~1.1.0` can share `1.1.5` instead of
+/// having `1.9.0` too. Lockfile is resolved again with the rest of versions kept, and
+/// node_modules is relinked from new graph
+pub async fn dedupe(args: &[String]) {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let lockfile = Lockfile::read(Path::new(LOCKFILE))
+        .expect(format!("{LOCKFILE} is missing, run `razee` first").as_str());
+
+    let mut seed = import::from_lockfile(&lockfile, &[]);
+    let mut collapsed = 0;
+
+    for (name, duplicate) in find_duplicates(&lockfile) {
+        let kept = collapse(&duplicate.versions, &duplicate.ranges);
+
+        if kept.len() == duplicate.versions.len() {
+            continue;
+        }
+
+        let to_list = |versions: &[Version]| {
+            versions
+                .iter()
+                .map(Version::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        println!(
+            "{name}: {} -> {}",
+            to_list(&duplicate.versions),
+            to_list(&kept)
+        );
+
+        if let Some(seeded) = seed.get_mut(&name) {
+            seeded.retain(|seeded| kept.contains(&seeded.version));
+        }
+
+        collapsed += 1;
+    }
+
+    if collapsed == 0 {
+        println!("nothing to dedupe");
+        return;
+    }
+
+    if dry_run {
+        return;
+    }
+
+    let installed_before: Vec<String> = lockfile.packages.into_keys().collect();
+    let report = Installer::new(parse_root_package())
+        .interactive(io::stdin().is_terminal())
+        .seed(seed)
+        .install()
+        .await;
+
+    remove::remove_leftovers(installed_before, &report.graph);
+
+    match collapsed {
+        1 => println!("deduped 1 package"),
+        _ => println!("deduped {collapsed} packages"),
+    }
+}
+
+/// packages with several versions in lockfile, ones requested with anything but semver
+/// ranges (e.g. aliases or git urls) are left as they are
+fn find_duplicates(lockfile: &Lockfile) -> BTreeMap<String, Duplicate> {
+    let mut duplicates: BTreeMap<String, Duplicate> = BTreeMap::new();
+    let mut unsupported = vec![];
+
+    for (path, locked) in lockfile.packages.iter() {
+        if let Some(install_path) = path.strip_prefix(&format!("{NODE_MODULES}/")) {
+            let name = install_path
+                .rsplit_once(&format!("/{NODE_MODULES}/"))
+                .map_or(install_path, |(_, name)| name);
+
+            let version = locked
+                .version
+                .as_ref()
+                .and_then(|version| Version::parse(version).ok());
+
+            match version {
+                Some(version) => duplicates
+                    .entry(name.to_string())
+                    .or_default()
+                    .versions
+                    .push(version),
+                None => unsupported.push(name.to_string()),
+            }
+        }
+
+        let requested = [&locked.dependencies, &locked.dev_dependencies]
+            .into_iter()
+            .flatten()
+            .flatten();
+
+        for (name, range) in requested {
+            match Range::parse(range) {
+                Ok(range) => duplicates
+                    .entry(name.clone())
+                    .or_default()
+                    .ranges
+                    .push(range),
+                Err(_) => unsupported.push(name.clone()),
+            }
+        }
+    }
+
+    duplicates.retain(|name, duplicate| {
+        duplicate.versions.sort();
+        duplicate.versions.dedup();
+
+        return duplicate.versions.len() > 1 && !unsupported.contains(name);
+    });
+
+    return duplicates;
+}
+
+/// fewest versions, that satisfy every range, the highest ones are preferred (greedily,
+/// version that satisfies the most ranges is kept first)
+fn collapse(versions: &[Version], ranges: &[Range]) -> Vec<Version> {
+    let mut uncovered: Vec<&Range> = ranges.iter().collect();
+    let mut kept = vec![];
+
+    while !uncovered.is_empty() {
+        let best = versions
+            .iter()
+            .filter(|version| !kept.contains(*version))
+            .map(|version| {
+                let covered = uncovered
+                    .iter()
+                    .filter(|range| range.satisfies(version))
+                    .count();
+
+                (covered, version)
+            })
+            .filter(|(covered, _)| *covered > 0)
+            .max();
+
+        // range, that no installed version satisfies, is resolved from registry again
+        let Some((_, version)) = best else {
+            break;
+        };
+
+        uncovered.retain(|range| !range.satisfies(version));
+        kept.push(version.clone());
+    }
+
+    kept.sort();
+
+    return kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(versions: &[&str]) -> Vec<Version> {
+        return versions
+            .iter()
+            .map(|version| Version::parse(version).unwrap())
+            .collect();
+    }
+
+    fn ranges(ranges: &[&str]) -> Vec<Range> {
+        return ranges
+            .iter()
+            .map(|range| Range::parse(range).unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn collapses_to_shared_version() {
+        assert_eq!(
+            collapse(
+                &versions(&["1.1.5", "1.9.0"]),
+                &ranges(&["^1.0.0", "~1.1.0"])
+            ),
+            versions(&["1.1.5"])
+        );
+    }
+
+    #[test]
+    fn keeps_versions_of_incompatible_ranges() {
+        assert_eq!(
+            collapse(
+                &versions(&["1.1.5", "1.9.0", "2.0.0"]),
+                &ranges(&["^1.0.0", "~1.1.0", "^2.0.0"])
+            ),
+            versions(&["1.1.5", "2.0.0"])
+        );
+    }
+}
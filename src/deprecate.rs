@@ -0,0 +1,60 @@
+use node_semver::{Range, Version};
+use serde_json::Value;
+
+use crate::{registry::Registry, registry_api::RegistryApi};
+
+/// `razee deprecate <pkg>[@<range>] <message>`, empty message un-deprecates versions
+pub async fn deprecate(args: &[String]) {
+    let usage = "usage: razee deprecate <package>[@<range>] <message>";
+    let spec = args.first().expect(usage);
+    let message = args.get(1).expect(usage);
+
+    let (package_name, range) = match spec.rsplit_once("@") {
+        Some((name, range)) if !name.is_empty() => (name, range),
+        _ => (spec.as_str(), "*"),
+    };
+
+    let range = Range::parse(range).expect(format!("invalid version range: {range}").as_str());
+
+    let api = RegistryApi::new(&Registry::from_env());
+    let mut packument = api.packument(package_name).await;
+
+    let versions = packument
+        .get_mut("versions")
+        .and_then(Value::as_object_mut)
+        .expect(format!("{package_name} has no published versions").as_str());
+
+    let mut matched = vec![];
+
+    for (version, document) in versions.iter_mut() {
+        let Ok(parsed) = Version::parse(version) else {
+            continue;
+        };
+
+        if !range.satisfies(&parsed) {
+            continue;
+        }
+
+        if let Some(document) = document.as_object_mut() {
+            // registry treats empty message as "not deprecated"
+            document.insert("deprecated".to_string(), Value::from(message.as_str()));
+            matched.push(version.clone());
+        }
+    }
+
+    if matched.is_empty() {
+        panic!("no versions of {package_name} match {range}");
+    }
+
+    api.update_packument(package_name, &packument).await;
+
+    let action = if message.is_empty() {
+        "un-deprecated"
+    } else {
+        "deprecated"
+    };
+
+    for version in matched {
+        println!("{action} {package_name}@{version}");
+    }
+}
@@ -0,0 +1,45 @@
+use node_semver::Version;
+use std::{env, fs, path::PathBuf};
+
+use crate::{fetcher::extract_tarball, http_client::HttpClient, patch};
+
+/// prints unified diff between contents of two package versions
+pub async fn diff(from_spec: &str, to_spec: &str, client: &HttpClient) {
+    let from_dir = download(from_spec, client).await;
+    let to_dir = download(to_spec, client).await;
+
+    print!("{}", patch::diff_dirs(&from_dir, &to_dir));
+}
+
+async fn download(spec: &str, client: &HttpClient) -> PathBuf {
+    let (name, version) = parse_spec(spec);
+
+    let dependency = client.fetch_dependency(&name, &version).await.to_owned();
+    let tarball = client.fetch_tarball(&dependency.dist).await;
+
+    let dir = env::temp_dir()
+        .join("razee-diff")
+        .join(patch::escape_name(&format!("{name}@{version}")));
+
+    if dir.exists() {
+        fs::remove_dir_all(&dir).expect("cannot clean up previous diff dir");
+    }
+
+    extract_tarball(tarball.as_ref(), dir.to_str().expect("temp dir is not valid utf-8"))
+        .expect("cannot extract package");
+
+    return dir;
+}
+
+/// splits `name@version`, scoped names also start with "@" so splitting is done from the end
+fn parse_spec(spec: &str) -> (String, Version) {
+    let (name, version) = spec
+        .rsplit_once("@")
+        .filter(|(name, _)| !name.is_empty())
+        .expect(format!("expected <package>@<version>, got: {spec}").as_str());
+
+    let version = Version::parse(version)
+        .expect(format!("invalid version in {spec}, exact version is required").as_str());
+
+    return (name.to_string(), version);
+}
@@ -0,0 +1,44 @@
+use crate::{parse_root_package, registry::Registry, registry_api::RegistryApi};
+
+/// `razee dist-tag add <pkg>@<version> [tag]`, `rm <pkg> <tag>` and `ls <pkg>`
+pub async fn dist_tag(args: &[String]) {
+    let usage =
+        "usage: razee dist-tag add <package>@<version> [tag] | rm <package> <tag> | ls [package]";
+    let api = RegistryApi::new(&Registry::from_env());
+
+    match args.first().map(|command| command.as_str()) {
+        Some("add") => {
+            let spec = args.get(1).expect(usage);
+            let tag = args.get(2).map(|tag| tag.as_str()).unwrap_or("latest");
+
+            let (package_name, version) = spec
+                .rsplit_once("@")
+                .filter(|(name, _)| !name.is_empty())
+                .expect(usage);
+
+            api.add_dist_tag(package_name, version, tag).await;
+
+            println!("+{tag}: {package_name}@{version}");
+        }
+        Some("rm") => {
+            let package_name = args.get(1).expect(usage);
+            let tag = args.get(2).expect(usage);
+
+            api.remove_dist_tag(package_name, tag).await;
+
+            println!("-{tag}: {package_name}");
+        }
+        Some("ls") => {
+            // defaults to package in current directory, like npm does
+            let package_name = match args.get(1) {
+                Some(name) => name.to_owned(),
+                None => parse_root_package().name,
+            };
+
+            for (tag, version) in api.dist_tags(&package_name).await {
+                println!("{tag}: {version}");
+            }
+        }
+        _ => panic!("{usage}"),
+    }
+}
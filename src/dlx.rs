@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use crate::{add::parse_spec, install, scripts, InstallOptions, Manifest, NODE_MODULES};
+
+/// temp dir, that packages are installed into, it is removed even when command panics
+pub(crate) struct InstallDir(pub(crate) PathBuf);
+
+impl Drop for InstallDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// `razee dlx [--package|-p <package>]... <command>[@<range>] [args...]`: installs package into
+/// temp dir and runs its bin with `args` in current directory, then removes the temp dir, so
+/// local project (package.json, lockfile and node_modules) is not touched. With `--package`
+/// those packages are installed and `command` is the name of bin to run, bins of all of them
+/// are in PATH
+pub async fn dlx(args: &[String]) {
+    let usage = "usage: razee dlx [--package|-p <package>]... <command>[@<range>] [args...]";
+    let mut packages = vec![];
+    let mut index = 0;
+
+    while let Some(arg) = args.get(index) {
+        if arg != "--package" && arg != "-p" {
+            break;
+        }
+
+        packages.push(args.get(index + 1).expect(usage).clone());
+        index += 2;
+    }
+
+    let command = args.get(index).expect(usage);
+    let command_args = &args[index + 1..];
+
+    // without --package, command is the package, and it is asked for its bin
+    let (package_name, packages) = match packages.is_empty() {
+        true => (
+            Some(parse_spec(command).0.to_string()),
+            vec![command.clone()],
+        ),
+        false => (None, packages),
+    };
+
+    let project_dir = env::current_dir().expect("cannot get current dir");
+    let install_dir = InstallDir(
+        env::temp_dir()
+            .join("razee-dlx")
+            .join(process::id().to_string()),
+    );
+
+    fs::create_dir_all(&install_dir.0).expect("cannot create temp dir for dlx");
+    env::set_current_dir(&install_dir.0).expect("cannot enter temp dir for dlx");
+
+    let dependencies: HashMap<String, String> = packages
+        .iter()
+        .map(|spec| {
+            let (name, range) = parse_spec(spec);
+
+            (name.to_string(), range.to_string())
+        })
+        .collect();
+
+    let manifest = Manifest {
+        name: "razee-dlx".to_string(),
+        dependencies: Some(dependencies),
+        ..Default::default()
+    };
+
+    let report = install(InstallOptions {
+        manifest: Some(manifest),
+        ..Default::default()
+    })
+    .await;
+
+    env::set_current_dir(&project_dir).expect("cannot go back to project dir");
+
+    let bin_name = match package_name {
+        Some(package_name) => {
+            let package = report
+                .graph
+                .get(&package_name)
+                .expect(format!("{package_name} was not installed").as_str());
+
+            let bins: Vec<String> = package
+                .bin
+                .as_ref()
+                .map(|bin| bin.entries(&package.name))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+
+            // packages with several bins usually have one named after the package
+            let unscoped = package_name.rsplit("/").next().unwrap_or(&package_name);
+
+            bins.iter()
+                .find(|name| *name == unscoped)
+                .or(bins.first())
+                .expect(format!("{package_name} has no bin to run").as_str())
+                .clone()
+        }
+        None => command.clone(),
+    };
+
+    let bin_dir = install_dir.0.join(NODE_MODULES).join(".bin");
+    let bin_path = match cfg!(windows) {
+        true => bin_dir.join(format!("{bin_name}.cmd")),
+        false => bin_dir.join(&bin_name),
+    };
+
+    if !bin_path.exists() {
+        panic!("{bin_name} is not a bin of {}", packages.join(", "));
+    }
+
+    let status = Command::new(&bin_path)
+        .args(command_args)
+        .current_dir(&project_dir)
+        .env("PATH", path_with(&bin_dir))
+        .status()
+        .expect(format!("cannot run {bin_name}").as_str());
+
+    drop(install_dir);
+
+    if !status.success() {
+        process::exit(scripts::exit_code(status));
+    }
+}
+
+/// bins of installed packages can run each other
+fn path_with(bin_dir: &Path) -> OsString {
+    let path = env::var_os("PATH").unwrap_or_default();
+
+    return env::join_paths(
+        [bin_dir.to_path_buf()]
+            .into_iter()
+            .chain(env::split_paths(&path)),
+    )
+    .expect("cannot build PATH for dlx");
+}
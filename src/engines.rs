@@ -0,0 +1,79 @@
+use node_semver::{Range, Version};
+use serde_json::Value;
+use std::{env, process::Command};
+
+use crate::logger;
+
+/// checks `engines.node` of packages against node, that will run them
+#[derive(Debug, Clone)]
+pub(crate) struct EngineCheck {
+    /// `None` when node is not installed, nothing is checked then
+    pub node_version: Option<Version>,
+    /// fail install instead of warning, like npm's `engine-strict`
+    pub strict: bool,
+}
+
+impl EngineCheck {
+    /// asks `node --version`, unless `RAZEE_NODE_VERSION` is set, e.g. when node of
+    /// deployment differs from local one
+    pub(crate) fn detect(strict: bool) -> EngineCheck {
+        let version = env::var("RAZEE_NODE_VERSION").ok().or_else(|| {
+            let output = Command::new("node").arg("--version").output().ok()?;
+
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+        });
+
+        let node_version =
+            version.and_then(|version| Version::parse(version.trim().trim_start_matches("v")).ok());
+
+        if node_version.is_none() {
+            logger::log_debug("node is not found, engines of packages are not checked");
+        }
+
+        return EngineCheck {
+            node_version,
+            strict,
+        };
+    }
+
+    /// why package with `engines` can't run on current node, `None` when it can (or when
+    /// range can't be parsed, old packages have all kinds of values there)
+    pub(crate) fn mismatch(&self, engines: Option<&Value>) -> Option<String> {
+        let node_version = self.node_version.as_ref()?;
+        let range = engines?.get("node")?.as_str()?;
+
+        if Range::parse(range).ok()?.satisfies(node_version) {
+            return None;
+        }
+
+        return Some(format!("node {range} (current: {node_version})"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn checks_node_range() {
+        let check = EngineCheck {
+            node_version: Some(Version::parse("18.19.0").unwrap()),
+            strict: false,
+        };
+
+        assert_eq!(check.mismatch(Some(&json!({ "node": ">=16" }))), None);
+        assert_eq!(
+            check
+                .mismatch(Some(&json!({ "node": ">=20.0.0" })))
+                .as_deref(),
+            Some("node >=20.0.0 (current: 18.19.0)")
+        );
+        // old packages have engines as list
+        assert_eq!(check.mismatch(Some(&json!(["node >= 0.4"]))), None);
+        assert_eq!(check.mismatch(None), None);
+    }
+}
@@ -0,0 +1,512 @@
+use flate2::read::GzDecoder;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    panic,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use tar::Archive;
+use tokio::task;
+use walkdir::WalkDir;
+
+use crate::{
+    cancel, fs_retry::retry, http_client::HttpClient, installer::InstallContext, integrity,
+    permissions, signatures, Dependency, DependencyDist, NODE_MODULES,
+};
+
+/// downloads and extracts package into `dep_dir`, unless it is already there,
+/// returns whether package is in node_modules now
+pub(crate) async fn download_tarball(
+    package: &Dependency,
+    dep_dir: &str,
+    context: &InstallContext,
+    client: Arc<HttpClient>,
+) -> bool {
+    if let Err(error) = signatures::verify(package, &context.signatures, &client).await {
+        panic!(
+            "cannot install {}@{}: {error}",
+            package.name, package.version
+        );
+    }
+
+    if Path::new(dep_dir).exists() {
+        if let Some(file_count) = package.dist.file_count {
+            let mut file_counter = 0;
+
+            for entry in WalkDir::new(dep_dir) {
+                let entry = entry.unwrap();
+
+                if entry.file_type().is_file() {
+                    file_counter += 1;
+                }
+            }
+
+            if file_counter == file_count {
+                return true;
+            }
+        }
+    }
+
+    let copy = context
+        .patched_dependencies
+        .contains_key(&format!("{}@{}", package.name, package.version));
+    let stored = context.store.as_ref().and_then(|store| {
+        let package_dir = store.package_dir(&package.dist.integrity)?;
+
+        return Some((store, package_dir));
+    });
+
+    // package that is already in store only has to be linked
+    if let Some((store, package_dir)) = stored.as_ref().filter(|(_, dir)| dir.is_dir()) {
+        context.hooks.before_extract(package, Path::new(dep_dir));
+
+        let (store, package_dir, target) =
+            ((*store).clone(), package_dir.clone(), dep_dir.to_string());
+        let linked = run_fs(context, move || {
+            install_atomically(Path::new(&target), |temp_dir| {
+                store.link(&package_dir, temp_dir, copy)
+            })
+        })
+        .await;
+
+        return finish_extract(package, dep_dir, context, linked);
+    }
+
+    context.hooks.before_fetch(package);
+
+    let mut tarball = client.fetch_tarball_file(&package.dist).await;
+
+    // cached (or stored remotely) tarball can be truncated, so it is downloaded once more
+    if let Err(error) = verify_tarball(&tarball, &package.dist) {
+        context.warn(format!(
+            "{}@{} from {} is corrupted ({error}), downloading it again",
+            package.name, package.version, package.dist.tarball
+        ));
+
+        tarball = client.refetch_tarball_file(&package.dist).await;
+
+        if let Err(error) = verify_tarball(&tarball, &package.dist) {
+            panic!(
+                "cannot install {}@{}: tarball {} is corrupted: {error}",
+                package.name, package.version, package.dist.tarball
+            );
+        }
+    }
+
+    // extraction is not interrupted, so cancelled install has only complete packages
+    if cancel::is_cancelled() {
+        return false;
+    }
+
+    context.hooks.after_fetch(package, &tarball);
+    context.hooks.before_extract(package, Path::new(dep_dir));
+
+    let store = stored.map(|(store, _)| store.clone());
+    let integrity = package.dist.integrity.clone();
+    let target = dep_dir.to_string();
+
+    // tarball is streamed from file, it is never fully in memory
+    let extracted = run_fs(context, move || {
+        install_atomically(Path::new(&target), |temp_dir| match store {
+            Some(store) => store
+                .add(&integrity, |staging_dir| {
+                    extract_tarball(open_tarball(&tarball)?, &staging_dir.to_string_lossy())
+                })
+                .and_then(|package_dir| store.link(&package_dir, temp_dir, copy)),
+            None => extract_tarball(open_tarball(&tarball)?, &temp_dir.to_string_lossy()),
+        })
+    })
+    .await;
+
+    return finish_extract(package, dep_dir, context, extracted);
+}
+
+/// writes package into hidden sibling of `dep_dir` and moves it into place once it is
+/// complete, so crashed (or killed) install never leaves half of package, that would
+/// pass as installed next time
+fn install_atomically(
+    dep_dir: &Path,
+    write: impl FnOnce(&Path) -> io::Result<()>,
+) -> io::Result<()> {
+    let name = dep_dir.file_name().unwrap_or_default().to_string_lossy();
+    let temp_dir = dep_dir.with_file_name(format!(".{name}.razee-tmp"));
+
+    // left by crashed install
+    if fs::symlink_metadata(&temp_dir).is_ok() {
+        remove_path(&temp_dir)?;
+    }
+
+    cancel::track(&temp_dir);
+    retry(&temp_dir, || fs::create_dir_all(&temp_dir))?;
+
+    let written = write(&temp_dir).and_then(|_| move_into_place(&temp_dir, dep_dir));
+
+    if written.is_err() && fs::symlink_metadata(&temp_dir).is_ok() {
+        let _ = remove_path(&temp_dir);
+    }
+
+    cancel::finish(&temp_dir);
+
+    return written;
+}
+
+/// whole dir is renamed, when there is no package at `dep_dir` yet. Otherwise package
+/// is replaced entry by entry, keeping its node_modules, as nested packages can be
+/// installed before it (e.g. from lockfile) or kept from previous install
+fn move_into_place(temp_dir: &Path, dep_dir: &Path) -> io::Result<()> {
+    if !fs::symlink_metadata(dep_dir).is_ok_and(|metadata| metadata.is_dir()) {
+        // links (e.g. to workspace package) and files are replaced, not followed
+        if fs::symlink_metadata(dep_dir).is_ok() {
+            remove_path(dep_dir)?;
+        }
+
+        match fs::rename(temp_dir, dep_dir) {
+            Ok(()) => return Ok(()),
+            // nested package has just created it
+            Err(_) if dep_dir.is_dir() => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    for entry in fs::read_dir(dep_dir)? {
+        let entry = entry?;
+
+        if entry.file_name() != NODE_MODULES {
+            remove_path(&entry.path())?;
+        }
+    }
+
+    for entry in fs::read_dir(temp_dir)? {
+        let entry = entry?;
+        let target = dep_dir.join(entry.file_name());
+
+        if entry.file_name() != NODE_MODULES || !target.exists() {
+            retry(&target, || fs::rename(entry.path(), &target))?;
+            continue;
+        }
+
+        // bundled dependencies, installed ones are kept
+        for bundled in fs::read_dir(entry.path())? {
+            let bundled = bundled?;
+            let target = target.join(bundled.file_name());
+
+            if fs::symlink_metadata(&target).is_err() {
+                retry(&target, || fs::rename(bundled.path(), &target))?;
+            }
+        }
+    }
+
+    return remove_path(temp_dir);
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        return retry(path, || fs::remove_dir_all(path));
+    }
+
+    return retry(path, || fs::remove_file(path));
+}
+
+/// runs blocking filesystem work aside from resolution and downloads, at most
+/// `fs_concurrency` at once
+async fn run_fs<T: Send + 'static>(
+    context: &InstallContext,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    let _permit = context
+        .fs_limit
+        .acquire()
+        .await
+        .expect("fs limit is never closed");
+
+    return match task::spawn_blocking(work).await {
+        Ok(result) => result,
+        Err(error) => match error.try_into_panic() {
+            Ok(panic) => panic::resume_unwind(panic),
+            Err(error) => panic!("filesystem task failed: {error}"),
+        },
+    };
+}
+
+/// broken package is reported, but doesn't abort install of others
+fn finish_extract(
+    package: &Dependency,
+    dep_dir: &str,
+    context: &InstallContext,
+    extracted: io::Result<()>,
+) -> bool {
+    if let Err(error) = extracted {
+        context.warn(format!(
+            "cannot extract {}@{}: {error}",
+            package.name, package.version
+        ));
+        return false;
+    }
+
+    context.hooks.after_extract(package, Path::new(dep_dir));
+
+    return true;
+}
+
+/// checks tarball against integrity from registry (so nothing tampered with is extracted),
+/// and makes sure it can be fully unpacked
+pub(crate) fn verify_tarball(tarball: &Path, dist: &DependencyDist) -> Result<(), String> {
+    if let Some(integrity) = integrity::expected(dist) {
+        let content = open_tarball(tarball).map_err(|error| error.to_string())?;
+
+        integrity::check(content, &integrity)?;
+    }
+
+    let tarball = open_tarball(tarball).map_err(|error| error.to_string())?;
+    let mut archive = Archive::new(GzDecoder::new(tarball));
+    let entries = archive.entries().map_err(|error| error.to_string())?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|error| error.to_string())?;
+
+        io::copy(&mut entry, &mut io::sink()).map_err(|error| error.to_string())?;
+    }
+
+    return Ok(());
+}
+
+fn open_tarball(tarball: &Path) -> io::Result<BufReader<File>> {
+    return File::open(tarball).map(BufReader::new);
+}
+
+/// extracts package tarball (gzipped, as it is in registry) into `dep_dir`, stripping
+/// tarball's root folder (`package/`, some packages have other names). Entries and links,
+/// that would end up outside of `dep_dir`, fail whole package
+pub(crate) fn extract_tarball(tarball: impl Read, dep_dir: &str) -> io::Result<()> {
+    let dep_dir = Path::new(dep_dir);
+
+    retry(dep_dir, || fs::create_dir_all(dep_dir))?;
+
+    let real_dep_dir = fs::canonicalize(dep_dir)?;
+    let mut archive = Archive::new(GzDecoder::new(tarball));
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+
+        // devices, fifos, etc. are never part of packages
+        if !(entry_type.is_file()
+            || entry_type.is_dir()
+            || entry_type.is_symlink()
+            || entry_type.is_hard_link())
+        {
+            continue;
+        }
+
+        let archive_path = entry.path()?.to_path_buf();
+        let relative_path = package_path(&archive_path).ok_or_else(|| escapes(&archive_path))?;
+
+        // root folder itself
+        if relative_path.as_os_str().is_empty() {
+            continue;
+        }
+
+        let entry_path = dep_dir.join(&relative_path);
+        let parent = entry_path.parent().unwrap_or(dep_dir);
+
+        retry(parent, || fs::create_dir_all(parent))?;
+
+        // folders can be symlinks from earlier entries, so real path of parent is checked
+        let real_parent = fs::canonicalize(parent)?;
+
+        if !real_parent.starts_with(&real_dep_dir) {
+            return Err(escapes(&archive_path));
+        }
+
+        if fs::symlink_metadata(&entry_path).is_ok() {
+            continue;
+        }
+
+        if entry_type.is_symlink() {
+            let target = entry.link_name()?.ok_or_else(|| escapes(&archive_path))?;
+
+            if !normalize(&real_parent.join(target)).starts_with(&real_dep_dir) {
+                return Err(escapes(&archive_path));
+            }
+
+            retry(&entry_path, || entry.unpack(&entry_path))?;
+            continue;
+        }
+
+        // hardlink targets are paths in archive, not relative to link
+        if entry_type.is_hard_link() {
+            let target = entry.link_name()?.ok_or_else(|| escapes(&archive_path))?;
+            let target = package_path(&target).ok_or_else(|| escapes(&archive_path))?;
+            let real_target = fs::canonicalize(dep_dir.join(target))?;
+
+            if !real_target.starts_with(&real_dep_dir) {
+                return Err(escapes(&archive_path));
+            }
+
+            retry(&entry_path, || fs::hard_link(&real_target, &entry_path))?;
+            continue;
+        }
+
+        let header_mode = entry.header().mode().unwrap_or(0o644);
+
+        if entry_type.is_file() {
+            // content can be read from archive only once, so only creating file is retried,
+            // then entry is streamed into it, its size doesn't matter
+            let mut file = retry(&entry_path, || File::create(&entry_path))?;
+
+            io::copy(&mut entry, &mut file)?;
+        } else {
+            retry(&entry_path, || fs::create_dir_all(&entry_path))?;
+        }
+
+        retry(&entry_path, || {
+            permissions::normalize_extracted(&entry_path, header_mode, entry_type.is_dir())
+        })?;
+    }
+
+    return Ok(());
+}
+
+/// path of tarball entry without root folder, `None` for absolute paths and paths
+/// with `..`
+fn package_path(archive_path: &Path) -> Option<PathBuf> {
+    let mut package_path = PathBuf::new();
+    let components = archive_path
+        .components()
+        .filter(|component| *component != Component::CurDir);
+
+    for (index, component) in components.enumerate() {
+        match component {
+            Component::Normal(_) if index == 0 => {}
+            Component::Normal(part) => package_path.push(part),
+            _ => return None,
+        }
+    }
+
+    return Some(package_path);
+}
+
+/// resolves `.` and `..` of path without touching file system, targets of symlinks
+/// don't have to exist yet
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+
+    return normalized;
+}
+
+fn escapes(archive_path: &Path) -> io::Error {
+    return io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "tarball entry {} escapes package dir",
+            archive_path.display()
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{write::GzEncoder, Compression};
+    use std::{env, process};
+    use tar::{Builder, EntryType, Header};
+
+    use super::*;
+
+    fn tarball(entries: &[(&str, EntryType, &str)]) -> Vec<u8> {
+        let mut builder = Builder::new(GzEncoder::new(vec![], Compression::default()));
+
+        for (path, entry_type, content) in entries {
+            let mut header = Header::new_gnu();
+
+            header.set_entry_type(*entry_type);
+            header.set_mode(0o644);
+
+            if *entry_type == EntryType::Symlink {
+                header.set_size(0);
+                builder.append_link(&mut header, path, content).unwrap();
+            } else {
+                header.set_size(content.len() as u64);
+                builder
+                    .append_data(&mut header, path, content.as_bytes())
+                    .unwrap();
+            }
+        }
+
+        return builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    fn extract(name: &str, entries: &[(&str, EntryType, &str)]) -> (PathBuf, io::Result<()>) {
+        let dir = env::temp_dir().join(format!("razee-extract-{}-{name}", process::id()));
+        let dep_dir = dir.join("node_modules/pad");
+        let _ = fs::remove_dir_all(&dir);
+
+        let extracted = extract_tarball(&tarball(entries)[..], &dep_dir.to_string_lossy());
+
+        return (dir, extracted);
+    }
+
+    #[test]
+    fn strips_root_folder() {
+        assert_eq!(
+            package_path(Path::new("package/lib/index.js")),
+            Some(PathBuf::from("lib/index.js"))
+        );
+        assert_eq!(
+            package_path(Path::new("./estree/index.d.ts")),
+            Some(PathBuf::from("index.d.ts"))
+        );
+        assert_eq!(package_path(Path::new("package/../../evil")), None);
+        assert_eq!(package_path(Path::new("/etc/passwd")), None);
+
+        let (dir, extracted) = extract(
+            "package",
+            &[("package/package.json", EntryType::Regular, "{}")],
+        );
+
+        extracted.unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("node_modules/pad/package.json")).unwrap(),
+            "{}"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_symlinks_out_of_package() {
+        let (dir, extracted) = extract(
+            "symlink",
+            &[
+                ("package/inside", EntryType::Symlink, "lib/index.js"),
+                ("package/escape", EntryType::Symlink, "../../.."),
+            ],
+        );
+
+        assert_eq!(extracted.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(fs::symlink_metadata(dir.join("node_modules/pad/inside")).is_ok());
+        assert!(fs::symlink_metadata(dir.join("node_modules/pad/escape")).is_err());
+        fs::remove_dir_all(dir).unwrap();
+
+        // link to parent is fine by itself, but nothing can be written through it
+        let (dir, extracted) = extract(
+            "through-symlink",
+            &[
+                ("package/lib/up", EntryType::Symlink, ".."),
+                ("package/lib/up/up", EntryType::Symlink, ".."),
+            ],
+        );
+
+        assert_eq!(extracted.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+use std::{io, path::Path, thread, time::Duration};
+
+const ATTEMPTS: u32 = 5;
+const FIRST_BACKOFF: Duration = Duration::from_millis(20);
+
+/// reruns filesystem operation on errors that usually go away by themselves (busy files,
+/// files held by antivirus on windows), when attempts are exhausted error names the path
+pub(crate) fn retry<T>(path: &Path, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut backoff = FIRST_BACKOFF;
+
+    for attempt in 1..=ATTEMPTS {
+        match operation() {
+            Ok(result) => return Ok(result),
+            Err(error) if is_transient(&error) && attempt < ATTEMPTS => {
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            Err(error) => {
+                let attempts = if is_transient(&error) {
+                    format!(" (after {ATTEMPTS} attempts)")
+                } else {
+                    String::new()
+                };
+
+                return Err(io::Error::new(
+                    error.kind(),
+                    format!("{}: {error}{attempts}", path.display()),
+                ));
+            }
+        }
+    }
+
+    unreachable!("last attempt always returns");
+}
+
+fn is_transient(error: &io::Error) -> bool {
+    if matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+    ) {
+        return true;
+    }
+
+    let Some(code) = error.raw_os_error() else {
+        return false;
+    };
+
+    #[cfg(unix)]
+    return code == libc::EBUSY || code == libc::EAGAIN || code == libc::ETXTBSY;
+
+    // access denied, sharing and lock violations, usually antivirus or indexer holding file
+    #[cfg(windows)]
+    return code == 5 || code == 32 || code == 33;
+
+    #[cfg(not(any(unix, windows)))]
+    return false;
+}
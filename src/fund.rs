@@ -0,0 +1,88 @@
+use serde::Deserialize;
+use std::{env, fs, io::BufReader, path::Path};
+
+use crate::{PackageFunding, ResolutionGraph, NODE_MODULES};
+
+#[derive(Debug, Deserialize)]
+struct FundedPackage {
+    name: String,
+    version: String,
+    funding: Option<PackageFunding>,
+}
+
+/// `N packages are looking for funding` line for install output, `None` when nobody
+/// is looking for funding or summary is disabled with `RAZEE_FUND=false`
+pub fn summary(graph: &ResolutionGraph) -> Option<String> {
+    if env::var("RAZEE_FUND").is_ok_and(|fund| fund == "false" || fund == "0") {
+        return None;
+    }
+
+    let funded = graph
+        .packages
+        .values()
+        .filter(|package| package.funding.is_some())
+        .count();
+
+    return match funded {
+        0 => None,
+        1 => Some("1 package is looking for funding — run razee fund".to_string()),
+        _ => Some(format!(
+            "{funded} packages are looking for funding — run razee fund"
+        )),
+    };
+}
+
+/// `razee fund`, lists funding urls of installed packages
+pub fn fund() {
+    let mut packages = vec![];
+
+    collect_funded(Path::new(NODE_MODULES), &mut packages);
+
+    if packages.is_empty() {
+        println!("no installed packages are looking for funding");
+        return;
+    }
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for package in packages {
+        let Some(funding) = &package.funding else {
+            continue;
+        };
+
+        println!("{}@{}", package.name, package.version);
+
+        for url in funding.urls() {
+            println!("  {url}");
+        }
+    }
+}
+
+fn collect_funded(dir: &Path, packages: &mut Vec<FundedPackage>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        if file_name.starts_with(".") {
+            continue;
+        }
+
+        if file_name.starts_with("@") {
+            collect_funded(&entry.path(), packages);
+            continue;
+        }
+
+        let Ok(manifest) = fs::File::open(entry.path().join("package.json")) else {
+            continue;
+        };
+
+        if let Ok(package) = serde_json::from_reader::<_, FundedPackage>(BufReader::new(manifest)) {
+            if package.funding.is_some() {
+                packages.push(package);
+            }
+        }
+    }
+}
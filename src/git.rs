@@ -0,0 +1,245 @@
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use crate::{manifest::PACKAGE_JSON, publish, registry, Dependency};
+
+/// `<prefix>user/repo` shorthands and urls of their repos
+const HOSTS: [(&str, &str); 3] = [
+    ("github:", "https://github.com/"),
+    ("gitlab:", "https://gitlab.com/"),
+    ("bitbucket:", "https://bitbucket.org/"),
+];
+
+/// `git+https://host/repo.git#ref`, `git+ssh://...`, `git://...`, `github:user/repo#ref`
+/// (also gitlab and bitbucket) or plain `user/repo`, that npm takes for github repo
+pub(crate) fn is_git(spec: &str) -> bool {
+    return spec.starts_with("git+")
+        || spec.starts_with("git://")
+        || HOSTS.iter().any(|(prefix, _)| spec.starts_with(prefix))
+        || is_github_shorthand(spec);
+}
+
+fn is_github_shorthand(spec: &str) -> bool {
+    let repo = spec.split("#").next().unwrap_or_default();
+
+    return repo.split_once("/").is_some_and(|(user, name)| {
+        !user.is_empty()
+            && !name.is_empty()
+            && !name.contains("/")
+            && !repo.contains(":")
+            && !user.starts_with(['.', '@', '~'])
+    });
+}
+
+/// clone url of repo and committish (branch, tag or commit), that spec asks for
+fn parse(spec: &str) -> (String, Option<String>) {
+    let (repo, committish) = match spec.split_once("#") {
+        Some((repo, committish)) if !committish.is_empty() => (repo, Some(committish)),
+        _ => (spec.trim_end_matches("#"), None),
+    };
+
+    let url = match repo.strip_prefix("git+") {
+        Some(url) => url.to_string(),
+        None if repo.starts_with("git://") => repo.to_string(),
+        None => {
+            let (host, path) = HOSTS
+                .iter()
+                .find_map(|(prefix, host)| Some((*host, repo.strip_prefix(prefix)?)))
+                .unwrap_or(("https://github.com/", repo));
+
+            format!("{host}{}.git", path.trim_end_matches(".git"))
+        }
+    };
+
+    return (url, committish.map(String::from));
+}
+
+/// checks out requested commit of repo and reads its package.json, `dist.tarball` is
+/// `git+<url>#<commit>`, like npm locks it, so locked install gets the same commit
+pub(crate) fn read_dependency(name: &str, spec: &str) -> Dependency {
+    let (url, committish) = parse(spec);
+    let (dir, commit) = checkout(&url, committish.as_deref());
+    let mut manifest = read_manifest(&dir);
+
+    // package is installed under the name it was requested with
+    manifest["name"] = Value::from(name);
+
+    if manifest.get("version").and_then(Value::as_str).is_none() {
+        manifest["version"] = Value::from("0.0.0");
+    }
+
+    // packed on every machine, so there is no integrity to lock, commit pins the content
+    manifest["dist"] = json!({
+        "tarball": format!("git+{url}#{commit}"),
+        "integrity": "",
+    });
+
+    return serde_json::from_value(manifest)
+        .expect(format!("invalid {PACKAGE_JSON} of {spec}").as_str());
+}
+
+/// repo packed like `npm pack` would pack it, `tarball` is `dist.tarball` of git package
+pub(crate) fn tarball_file(tarball: &str) -> PathBuf {
+    let (url, commit) = parse(tarball);
+    let (dir, commit) = checkout(&url, commit.as_deref());
+    let tarball_path = dir.with_extension("tgz");
+
+    if tarball_path.is_file() {
+        return tarball_path;
+    }
+
+    let packed = publish::pack_dir(&dir, &read_manifest(&dir));
+    let temp_path = dir.with_extension(format!("tgz.{}.tmp", process::id()));
+
+    fs::write(&temp_path, packed)
+        .and_then(|_| fs::rename(&temp_path, &tarball_path))
+        .expect(format!("cannot pack {url}#{commit}").as_str());
+
+    return tarball_path;
+}
+
+/// clones repo and checks `committish` (default branch, when `None`) out, checkouts are
+/// kept in temp dir by commit, so locked commits are not cloned again
+fn checkout(url: &str, committish: Option<&str>) -> (PathBuf, String) {
+    registry::ensure_https(url, "git");
+
+    let repo_dir = checkouts_dir().join(hash(url));
+    let is_commit = committish.is_some_and(|committish| {
+        committish.len() == 40 && committish.chars().all(|char| char.is_ascii_hexdigit())
+    });
+
+    if let Some(commit) = committish.filter(|_| is_commit) {
+        let dir = repo_dir.join(commit);
+
+        if dir.join(PACKAGE_JSON).is_file() {
+            return (dir, commit.to_string());
+        }
+    }
+
+    let clone_dir = repo_dir.join(format!("clone-{}", process::id()));
+
+    let _ = fs::remove_dir_all(&clone_dir);
+    fs::create_dir_all(&repo_dir).expect(format!("cannot create dir for {url}").as_str());
+
+    // `--` keeps url, that starts with `-`, from being taken for an option
+    git(
+        &repo_dir,
+        &["clone", "--quiet", "--", url, &clone_dir.to_string_lossy()],
+    );
+
+    if let Some(committish) = committish {
+        let commit = resolve_committish(&clone_dir, url, committish);
+
+        git(&clone_dir, &["checkout", "--quiet", &commit]);
+    }
+
+    let commit = git(&clone_dir, &["rev-parse", "HEAD"]);
+    let dir = repo_dir.join(&commit);
+
+    if dir.join(PACKAGE_JSON).is_file() {
+        let _ = fs::remove_dir_all(&clone_dir);
+    } else {
+        let _ = fs::remove_dir_all(&dir);
+
+        fs::rename(&clone_dir, &dir).expect(format!("cannot check out {url}").as_str());
+    }
+
+    return (dir, commit);
+}
+
+/// commit of branch, tag or commit, that spec asks for, committish is only ever given to
+/// git as revision, so it can't pass options to it
+fn resolve_committish(clone_dir: &Path, url: &str, committish: &str) -> String {
+    if committish.starts_with("-") {
+        panic!("invalid committish {committish} of {url}");
+    }
+
+    // branches, other than default one, are only cloned as remote ones
+    return [committish.to_string(), format!("origin/{committish}")]
+        .iter()
+        .find_map(|revision| {
+            let revision = format!("{revision}^{{commit}}");
+
+            try_git(clone_dir, &["rev-parse", "--verify", "--quiet", &revision]).ok()
+        })
+        .expect(format!("{url} has no branch, tag or commit {committish}").as_str());
+}
+
+/// runs git in `dir`, returns its trimmed output
+fn git(dir: &Path, args: &[&str]) -> String {
+    return try_git(dir, args)
+        .unwrap_or_else(|error| panic!("git {} failed: {error}", args.join(" ")));
+}
+
+fn try_git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .expect("cannot run git, it is needed for git dependencies");
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+}
+
+fn read_manifest(dir: &Path) -> Value {
+    let manifest_path = dir.join(PACKAGE_JSON);
+    let content = fs::read_to_string(&manifest_path)
+        .expect(format!("git repo has no {PACKAGE_JSON}: {}", dir.display()).as_str());
+
+    return serde_json::from_str(&content)
+        .expect(format!("cannot parse {}", manifest_path.display()).as_str());
+}
+
+fn checkouts_dir() -> PathBuf {
+    return env::temp_dir().join("razee-git");
+}
+
+fn hash(url: &str) -> String {
+    return Sha256::digest(url.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_git_specs() {
+        assert_eq!(
+            parse("github:user/repo#v1.0.0"),
+            (
+                "https://github.com/user/repo.git".to_string(),
+                Some("v1.0.0".to_string())
+            )
+        );
+        assert_eq!(
+            parse("git+ssh://git@host.com/repo.git#abc"),
+            (
+                "ssh://git@host.com/repo.git".to_string(),
+                Some("abc".to_string())
+            )
+        );
+        assert_eq!(
+            parse("user/repo").0,
+            "https://github.com/user/repo.git".to_string()
+        );
+        assert!(is_git("user/repo#main"));
+        assert!(!is_git("^1.0.0"));
+        assert!(!is_git("./vendor/pkg"));
+        assert!(!is_git("@scope/pkg"));
+        assert!(!is_git("https://registry.npmjs.org/pkg/-/pkg-1.0.0.tgz"));
+    }
+}
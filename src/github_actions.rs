@@ -0,0 +1,70 @@
+use std::{any::Any, env, panic};
+
+/// whether razee runs as a step of GitHub Actions workflow
+pub fn is_enabled() -> bool {
+    return env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true");
+}
+
+/// starts collapsible group of log lines, no-op outside of GitHub Actions
+pub fn group(title: &str) {
+    if is_enabled() {
+        println!("::group::{}", escape(title));
+    }
+}
+
+pub fn end_group() {
+    if is_enabled() {
+        println!("::endgroup::");
+    }
+}
+
+/// warning annotation, shown in checks of pull request, or plain warning line on stderr
+/// otherwise, so it doesn't end up in piped output
+pub fn warning(message: &str) {
+    if is_enabled() {
+        println!("::warning::{}", escape(message));
+    } else {
+        eprintln!("warning: {message}");
+    }
+}
+
+/// error annotation, or plain error line on stderr outside of GitHub Actions
+pub fn error(message: &str) {
+    if is_enabled() {
+        println!("::error::{}", escape(message));
+    } else {
+        eprintln!("error: {message}");
+    }
+}
+
+/// razee fails by panicking, so panic messages are turned into error annotations
+pub fn install_panic_hook() {
+    if !is_enabled() {
+        return;
+    }
+
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        error(&panic_message(info.payload()));
+
+        default_hook(info);
+    }));
+}
+
+/// message `panic!` was called with
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    return payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "razee panicked".to_string());
+}
+
+/// workflow commands are line based, so new lines have to be encoded
+fn escape(message: &str) -> String {
+    return message
+        .replace("%", "%25")
+        .replace("\r", "%0D")
+        .replace("\n", "%0A");
+}
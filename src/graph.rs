@@ -0,0 +1,102 @@
+use serde_json::json;
+use std::collections::HashMap;
+
+use crate::ResolutionGraph;
+
+pub enum GraphFormat {
+    Dot,
+    Json,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn parse(format: &str) -> Option<GraphFormat> {
+        match format {
+            "dot" => Some(GraphFormat::Dot),
+            "json" => Some(GraphFormat::Json),
+            "mermaid" => Some(GraphFormat::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+pub fn export(graph: &ResolutionGraph, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => to_dot(graph),
+        GraphFormat::Json => to_json(graph),
+        GraphFormat::Mermaid => to_mermaid(graph),
+    }
+}
+
+/// `name@version`, root package has no resolved version
+fn node_label(graph: &ResolutionGraph, name: &str) -> String {
+    match graph.get(name) {
+        Some(package) => format!("{}@{}", package.name, package.version),
+        None => name.to_string(),
+    }
+}
+
+fn to_dot(graph: &ResolutionGraph) -> String {
+    let mut dot = String::from("digraph dependencies {\n");
+
+    for edge in graph.edges.iter() {
+        let style = if edge.deduped { ", style=dashed" } else { "" };
+
+        dot.push_str(&format!(
+            "  \"{}\" -> \"{}\" [label=\"{}\"{style}];\n",
+            node_label(graph, &edge.from),
+            node_label(graph, &edge.to),
+            edge.range.replace("\"", "\\\""),
+        ));
+    }
+
+    dot.push_str("}\n");
+
+    return dot;
+}
+
+fn to_json(graph: &ResolutionGraph) -> String {
+    let packages: HashMap<&String, _> = graph
+        .packages
+        .iter()
+        .map(|(name, package)| {
+            let value = json!({
+                "version": package.version,
+                "resolved": package.dist.tarball,
+                "integrity": package.dist.integrity,
+            });
+
+            (name, value)
+        })
+        .collect();
+
+    let document = json!({
+        "root": graph.root,
+        "packages": packages,
+        "edges": graph.edges,
+    });
+
+    return serde_json::to_string_pretty(&document).unwrap() + "\n";
+}
+
+fn to_mermaid(graph: &ResolutionGraph) -> String {
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut mermaid = String::from("graph TD\n");
+
+    for edge in graph.edges.iter() {
+        let mut node = |name: &str| {
+            let next_id = ids.len();
+            let id = *ids.entry(name.to_string()).or_insert(next_id);
+
+            format!("n{id}[\"{}\"]", node_label(graph, name))
+        };
+
+        let from = node(&edge.from);
+        let to = node(&edge.to);
+        let arrow = if edge.deduped { "-.->" } else { "-->" };
+
+        mermaid.push_str(&format!("  {from} {arrow}|{}| {to}\n", edge.range));
+    }
+
+    return mermaid;
+}
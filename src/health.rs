@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::{http_client::HttpClient, registry::Registry};
+
+const NPM_REGISTRY_HOST: &str = "registry.npmjs.org";
+const DOWNLOADS_API: &str = "https://api.npmjs.org/downloads/point/last-week";
+const STALE_AFTER_DAYS: i64 = 365;
+const FEW_DOWNLOADS: u64 = 1000;
+
+/// quick signals about package, shown before it is added to the project
+#[derive(Debug)]
+pub struct HealthReport {
+    pub name: String,
+    pub latest_version: Option<String>,
+    /// days since latest release
+    pub latest_release_age: Option<i64>,
+    /// only known for packages from npm registry
+    pub weekly_downloads: Option<u64>,
+    pub maintainers: usize,
+    pub repository: Option<String>,
+}
+
+impl HealthReport {
+    /// everything that looks suspicious about the package
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+
+        match self.latest_release_age {
+            Some(age) if age > STALE_AFTER_DAYS => {
+                warnings.push(format!("last release was {age} days ago"))
+            }
+            None => warnings.push("release date is unknown".to_string()),
+            _ => {}
+        }
+
+        if let Some(downloads) = self.weekly_downloads.filter(|d| *d < FEW_DOWNLOADS) {
+            warnings.push(format!("only {downloads} downloads last week"));
+        }
+
+        if self.maintainers == 0 {
+            warnings.push("package has no maintainers".to_string());
+        }
+
+        if self.repository.is_none() {
+            warnings.push("package has no repository".to_string());
+        }
+
+        return warnings;
+    }
+
+    pub fn print(&self) {
+        let version = self.latest_version.as_deref().unwrap_or("?");
+
+        println!("{}@{version}", self.name);
+
+        if let Some(age) = self.latest_release_age {
+            println!("  released: {age} days ago");
+        }
+
+        if let Some(downloads) = self.weekly_downloads {
+            println!("  weekly downloads: {downloads}");
+        }
+
+        println!("  maintainers: {}", self.maintainers);
+        println!(
+            "  repository: {}",
+            self.repository.as_deref().unwrap_or("none")
+        );
+
+        for warning in self.warnings() {
+            println!("  warning: {warning}");
+        }
+    }
+}
+
+pub(crate) async fn check(packument: &Value, client: &HttpClient) -> HealthReport {
+    let name = packument
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let latest_version = packument
+        .pointer("/dist-tags/latest")
+        .and_then(Value::as_str)
+        .map(String::from);
+
+    let latest_release_age = latest_version
+        .as_ref()
+        .and_then(|version| packument.get("time")?.get(version)?.as_str())
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|released_at| (Utc::now() - released_at.with_timezone(&Utc)).num_days());
+
+    let maintainers = packument
+        .get("maintainers")
+        .and_then(Value::as_array)
+        .map(Vec::len)
+        .unwrap_or(0);
+
+    let repository = match packument.get("repository") {
+        Some(Value::String(url)) => Some(url.clone()),
+        Some(repository) => repository
+            .get("url")
+            .and_then(Value::as_str)
+            .map(String::from),
+        None => None,
+    };
+
+    // download counts only exist for npm registry
+    let weekly_downloads = match client.registry() {
+        Registry::Remote(url) if url.ends_with(NPM_REGISTRY_HOST) => client
+            .fetch_json(&format!("{DOWNLOADS_API}/{name}"))
+            .await
+            .and_then(|downloads| downloads.get("downloads")?.as_u64()),
+        _ => None,
+    };
+
+    return HealthReport {
+        name,
+        latest_version,
+        latest_release_age,
+        weekly_downloads,
+        maintainers,
+        repository,
+    };
+}
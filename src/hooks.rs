@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use crate::{Dep, Dependency};
+
+/// returning `Err` from any hook aborts the install with given reason
+pub type HookResult = Result<(), String>;
+
+/// install events that can be observed (telemetry) or rejected (policy checks),
+/// every hook does nothing by default, so only needed ones can be implemented
+pub trait InstallHook {
+    fn before_resolve(&self, _dep: &Dep) -> HookResult {
+        return Ok(());
+    }
+
+    fn after_resolve(&self, _dep: &Dep, _resolved: &Dependency) -> HookResult {
+        return Ok(());
+    }
+
+    fn before_fetch(&self, _package: &Dependency) -> HookResult {
+        return Ok(());
+    }
+
+    /// `tarball` is downloaded file, it is removed after install
+    fn after_fetch(&self, _package: &Dependency, _tarball: &Path) -> HookResult {
+        return Ok(());
+    }
+
+    fn before_extract(&self, _package: &Dependency, _dir: &Path) -> HookResult {
+        return Ok(());
+    }
+
+    fn after_extract(&self, _package: &Dependency, _dir: &Path) -> HookResult {
+        return Ok(());
+    }
+
+    fn before_script(&self, _package: &Dependency, _script_name: &str) -> HookResult {
+        return Ok(());
+    }
+
+    fn after_script(
+        &self,
+        _package: &Dependency,
+        _script_name: &str,
+        _success: bool,
+    ) -> HookResult {
+        return Ok(());
+    }
+}
+
+#[derive(Default)]
+pub struct Hooks {
+    hooks: Vec<Box<dyn InstallHook>>,
+}
+
+impl Hooks {
+    pub fn add(&mut self, hook: Box<dyn InstallHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn before_resolve(&self, dep: &Dep) {
+        self.run(&dep.name, |hook| hook.before_resolve(dep));
+    }
+
+    pub fn after_resolve(&self, dep: &Dep, resolved: &Dependency) {
+        self.run(&dep.name, |hook| hook.after_resolve(dep, resolved));
+    }
+
+    pub fn before_fetch(&self, package: &Dependency) {
+        self.run(&package.name, |hook| hook.before_fetch(package));
+    }
+
+    pub fn after_fetch(&self, package: &Dependency, tarball: &Path) {
+        self.run(&package.name, |hook| hook.after_fetch(package, tarball));
+    }
+
+    pub fn before_extract(&self, package: &Dependency, dir: &Path) {
+        self.run(&package.name, |hook| hook.before_extract(package, dir));
+    }
+
+    pub fn after_extract(&self, package: &Dependency, dir: &Path) {
+        self.run(&package.name, |hook| hook.after_extract(package, dir));
+    }
+
+    pub fn before_script(&self, package: &Dependency, script_name: &str) {
+        self.run(&package.name, |hook| {
+            hook.before_script(package, script_name)
+        });
+    }
+
+    pub fn after_script(&self, package: &Dependency, script_name: &str, success: bool) {
+        self.run(&package.name, |hook| {
+            hook.after_script(package, script_name, success)
+        });
+    }
+
+    fn run(&self, package_name: &str, call: impl Fn(&dyn InstallHook) -> HookResult) {
+        for hook in self.hooks.iter() {
+            if let Err(reason) = call(hook.as_ref()) {
+                panic!("install of {package_name} was rejected by hook: {reason}");
+            }
+        }
+    }
+}
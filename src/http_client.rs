@@ -1,54 +1,214 @@
 use bytes::Bytes;
 use elsa::FrozenMap;
 use node_semver::Version;
-use reqwest::StatusCode;
-use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest::{header, Method, NoProxy, Proxy, Response, StatusCode, Url};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, RequestBuilder};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use serde_json::Value;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::StreamExt;
+use sha2::{Digest, Sha256, Sha512};
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{Mutex as AsyncMutex, OnceCell, OwnedMutexGuard, Semaphore, SemaphorePermit},
+};
 
-use crate::{Dep, Dependency, DependencyDist, RegistryPackage};
+use crate::{
+    cancel, git, local_dir, local_tarball,
+    metadata_cache::MetadataCache,
+    npmrc::Npmrc,
+    registry::{self, Registry},
+    signatures::RegistryKey,
+    store::{self, Store},
+    Dep, Dependency, DependencyDist, RegistryPackage,
+};
 
-const REGISTRY_URL: &str = "http://registry.npmjs.org";
+/// abbreviated ("corgi") packument, only with fields needed for install, full one of
+/// popular packages can take megabytes
+const ABBREVIATED_PACKUMENT: &str =
+    "application/vnd.npm.install-v1+json; q=1.0, application/json; q=0.8, */*";
+const FULL_PACKUMENT: &str = "application/json";
+/// same as pnpm's default `network-concurrency`
+const NETWORK_CONCURRENCY: usize = 16;
+
+/// every client has its own dir for tarballs
+static CLIENTS: AtomicUsize = AtomicUsize::new(0);
 
 pub struct HttpClient {
     client: ClientWithMiddleware,
+    registry: Registry,
+    /// `@scope` -> registry of its packages
+    scoped_registries: HashMap<String, Registry>,
+    /// auth tokens of registries
+    npmrc: &'static Npmrc,
+    store: Option<Box<dyn Store>>,
+    metadata_cache: Option<MetadataCache>,
     tarball_cache: FrozenMap<String, Box<Bytes>>,
+    /// url -> temp file of tarballs, that were fetched for install
+    tarball_files: FrozenMap<String, Box<PathBuf>>,
+    tarball_dir: PathBuf,
     package_cache: FrozenMap<String, Box<RegistryPackage>>,
     dependency_cache: FrozenMap<String, Box<Dependency>>,
+    in_flight: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    /// limits requests at once, so big installs don't run out of file descriptors or
+    /// trip rate limits of registry
+    network_limit: Semaphore,
+    registry_keys: OnceCell<Vec<RegistryKey>>,
+}
+
+impl fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f
+            .debug_struct("HttpClient")
+            .field("registry", &self.registry)
+            .finish_non_exhaustive();
+    }
+}
+
+impl Drop for HttpClient {
+    fn drop(&mut self) {
+        if self.tarball_dir.exists() {
+            let _ = fs::remove_dir_all(&self.tarball_dir);
+        }
+
+        cancel::finish(&self.tarball_dir);
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        return HttpClient::new();
+    }
 }
 
 impl HttpClient {
     pub fn new() -> HttpClient {
+        return HttpClient::with_registry(Registry::from_env());
+    }
+
+    pub fn with_registry(registry: Registry) -> HttpClient {
+        registry.ensure_secure();
+
+        let scoped_registries = registry::scoped_from_env();
+        let npmrc = Npmrc::load();
+
+        for scoped in scoped_registries.values() {
+            scoped.ensure_secure();
+        }
+
         let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
-        let client = ClientBuilder::new(reqwest::Client::new())
+        let client = ClientBuilder::new(build_client(npmrc))
             .with(RetryTransientMiddleware::new_with_policy(retry_policy))
             .build();
 
         // let client = reqwest::Client::new();
 
+        let tarball_dir = env::temp_dir().join(format!(
+            "razee-tarballs-{}-{}",
+            process::id(),
+            CLIENTS.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        // cancelled install exits without dropping client
+        cancel::track(&tarball_dir);
+
         return HttpClient {
             client,
+            registry,
+            scoped_registries,
+            npmrc,
+            store: store::from_env(),
+            metadata_cache: MetadataCache::from_env(),
             tarball_cache: FrozenMap::new(),
+            tarball_files: FrozenMap::new(),
+            tarball_dir,
             package_cache: FrozenMap::new(),
             dependency_cache: FrozenMap::new(),
+            in_flight: Mutex::new(HashMap::new()),
+            network_limit: Semaphore::new(NETWORK_CONCURRENCY),
+            registry_keys: OnceCell::new(),
         };
     }
 
+    /// packages of `scope` (e.g. `@mycorp`) come from `registry`, not the default one
+    pub fn scoped_registry(mut self, scope: &str, registry: Registry) -> HttpClient {
+        registry.ensure_secure();
+        self.scoped_registries.insert(scope.to_string(), registry);
+
+        return self;
+    }
+
+    /// tarballs are looked up in `store` first, and uploaded there after download from registry
+    pub fn with_store(mut self, store: Box<dyn Store>) -> HttpClient {
+        self.store = Some(store);
+
+        return self;
+    }
+
+    /// how many requests can be made at once, defaults to 16
+    pub fn network_concurrency(mut self, network_concurrency: usize) -> HttpClient {
+        self.network_limit = Semaphore::new(network_concurrency.max(1));
+
+        return self;
+    }
+
+    /// waits until tarballs are uploaded to store
+    pub(crate) async fn flush_store(&self) {
+        if let Some(store) = &self.store {
+            store.flush().await;
+        }
+    }
+
     /// fetches specific package version for gathering tarball url and other dependencies
     pub(crate) async fn fetch_dependency(&self, dep_name: &String, dep_version: &Version) -> &Dependency {
-        let url = format!("{REGISTRY_URL}/{}/{}", dep_name, dep_version);
+        let url = format!("{}/{}", self.package_url(dep_name), dep_version);
 
         if let Some(dependency) = self.dependency_cache.get(&url) {
             return dependency;
         }
 
+        if let Registry::Fixture(dir) = self.registry_for(dep_name) {
+            let dependency = registry::read_fixture_dependency(dir, dep_name, &dep_version.to_string());
+
+            return self.dependency_cache.insert(url, Box::new(dependency));
+        }
+
+        let _in_flight = self.in_flight(&url).await;
+
+        if let Some(dependency) = self.dependency_cache.get(&url) {
+            return dependency;
+        }
+
+        // abbreviated packument, that version was resolved from, has whole version document
+        let packaged = self
+            .package_cache
+            .get(&self.package_url(dep_name))
+            .and_then(|package| package.versions.get(&dep_version.to_string()))
+            .and_then(|document| serde_json::from_value::<Dependency>(document.clone()).ok());
+
+        if let Some(dependency) = packaged {
+            return self.dependency_cache.insert(url, Box::new(dependency));
+        }
+
+        let _permit = self.network_permit().await;
         let dependency_res = self
-            .client
-            .get(&url)
-            .header("User-Agent", "Razee (Node Package Manger in Rust)")
+            .request(Method::GET, &url)
             .send()
             .await
             .expect("probably no internet");
 
+        ensure_authorized(&dependency_res, &url);
+
         let dependency;
 
         match dependency_res.status() {
@@ -57,12 +217,10 @@ impl HttpClient {
             }
             _ => {
                 println!("{}:{}\n\n", dep_name, dep_version);
-                let latest_url = format!("{REGISTRY_URL}/{}/{}", dep_name, "latest");
+                let latest_url = format!("{}/{}", self.package_url(dep_name), "latest");
 
                 dependency = self
-                    .client
-                    .get(&latest_url)
-                    .header("User-Agent", "Razee (Node Package Manger in Rust)")
+                    .request(Method::GET, &latest_url)
                     .send()
                     .await
                     .expect("probably no internet")
@@ -79,22 +237,29 @@ impl HttpClient {
 
     /// fetches package info to resolve version
     pub(crate) async fn fetch_package(&self, dep: &Dep) -> &RegistryPackage {
-        let url = format!("{REGISTRY_URL}/{}", dep.name);
+        let url = self.package_url(&dep.name);
+
+        if let Some(package) = self.package_cache.get(&url) {
+            return package;
+        }
+
+        if let Registry::Fixture(dir) = self.registry_for(&dep.name) {
+            let package = registry::read_fixture_package(dir, &dep.name);
+
+            return self.package_cache.insert(url, Box::new(package));
+        }
+
+        let _in_flight = self.in_flight(&url).await;
 
         if let Some(package) = self.package_cache.get(&url) {
             return package;
         }
 
         let package: RegistryPackage = self
-            .client
-            .get(&url)
-            .header("User-Agent", "Razee (Node Package Manger in Rust)")
-            .send()
+            .fetch_cached(&url, ABBREVIATED_PACKUMENT)
             .await
-            .expect("probably no internet")
-            .json::<RegistryPackage>()
-            .await
-            .expect("cannot parse dependency");
+            .and_then(|body| serde_json::from_str(&body).ok())
+            .expect(format!("cannot fetch {} from registry", dep.name).as_str());
 
         return self
             .package_cache
@@ -107,18 +272,501 @@ impl HttpClient {
             return tarball;
         }
 
-        let tarball = self
-            .client
-            .get(&dist.tarball)
+        if local_dir::is_local_dir(&dist.tarball) {
+            let tarball_path = local_dir::tarball_file(&dist.tarball);
+            let tarball = fs::read(&tarball_path)
+                .expect(format!("cannot read {}", tarball_path.display()).as_str());
+
+            return self
+                .tarball_cache
+                .insert(dist.tarball.to_string(), Box::new(Bytes::from(tarball)));
+        }
+
+        if dist.tarball.starts_with("file:") {
+            let tarball = local_tarball::read(&dist.tarball);
+
+            return self
+                .tarball_cache
+                .insert(dist.tarball.to_string(), Box::new(tarball));
+        }
+
+        if git::is_git(&dist.tarball) {
+            let tarball_path = git::tarball_file(&dist.tarball);
+            let tarball = fs::read(&tarball_path)
+                .expect(format!("cannot read {}", tarball_path.display()).as_str());
+
+            return self
+                .tarball_cache
+                .insert(dist.tarball.to_string(), Box::new(Bytes::from(tarball)));
+        }
+
+        if let Registry::Fixture(dir) = self.tarball_registry(&dist.tarball) {
+            let tarball = registry::read_fixture_tarball(dir, &dist.tarball);
+
+            return self
+                .tarball_cache
+                .insert(dist.tarball.to_string(), Box::new(tarball));
+        }
+
+        let _in_flight = self.in_flight(&dist.tarball).await;
+
+        if let Some(tarball) = self.tarball_cache.get(&dist.tarball) {
+            return tarball;
+        }
+
+        let store = self.store.as_ref().filter(|_| !dist.integrity.is_empty());
+
+        let stored = match store {
+            Some(store) => store.get(&dist.integrity).await,
+            None => None,
+        };
+
+        if let Some(tarball) = stored {
+            return self
+                .tarball_cache
+                .insert(dist.tarball.to_string(), Box::new(tarball));
+        }
+
+        let tarball = self.download_tarball(&dist.tarball).await;
+
+        // broken downloads must not spread to other machines through store
+        if let Some(store) = store {
+            if local_tarball::integrity(&tarball) == dist.integrity {
+                store.put(&dist.integrity, tarball.clone());
+            }
+        }
+
+        return self
+            .tarball_cache
+            .insert(dist.tarball.to_string(), Box::new(tarball));
+    }
+
+    /// tarball for install, downloaded into temp file, so memory doesn't grow with
+    /// size of packages, files are kept (and reused) until client is dropped
+    pub(crate) async fn fetch_tarball_file(&self, dist: &DependencyDist) -> PathBuf {
+        // local dirs are packed, tarballs are read right where they are
+        if local_dir::is_local_dir(&dist.tarball) {
+            return local_dir::tarball_file(&dist.tarball);
+        }
+
+        if let Some(path) = dist.tarball.strip_prefix("file:") {
+            return PathBuf::from(path);
+        }
+
+        // git repos are packed in temp dir, where they are checked out
+        if git::is_git(&dist.tarball) {
+            return git::tarball_file(&dist.tarball);
+        }
+
+        let _in_flight = self.in_flight(&dist.tarball).await;
+
+        if let Some(path) = self.tarball_files.get(&dist.tarball) {
+            return path.clone();
+        }
+
+        let path = self.tarball_path(&dist.tarball);
+
+        // tarballs of one-off commands, that are already in memory
+        let tarball = match self.tarball_registry(&dist.tarball) {
+            Registry::Fixture(dir) => Some(registry::read_fixture_tarball(dir, &dist.tarball)),
+            Registry::Remote(_) => self.tarball_cache.get(&dist.tarball).cloned(),
+        };
+
+        let store = self.store.as_ref().filter(|_| !dist.integrity.is_empty());
+        let tarball = match (tarball, store) {
+            (None, Some(store)) => store.get(&dist.integrity).await,
+            (tarball, _) => tarball,
+        };
+
+        match tarball {
+            Some(tarball) => write_tarball(&path, &tarball),
+            None => {
+                let integrity = self.download_tarball_file(&dist.tarball, &path).await;
+
+                // broken downloads must not spread to other machines through store
+                if let Some(store) = store.filter(|_| integrity == dist.integrity) {
+                    let tarball = fs::read(&path)
+                        .expect(format!("cannot read {}", path.display()).as_str());
+
+                    store.put(&dist.integrity, Bytes::from(tarball));
+                }
+            }
+        }
+
+        return self
+            .tarball_files
+            .insert(dist.tarball.to_string(), Box::new(path))
+            .clone();
+    }
+
+    /// downloads tarball again, skipping stores, for when fetched one turned out to be
+    /// corrupted
+    pub(crate) async fn refetch_tarball_file(&self, dist: &DependencyDist) -> PathBuf {
+        if local_dir::is_local_dir(&dist.tarball) {
+            return local_dir::tarball_file(&dist.tarball);
+        }
+
+        if let Some(path) = dist.tarball.strip_prefix("file:") {
+            return PathBuf::from(path);
+        }
+
+        if git::is_git(&dist.tarball) {
+            return git::tarball_file(&dist.tarball);
+        }
+
+        let path = self.tarball_path(&dist.tarball);
+
+        match self.tarball_registry(&dist.tarball) {
+            Registry::Fixture(dir) => {
+                write_tarball(&path, &registry::read_fixture_tarball(dir, &dist.tarball))
+            }
+            Registry::Remote(_) => {
+                self.download_tarball_file(&dist.tarball, &path).await;
+            }
+        }
+
+        return path;
+    }
+
+    /// streams response body into `path`, returns integrity of what was downloaded
+    async fn download_tarball_file(&self, url: &str, path: &Path) -> String {
+        registry::ensure_https(url, "tarball");
+
+        let _permit = self.network_permit().await;
+
+        let response = self
+            .request(Method::GET, url)
+            .send()
+            .await
+            .expect(format!("cannot download {url}").as_str());
+
+        ensure_authorized(&response, url);
+
+        let response = response
+            .error_for_status()
+            .expect(format!("cannot download {url}").as_str());
+
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .expect(format!("cannot create {}", path.display()).as_str());
+        let mut hasher = Sha512::new();
+        let mut body = response.bytes_stream();
+
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.expect(format!("cannot download {url}").as_str());
+
+            hasher.update(&chunk);
+            file.write_all(&chunk)
+                .await
+                .expect(format!("cannot write {}", path.display()).as_str());
+        }
+
+        file.flush()
+            .await
+            .expect(format!("cannot write {}", path.display()).as_str());
+
+        return format!("sha512-{}", STANDARD.encode(hasher.finalize()));
+    }
+
+    /// temp file of tarball from `url`
+    fn tarball_path(&self, url: &str) -> PathBuf {
+        fs::create_dir_all(&self.tarball_dir)
+            .expect(format!("cannot create {}", self.tarball_dir.display()).as_str());
+
+        let hash: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        return self.tarball_dir.join(format!("{hash}.tgz"));
+    }
+
+    async fn download_tarball(&self, url: &str) -> Bytes {
+        registry::ensure_https(url, "tarball");
+
+        let _permit = self.network_permit().await;
+
+        let response = self
+            .request(Method::GET, url)
             .send()
             .await
-            .unwrap()
+            .expect(format!("cannot download {url}").as_str());
+
+        ensure_authorized(&response, url);
+
+        return response
+            .error_for_status()
+            .expect(format!("cannot download {url}").as_str())
             .bytes()
             .await
-            .unwrap();
+            .expect(format!("cannot download {url}").as_str());
+    }
+
+    /// whole packument as plain json, for commands that need more than versions
+    pub(crate) async fn fetch_packument(&self, package_name: &str) -> Value {
+        if let Registry::Fixture(dir) = self.registry_for(package_name) {
+            return registry::read_fixture_packument(dir, package_name);
+        }
+
+        let url = self.package_url(package_name);
+
+        return self
+            .fetch_cached(&url, FULL_PACKUMENT)
+            .await
+            .and_then(|body| serde_json::from_str(&body).ok())
+            .expect(format!("cannot fetch {package_name} from registry").as_str());
+    }
+
+    /// fetches registry document, that is revalidated against on-disk cache with
+    /// `If-None-Match`, so unchanged documents are not transferred again, `None` if
+    /// request failed
+    async fn fetch_cached(&self, url: &str, accept: &str) -> Option<String> {
+        // abbreviated and full documents have the same url
+        let cache_key = format!("{accept} {url}");
+        let _permit = self.network_permit().await;
+        let cached = self.metadata_cache.as_ref().and_then(|cache| cache.get(&cache_key));
+        let mut request = self
+            .request(Method::GET, url)
+            .header(header::ACCEPT, accept);
+
+        if let Some(cached) = &cached {
+            request = request.header(header::IF_NONE_MATCH, &cached.etag);
+        }
+
+        let response = request.send().await.expect("probably no internet");
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return cached.map(|cached| cached.body);
+        }
+
+        ensure_authorized(&response, url);
+
+        if !response.status().is_success() {
+            return None;
+        }
+
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_string());
+        let body = response.text().await.ok()?;
+
+        if let (Some(cache), Some(etag)) = (&self.metadata_cache, etag) {
+            cache.put(&cache_key, &etag, &body);
+        }
+
+        return Some(body);
+    }
+
+    /// fetches json from any url, `None` if request or parsing failed
+    pub(crate) async fn fetch_json(&self, url: &str) -> Option<Value> {
+        let _permit = self.network_permit().await;
+        let response = self
+            .request(Method::GET, url)
+            .send()
+            .await
+            .ok()?;
+
+        if !response.status().is_success() {
+            return None;
+        }
+
+        return response.json().await.ok();
+    }
+
+    /// json of registry endpoint, fixture registry answers with `<dir>/<path>.json`,
+    /// `None` if there is no such document
+    pub(crate) async fn fetch_registry_json(&self, path: &str) -> Option<Value> {
+        if let Registry::Fixture(dir) = &self.registry {
+            return dir
+                .join(format!("{path}.json"))
+                .exists()
+                .then(|| registry::read_fixture_response(dir, path));
+        }
 
         return self
-            .tarball_cache
-            .insert(dist.tarball.to_string(), Box::new(tarball));
+            .fetch_json(&format!("{}/{path}", self.registry_url()))
+            .await;
     }
+
+    /// keys, that registry signs packages with, fetched once, empty when registry
+    /// doesn't sign packages
+    pub(crate) async fn fetch_registry_keys(&self) -> &[RegistryKey] {
+        return self
+            .registry_keys
+            .get_or_init(|| async {
+                return self
+                    .fetch_registry_json("-/npm/v1/keys")
+                    .await
+                    .and_then(|mut keys| serde_json::from_value(keys.get_mut("keys")?.take()).ok())
+                    .unwrap_or_default();
+            })
+            .await;
+    }
+
+    /// posts json to registry endpoint, fixture registry answers with `<dir>/<path>.json`
+    pub(crate) async fn post_registry_json(&self, path: &str, body: &Value) -> Value {
+        if let Registry::Fixture(dir) = &self.registry {
+            return registry::read_fixture_response(dir, path);
+        }
+
+        let url = format!("{}/{path}", self.registry_url());
+
+        let response = self
+            .request(Method::POST, &url)
+            .json(body)
+            .send()
+            .await
+            .expect("probably no internet");
+
+        ensure_authorized(&response, &url);
+
+        return response
+            .error_for_status()
+            .expect(format!("registry rejected {path}").as_str())
+            .json()
+            .await
+            .expect(format!("cannot parse response of {path}").as_str());
+    }
+
+    /// held for the whole request, including reading of response body
+    async fn network_permit(&self) -> SemaphorePermit<'_> {
+        return self
+            .network_limit
+            .acquire()
+            .await
+            .expect("network limit is never closed");
+    }
+
+    /// held while `url` is fetched, so concurrent requests of the same url (e.g. 50
+    /// dependents of tslib) wait for the first one and take its response from cache
+    async fn in_flight(&self, url: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .in_flight
+            .lock()
+            .expect("in flight requests are poisoned")
+            .entry(url.to_string())
+            .or_default()
+            .clone();
+
+        return lock.lock_owned().await;
+    }
+
+    /// token (if there is one) is sent only to registry hosts, not to every tarball url,
+    /// token of .npmrc for the url wins over the one from env
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("User-Agent", "Razee (Node Package Manger in Rust)");
+
+        let token = self.npmrc.auth_token_for(url).map(String::from).or_else(|| {
+            [&self.registry]
+                .into_iter()
+                .chain(self.scoped_registries.values())
+                .find_map(|registry| registry.auth_token_for(url))
+        });
+
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        return request;
+    }
+
+    pub(crate) fn registry(&self) -> &Registry {
+        return &self.registry;
+    }
+
+    fn registry_url(&self) -> &str {
+        return registry_url(&self.registry);
+    }
+
+    /// registry of package scope, default one for unscoped packages
+    fn registry_for(&self, package_name: &str) -> &Registry {
+        return registry::scope_of(package_name)
+            .and_then(|scope| self.scoped_registries.get(scope))
+            .unwrap_or(&self.registry);
+    }
+
+    /// url of package documents, in registry of its scope
+    fn package_url(&self, package_name: &str) -> String {
+        return format!("{}/{package_name}", registry_url(self.registry_for(package_name)));
+    }
+
+    /// registry, that has tarball under its url, or that has packages of tarball's scope,
+    /// when it is a local mirror, which tarball urls don't point to
+    fn tarball_registry(&self, tarball_url: &str) -> &Registry {
+        return self
+            .scoped_registries
+            .iter()
+            .find(|(scope, registry)| match registry {
+                Registry::Remote(url) => tarball_url.starts_with(&format!("{url}/")),
+                Registry::Fixture(_) => {
+                    registry::scope_of(registry::fixture_tarball_path(tarball_url))
+                        == Some(scope.as_str())
+                }
+            })
+            .map_or(&self.registry, |(_, registry)| registry);
+    }
+}
+
+fn registry_url(registry: &Registry) -> &str {
+    match registry {
+        Registry::Remote(url) => url,
+        Registry::Fixture(_) => "fixture:",
+    }
+}
+
+/// registry answers with error document (or html page of proxy) then, which can't be
+/// parsed as package
+fn ensure_authorized(response: &Response, url: &str) {
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|url| match url.port() {
+            Some(port) => Some(format!("{}:{port}", url.host_str()?)),
+            None => Some(url.host_str()?.to_string()),
+        })
+        .unwrap_or_default();
+
+    match response.status() {
+        StatusCode::UNAUTHORIZED => panic!(
+            "cannot fetch {url}: not logged in (E401), set //{host}/:_authToken in .npmrc, or \
+            RAZEE_AUTH_TOKEN"
+        ),
+        StatusCode::FORBIDDEN => panic!(
+            "cannot fetch {url}: permission denied (E403), token has no access to the package"
+        ),
+        _ => {}
+    }
+}
+
+/// `https-proxy` (or `proxy`) of .npmrc for https requests, `proxy` for plaintext ones,
+/// hosts of `noproxy` are requested directly, `strict-ssl=false` accepts any certificate
+fn build_client(npmrc: &Npmrc) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder();
+    let no_proxy = npmrc.get("noproxy").and_then(NoProxy::from_string);
+
+    if let Some(proxy) = npmrc.get("https-proxy").or(npmrc.get("proxy")) {
+        let proxy = Proxy::https(proxy).expect(format!("invalid https-proxy {proxy}").as_str());
+
+        builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+    }
+
+    if let Some(proxy) = npmrc.get("proxy") {
+        let proxy = Proxy::http(proxy).expect(format!("invalid proxy {proxy}").as_str());
+
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+
+    if npmrc.get("strict-ssl") == Some("false") {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    return builder.build().expect("cannot create http client");
+}
+
+fn write_tarball(path: &Path, tarball: &Bytes) {
+    fs::write(path, tarball).expect(format!("cannot write {}", path.display()).as_str());
 }
@@ -0,0 +1,200 @@
+use node_semver::{Range, Version};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io::BufReader, path::Path};
+
+use crate::{
+    lockfile::{self, LockedPackage, Lockfile},
+    DependenciesMap, Manifest, PackageBin, PackageFunding, NODE_MODULES,
+};
+
+mod bun;
+mod pnpm;
+mod yarn;
+
+/// versions pinned by other package manager's lockfile, used to reproduce
+/// their tree on first install
+pub type ResolutionSeed = HashMap<String, Vec<SeededVersion>>;
+
+#[derive(Debug, Clone)]
+pub struct SeededVersion {
+    pub version: Version,
+    /// tarball url from lockfile, if it has one
+    pub resolved: Option<String>,
+    pub integrity: Option<String>,
+}
+
+/// npm (< 7) stores resolution metadata right inside installed package.json
+#[derive(Debug, Deserialize)]
+struct InstalledPackage {
+    name: Option<String>,
+    version: Option<String>,
+    dependencies: Option<DependenciesMap>,
+    #[serde(rename = "optionalDependencies")]
+    optional_dependencies: Option<DependenciesMap>,
+    #[serde(rename = "peerDependencies")]
+    peer_dependencies: Option<DependenciesMap>,
+    bin: Option<PackageBin>,
+    funding: Option<PackageFunding>,
+    #[serde(rename = "_resolved")]
+    resolved: Option<String>,
+    #[serde(rename = "_integrity")]
+    integrity: Option<String>,
+}
+
+/// synthesizes lockfile from already installed (by npm or yarn) node_modules
+pub fn from_node_modules(package: &Manifest) -> Lockfile {
+    let mut lockfile = Lockfile::new(package);
+
+    // npm >= 7 keeps "hidden" lockfile with resolved urls and integrity
+    let hidden_lockfile = Lockfile::read(&Path::new(NODE_MODULES).join(".package-lock.json"));
+
+    collect_packages(Path::new(NODE_MODULES), &hidden_lockfile, &mut lockfile);
+
+    return lockfile;
+}
+
+fn collect_packages(dir: &Path, hidden_lockfile: &Option<Lockfile>, lockfile: &mut Lockfile) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+
+        if file_name.starts_with(".") || !path.is_dir() {
+            continue;
+        }
+
+        if file_name.starts_with("@") {
+            collect_packages(&path, hidden_lockfile, lockfile);
+            continue;
+        }
+
+        if let Some(locked) = read_installed_package(&path, hidden_lockfile) {
+            lockfile.packages.insert(to_lockfile_key(&path), locked);
+        }
+
+        collect_packages(&path.join(NODE_MODULES), hidden_lockfile, lockfile);
+    }
+}
+
+fn read_installed_package(dir: &Path, hidden_lockfile: &Option<Lockfile>) -> Option<LockedPackage> {
+    let manifest = fs::File::open(dir.join("package.json")).ok()?;
+    let installed: InstalledPackage = serde_json::from_reader(BufReader::new(manifest)).ok()?;
+
+    // not a package, just some folder
+    installed.name.as_ref()?;
+
+    let hidden = hidden_lockfile
+        .as_ref()
+        .and_then(|hidden| hidden.packages.get(&to_lockfile_key(dir)));
+
+    let resolved = installed
+        .resolved
+        .or_else(|| hidden.and_then(|hidden| hidden.resolved.clone()));
+    let integrity = installed
+        .integrity
+        .or_else(|| hidden.and_then(|hidden| hidden.integrity.clone()));
+
+    return Some(LockedPackage {
+        name: None,
+        version: installed.version,
+        resolved,
+        integrity,
+        optional: hidden.is_some_and(|hidden| hidden.optional),
+        dependencies: lockfile::to_locked_dependencies(&installed.dependencies),
+        dev_dependencies: None,
+        optional_dependencies: lockfile::to_locked_dependencies(&installed.optional_dependencies),
+        peer_dependencies: lockfile::to_locked_dependencies(&installed.peer_dependencies),
+        bin: installed.bin,
+        funding: installed.funding,
+        ..Default::default()
+    });
+}
+
+/// lockfile keys always use forward slashes
+fn to_lockfile_key(path: &Path) -> String {
+    return path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+}
+
+/// reads resolution seed from other package manager's lockfile, if there is one
+pub fn read_seed() -> ResolutionSeed {
+    let bun_lockfile = Path::new(bun::BUN_LOCKFILE);
+
+    if bun_lockfile.exists() {
+        eprintln!("seeding resolution from {}", bun::BUN_LOCKFILE);
+
+        return bun::read_seed(bun_lockfile);
+    }
+
+    let pnpm_lockfile = Path::new(pnpm::PNPM_LOCKFILE);
+
+    if pnpm_lockfile.exists() {
+        eprintln!("seeding resolution from {}", pnpm::PNPM_LOCKFILE);
+
+        return pnpm::read_seed(pnpm_lockfile);
+    }
+
+    let yarn_lockfile = Path::new(yarn::YARN_LOCKFILE);
+
+    if yarn_lockfile.exists() {
+        eprintln!("seeding resolution from {}", yarn::YARN_LOCKFILE);
+
+        return yarn::read_seed(yarn_lockfile);
+    }
+
+    return ResolutionSeed::new();
+}
+
+/// versions of own lockfile, except `packages`, so `razee update <package>` re-resolves
+/// only them and keeps the rest of the tree
+pub fn from_lockfile(lockfile: &Lockfile, packages: &[String]) -> ResolutionSeed {
+    let mut seed = ResolutionSeed::new();
+
+    for (path, locked) in lockfile.packages.iter() {
+        let Some(install_path) = path.strip_prefix(&format!("{NODE_MODULES}/")) else {
+            continue;
+        };
+
+        let name = install_path
+            .rsplit_once(&format!("/{NODE_MODULES}/"))
+            .map_or(install_path, |(_, name)| name);
+
+        if packages.iter().any(|package| package == name) {
+            continue;
+        }
+
+        let Some(version) = locked
+            .version
+            .as_ref()
+            .and_then(|version| Version::parse(version).ok())
+        else {
+            continue;
+        };
+
+        seed.entry(name.to_string())
+            .or_default()
+            .push(SeededVersion {
+                version,
+                resolved: locked.resolved.clone(),
+                integrity: locked.integrity.clone(),
+            });
+    }
+
+    return seed;
+}
+
+/// highest seeded version of the package that satisfies requested range
+pub fn seeded_version(seed: &ResolutionSeed, name: &str, range: &Range) -> Option<SeededVersion> {
+    return seed
+        .get(name)?
+        .iter()
+        .filter(|seeded| range.satisfies(&seeded.version))
+        .max_by(|a, b| a.version.cmp(&b.version))
+        .cloned();
+}
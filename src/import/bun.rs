@@ -0,0 +1,129 @@
+use node_semver::Version;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+use super::{ResolutionSeed, SeededVersion};
+
+pub const BUN_LOCKFILE: &str = "bun.lock";
+
+/// only packages are needed from bun.lock, every package is an array of
+/// `["name@version", "registry", { dependencies... }, "integrity"]`
+#[derive(Debug, Deserialize)]
+struct BunLockfile {
+    #[serde(default)]
+    packages: HashMap<String, Vec<serde_json::Value>>,
+}
+
+/// reads pinned versions from bun's text lockfile
+pub fn read_seed(path: &Path) -> ResolutionSeed {
+    let content = fs::read_to_string(path).expect("cannot read bun.lock");
+
+    return parse_seed(&content);
+}
+
+fn parse_seed(content: &str) -> ResolutionSeed {
+    let lockfile: BunLockfile =
+        serde_json::from_str(&strip_trailing_commas(content)).expect("cannot parse bun.lock");
+
+    let mut seed = ResolutionSeed::new();
+
+    for entry in lockfile.packages.values() {
+        let Some(ident) = entry.first().and_then(|ident| ident.as_str()) else {
+            continue;
+        };
+
+        // scoped packages also start with "@", so splitting from the end
+        let Some((name, version)) = ident.rsplit_once("@") else {
+            continue;
+        };
+
+        // workspace:, github:, file: and other non registry packages are not seeded
+        if let Ok(version) = Version::parse(version) {
+            seed.entry(name.to_string())
+                .or_default()
+                .push(SeededVersion {
+                    version,
+                    resolved: None,
+                    integrity: entry
+                        .get(3)
+                        .and_then(|integrity| integrity.as_str())
+                        .map(String::from),
+                });
+        }
+    }
+
+    return seed;
+}
+
+/// bun.lock is JSONC-like and always has trailing commas, which serde_json rejects
+fn strip_trailing_commas(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars();
+    let mut in_string = false;
+
+    while let Some(char) = chars.next() {
+        if in_string {
+            result.push(char);
+
+            if char == '\\' {
+                if let Some(escaped) = chars.next() {
+                    result.push(escaped);
+                }
+            } else if char == '"' {
+                in_string = false;
+            }
+
+            continue;
+        }
+
+        if char == '"' {
+            in_string = true;
+        }
+
+        if char == ',' {
+            let next_significant = chars.clone().find(|next| !next.is_whitespace());
+
+            if matches!(next_significant, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+
+        result.push(char);
+    }
+
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bun_lock() {
+        let seed = parse_seed(
+            r#"{
+              "lockfileVersion": 1,
+              "workspaces": {
+                "": { "name": "app", "dependencies": { "react": "^18.2.0", }, },
+              },
+              "packages": {
+                "react": [
+                  "react@18.2.0",
+                  "",
+                  { "dependencies": { "loose-envify": "^1.1.0" } },
+                  "sha512-react",
+                ],
+                "@types/node": ["@types/node@20.11.5", "", {}, "sha512-node"],
+                "app-utils": ["app-utils@workspace:packages/utils"],
+              },
+            }"#,
+        );
+
+        let react = &seed["react"][0];
+
+        assert_eq!(react.version.to_string(), "18.2.0");
+        assert_eq!(react.integrity.as_deref(), Some("sha512-react"));
+        assert_eq!(seed["@types/node"][0].version.to_string(), "20.11.5");
+        assert!(!seed.contains_key("app-utils"));
+    }
+}
@@ -0,0 +1,171 @@
+use node_semver::Version;
+use std::{fs, path::Path};
+
+use super::{ResolutionSeed, SeededVersion};
+
+pub const PNPM_LOCKFILE: &str = "pnpm-lock.yaml";
+
+/// reads pinned versions from `packages` of pnpm lockfile, keys of packages are
+/// `/name/1.0.0` (v5), `/name@1.0.0(peer@2.0.0)` (v6) or `name@1.0.0` (v9):
+///
+/// ```text
+/// packages:
+///   /left-pad@1.3.0:
+///     resolution: {integrity: sha512-...}
+/// ```
+///
+/// specifiers of importers are not needed, seeded versions are matched against
+/// ranges from package.json
+pub fn read_seed(path: &Path) -> ResolutionSeed {
+    let content = fs::read_to_string(path).expect("cannot read pnpm-lock.yaml");
+
+    return parse_seed(&content);
+}
+
+fn parse_seed(content: &str) -> ResolutionSeed {
+    let mut seed = ResolutionSeed::new();
+    let mut in_packages = false;
+    let mut package: Option<(String, SeededVersion)> = None;
+
+    for line in content.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim_end();
+
+        if line.trim().is_empty() || line.trim_start().starts_with("#") {
+            continue;
+        }
+
+        if indent <= 2 {
+            if let Some((name, seeded)) = package.take() {
+                seed.entry(name).or_default().push(seeded);
+            }
+        }
+
+        if indent == 0 {
+            in_packages = line == "packages:";
+            continue;
+        }
+
+        if !in_packages {
+            continue;
+        }
+
+        if indent == 2 {
+            package = line
+                .trim()
+                .strip_suffix(":")
+                .and_then(parse_package_id)
+                .map(|(name, version)| {
+                    let seeded = SeededVersion {
+                        version,
+                        resolved: None,
+                        integrity: None,
+                    };
+
+                    (name, seeded)
+                });
+
+            continue;
+        }
+
+        let (Some((_, seeded)), Some(resolution)) =
+            (package.as_mut(), line.trim().strip_prefix("resolution:"))
+        else {
+            continue;
+        };
+
+        let resolution = resolution
+            .trim()
+            .trim_start_matches('{')
+            .trim_end_matches('}');
+
+        for field in resolution.split(", ") {
+            match field.trim().split_once(": ") {
+                Some(("integrity", integrity)) => seeded.integrity = Some(integrity.to_string()),
+                Some(("tarball", tarball)) => seeded.resolved = Some(unquote(tarball).to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    if let Some((name, seeded)) = package {
+        seed.entry(name).or_default().push(seeded);
+    }
+
+    return seed;
+}
+
+/// name and version of package id, `None` for non registry packages (e.g. `file:`,
+/// `github.com/...` ids)
+fn parse_package_id(id: &str) -> Option<(String, Version)> {
+    let id = unquote(id);
+    let id = id.strip_prefix("/").unwrap_or(id);
+    // peers, that package was resolved with
+    let id = id.split("(").next()?;
+
+    // scoped packages also start with "@"
+    if let Some(separator) = id.get(1..).and_then(|rest| rest.rfind("@")) {
+        let (name, version) = id.split_at(separator + 1);
+        // `@` of v5 peer suffix leaves version as a part of name
+        let is_name = name.matches("/").count() <= usize::from(name.starts_with("@"));
+
+        if let (true, Ok(version)) = (is_name, Version::parse(&version[1..])) {
+            return Some((name.to_string(), version));
+        }
+    }
+
+    // v5 ids are `/name/version_peer@version`
+    let (name, version) = id.rsplit_once("/")?;
+    let version = version.split("_").next()?;
+
+    return Some((name.to_string(), Version::parse(version).ok()?));
+}
+
+fn unquote(value: &str) -> &str {
+    return value.trim().trim_matches('\'').trim_matches('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pnpm_lock() {
+        let seed = parse_seed(
+            "lockfileVersion: '6.0'\n\
+            \n\
+            dependencies:\n  \
+              left-pad:\n    \
+                specifier: ^1.0.0\n    \
+                version: 1.3.0\n\
+            \n\
+            packages:\n\
+            \n  \
+              /left-pad@1.3.0:\n    \
+                resolution: {integrity: sha512-pad}\n    \
+                dev: false\n\
+            \n  \
+              /@types/react@18.2.0(react@18.2.0):\n    \
+                resolution: {integrity: sha512-types, tarball: 'https://example.com/react.tgz'}\n\
+            \n  \
+              '@scope/old/2.0.0_react@18.2.0':\n    \
+                resolution: {integrity: sha512-old}\n\
+            \n  \
+              file:vendor/local:\n    \
+                resolution: {directory: vendor/local, type: directory}\n",
+        );
+
+        assert_eq!(seed["left-pad"][0].version.to_string(), "1.3.0");
+        assert_eq!(seed["left-pad"][0].integrity.as_deref(), Some("sha512-pad"));
+
+        let types = &seed["@types/react"][0];
+
+        assert_eq!(types.version.to_string(), "18.2.0");
+        assert_eq!(
+            types.resolved.as_deref(),
+            Some("https://example.com/react.tgz")
+        );
+        assert_eq!(seed["@scope/old"][0].version.to_string(), "2.0.0");
+        assert_eq!(seed.len(), 3);
+    }
+}
@@ -0,0 +1,140 @@
+use node_semver::Version;
+use std::{fs, path::Path};
+
+use super::{ResolutionSeed, SeededVersion};
+
+pub const YARN_LOCKFILE: &str = "yarn.lock";
+
+/// reads pinned versions and tarball urls from yarn (v1) lockfile, every entry is
+///
+/// ```text
+/// "left-pad@^1.0.0", left-pad@^1.1.0:
+///   version "1.3.0"
+///   resolved "https://registry.yarnpkg.com/left-pad/-/left-pad-1.3.0.tgz#<sha1>"
+/// ```
+pub fn read_seed(path: &Path) -> ResolutionSeed {
+    let content = fs::read_to_string(path).expect("cannot read yarn.lock");
+
+    return parse_seed(&content);
+}
+
+fn parse_seed(content: &str) -> ResolutionSeed {
+    if content.contains("__metadata:") {
+        panic!("yarn.lock is from yarn berry (v2+), only yarn v1 lockfiles can be imported");
+    }
+
+    let mut seed = ResolutionSeed::new();
+    let mut name: Option<String> = None;
+    let mut version: Option<Version> = None;
+    let mut resolved: Option<String> = None;
+    let mut integrity: Option<String> = None;
+
+    for line in content.lines().chain([""]) {
+        if line.starts_with("#") {
+            continue;
+        }
+
+        // entry ends with blank line (or with the next entry)
+        if line.trim().is_empty() || !line.starts_with(" ") {
+            if let (Some(name), Some(version)) = (name.take(), version.take()) {
+                seed.entry(name).or_default().push(SeededVersion {
+                    version,
+                    resolved: resolved.take(),
+                    integrity: integrity.take(),
+                });
+            }
+
+            resolved = None;
+            integrity = None;
+
+            if let Some(header) = line.strip_suffix(":") {
+                name = parse_name(header);
+            }
+
+            continue;
+        }
+
+        // only fields of the entry itself, not of its dependencies
+        let Some(field) = line
+            .strip_prefix("  ")
+            .filter(|field| !field.starts_with(" "))
+        else {
+            continue;
+        };
+
+        let Some((key, value)) = field.split_once(" ") else {
+            continue;
+        };
+
+        let value = unquote(value);
+
+        match key {
+            // workspace:, github:, file: and other non registry packages are not seeded
+            "version" => version = Version::parse(value).ok(),
+            // yarn appends sha1 of tarball as url fragment
+            "resolved" => resolved = Some(value.split('#').next().unwrap_or(value).to_string()),
+            "integrity" => integrity = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    return seed;
+}
+
+/// name of the package from header with its specs, e.g. `"@scope/pkg@^1", "@scope/pkg@^1.2"`,
+/// aliases (`alias@npm:pkg@^1`) are not seeded, they don't resolve to the package itself
+fn parse_name(header: &str) -> Option<String> {
+    let spec = unquote(header.split(",").next()?.trim());
+
+    // scoped packages also start with "@"
+    let separator = spec.get(1..)?.find("@")? + 1;
+    let (name, range) = spec.split_at(separator);
+
+    if range.starts_with("@npm:") {
+        return None;
+    }
+
+    return Some(name.to_string());
+}
+
+fn unquote(value: &str) -> &str {
+    return value.trim().trim_matches('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_yarn_lock() {
+        let seed = parse_seed(
+            "# yarn lockfile v1\n\
+            \n\
+            \"@babel/code-frame@^7.0.0\", \"@babel/code-frame@^7.22.13\":\n  \
+              version \"7.22.13\"\n  \
+              resolved \"https://npm.example/@babel/code-frame/-/code-frame-7.22.13.tgz#sha1\"\n  \
+              integrity sha512-frame\n  \
+              dependencies:\n    \
+                chalk \"^2.4.2\"\n\
+            \n\
+            left-pad@^1.0.0, left-pad@^1.1.0:\n  \
+              version \"1.3.0\"\n\
+            \n\
+            string-width-cjs@npm:string-width@^4.2.0:\n  \
+              version \"4.2.3\"\n",
+        );
+
+        let frame = &seed["@babel/code-frame"];
+
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0].version.to_string(), "7.22.13");
+        assert_eq!(
+            frame[0].resolved.as_deref(),
+            Some("https://npm.example/@babel/code-frame/-/code-frame-7.22.13.tgz")
+        );
+        assert_eq!(frame[0].integrity.as_deref(), Some("sha512-frame"));
+        assert_eq!(seed["left-pad"][0].version.to_string(), "1.3.0");
+        assert!(!seed.contains_key("chalk"));
+        assert!(!seed.contains_key("string-width-cjs"));
+    }
+}
@@ -0,0 +1,124 @@
+use node_semver::Version;
+use serde_json::{json, Value};
+use std::{env, path::Path};
+
+use crate::{
+    manifest::{self, PACKAGE_JSON},
+    prompt::ask,
+};
+
+const DEFAULT_TEST_SCRIPT: &str = "echo \"Error: no test specified\" && exit 1";
+
+/// `razee init [--yes|-y]`: creates package.json in current directory, asking for its fields,
+/// `-y` (or non interactive shell) takes defaults, name defaults to the name of directory
+pub fn init(args: &[String]) {
+    let yes = args.iter().any(|arg| arg == "--yes" || arg == "-y");
+    let manifest_path = Path::new(PACKAGE_JSON);
+
+    if manifest_path.exists() {
+        panic!("{PACKAGE_JSON} already exists");
+    }
+
+    let current_dir = env::current_dir().expect("cannot get current dir");
+    let dir_name = current_dir
+        .file_name()
+        .map(|name| to_package_name(&name.to_string_lossy()))
+        .unwrap_or_default();
+
+    let field = |question: &str, default: &str| match yes {
+        true => default.to_string(),
+        false => ask(question, default),
+    };
+
+    let name = loop {
+        let name = field("package name", &dir_name);
+
+        if is_valid_name(&name) {
+            break name;
+        }
+
+        if yes {
+            panic!("{name} is not a valid package name, run `razee init` to choose one");
+        }
+
+        eprintln!("{name} is not a valid package name");
+    };
+
+    let version = loop {
+        let version = field("version", "1.0.0");
+
+        if Version::parse(&version).is_ok() {
+            break version;
+        }
+
+        eprintln!("{version} is not a valid version");
+    };
+
+    let description = field("description", "");
+    let main = field("entry point", "index.js");
+    let test = field("test command", DEFAULT_TEST_SCRIPT);
+    let license = field("license", "ISC");
+
+    let manifest: Value = json!({
+        "name": name,
+        "version": version,
+        "description": description,
+        "main": main,
+        "scripts": { "test": test },
+        "license": license,
+    });
+
+    manifest::write_raw(manifest_path, &manifest);
+
+    println!("wrote {}", current_dir.join(PACKAGE_JSON).display());
+}
+
+/// dir names can have spaces and capitals, that package names can't
+fn to_package_name(dir_name: &str) -> String {
+    return dir_name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|char| match char {
+            'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' => char,
+            _ => '-',
+        })
+        .collect::<String>()
+        .trim_start_matches(['.', '_'])
+        .to_string();
+}
+
+/// npm rules: lowercase url safe name (optionally scoped), that doesn't start with `.` or `_`
+fn is_valid_name(name: &str) -> bool {
+    let unscoped = match name.strip_prefix("@") {
+        Some(scoped) => match scoped.split_once("/") {
+            Some((scope, name)) if is_valid_part(scope) => name,
+            _ => return false,
+        },
+        None => name,
+    };
+
+    return name.len() <= 214 && is_valid_part(unscoped) && !unscoped.starts_with(['.', '_']);
+}
+
+fn is_valid_part(part: &str) -> bool {
+    return !part.is_empty()
+        && part
+            .chars()
+            .all(|char| matches!(char, 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~'));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_package_names() {
+        assert!(is_valid_name("my-app"));
+        assert!(is_valid_name("@company/my.app"));
+        assert!(!is_valid_name("My App"));
+        assert!(!is_valid_name("_private"));
+        assert!(!is_valid_name("@company"));
+        assert!(is_valid_name(&to_package_name("My Cool App")));
+    }
+}
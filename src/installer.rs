@@ -0,0 +1,1184 @@
+use async_recursion::async_recursion;
+use elsa::{FrozenMap, FrozenVec};
+use futures::{future::join_all, FutureExt};
+use node_semver::{Range, Version};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    env, fs,
+    future::{self, Future},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{Arc, Once},
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::Semaphore;
+
+use crate::{
+    bin_linker, cancel,
+    engines::EngineCheck,
+    fetcher::download_tarball,
+    fs_retry::retry,
+    github_actions,
+    hooks::{Hooks, InstallHook},
+    http_client::HttpClient,
+    import::{self, ResolutionSeed},
+    lifecycle::{self, ScriptPolicy},
+    linker::{self, NodeLinker},
+    lock::{DirLock, LOCK_FILE},
+    lockfile::{Lockfile, LOCKFILE},
+    logger, npmrc,
+    overrides::Overrides,
+    package_manager, patch,
+    peers::{self, PeerMode},
+    permissions,
+    platform::Platform,
+    portal, prune, remove,
+    registry::Registry,
+    report::{self, InstallReport, InstallTimings},
+    resolver::{self, fetch_dep, GraphEdge, ResolutionGraph},
+    signatures::SignaturePolicy,
+    store::LocalStore,
+    workspaces, Dep, DependenciesMap, Dependency, Manifest, NODE_MODULES,
+};
+
+type ProcessedDeps = Arc<FrozenMap<String, Box<Dependency>>>;
+
+/// everything that is read once before install and shared between all deps
+pub(crate) struct InstallContext {
+    pub seed: ResolutionSeed,
+    pub lockfile: Option<Lockfile>,
+    pub patched_dependencies: DependenciesMap,
+    /// `overrides` of root package, that apply everywhere, nested ones are entered with
+    /// packages, they are nested under
+    pub overrides: Overrides,
+    /// name -> path of workspace packages
+    pub workspaces: DependenciesMap,
+    pub store: Option<LocalStore>,
+    pub node_linker: NodeLinker,
+    pub signatures: SignaturePolicy,
+    pub scripts: ScriptPolicy,
+    pub platform: Platform,
+    pub engines: EngineCheck,
+    /// limits how many packages are extracted (or linked from store) at once
+    pub fs_limit: Semaphore,
+    pub hooks: Hooks,
+    /// whether packages are downloaded and linked into node_modules, or only resolved
+    pub link: bool,
+    pub peers: PeerMode,
+    pub warnings: FrozenVec<String>,
+    /// install paths of optional packages, that failed to install
+    pub skipped: FrozenVec<String>,
+    /// install paths of packages, `os`, `cpu` or `libc` of which don't match this platform
+    pub other_platform: FrozenVec<String>,
+    /// packages that were written into node_modules
+    pub linked: FrozenVec<String>,
+    pub edges: FrozenVec<Box<GraphEdge>>,
+    pub resolution_time: Cell<Duration>,
+    pub fetching_time: Cell<Duration>,
+}
+
+impl InstallContext {
+    /// prints warning and keeps it for install report
+    pub fn warn(&self, message: String) {
+        github_actions::warning(&message);
+
+        self.warnings.push(message);
+    }
+}
+
+/// installs dependencies (and dev dependencies) of the manifest into
+/// node_modules of current directory
+pub struct Installer {
+    manifest: Manifest,
+    hooks: Hooks,
+    registry: Registry,
+    peers: PeerMode,
+    interactive: bool,
+    frozen_lockfile: bool,
+    store: Option<LocalStore>,
+    node_linker: NodeLinker,
+    signatures: SignaturePolicy,
+    scripts: ScriptPolicy,
+    update: Option<Vec<String>>,
+    seed: Option<ResolutionSeed>,
+    production: bool,
+    engine_strict: bool,
+    strict_package_manager: bool,
+    fs_concurrency: usize,
+    client: Option<Arc<HttpClient>>,
+}
+
+impl Installer {
+    pub fn new(manifest: Manifest) -> Installer {
+        // umask must be read before anything gets extracted
+        permissions::umask();
+
+        let scripts = ScriptPolicy::from_env(manifest.trusted_dependencies.as_ref());
+
+        return Installer {
+            manifest,
+            hooks: Hooks::default(),
+            registry: Registry::from_env(),
+            peers: PeerMode::from_env(),
+            interactive: false,
+            frozen_lockfile: false,
+            store: LocalStore::from_env(),
+            node_linker: NodeLinker::from_env(),
+            signatures: SignaturePolicy::from_env(),
+            scripts,
+            update: None,
+            seed: None,
+            production: env::var("NODE_ENV").is_ok_and(|node_env| node_env == "production")
+                || npmrc::get("omit")
+                    .is_some_and(|omit| omit.split(",").any(|kind| kind.trim() == "dev")),
+            engine_strict: npmrc::flag("RAZEE_ENGINE_STRICT", "engine-strict"),
+            strict_package_manager: npmrc::flag(
+                "RAZEE_STRICT_PACKAGE_MANAGER",
+                "package-manager-strict",
+            ),
+            fs_concurrency: thread::available_parallelism().map_or(4, |threads| threads.get()),
+            client: None,
+        };
+    }
+
+    /// overrides registry, e.g. with `Registry::Fixture` for hermetic tests
+    pub fn registry(mut self, registry: Registry) -> Installer {
+        self.registry = registry;
+
+        return self;
+    }
+
+    /// selects how peer dependencies are handled, defaults to `RAZEE_PEERS` or warning about
+    /// unmet ones
+    pub fn peers(mut self, peers: PeerMode) -> Installer {
+        self.peers = peers;
+
+        return self;
+    }
+
+    /// shares http client (and its caches) with other installers, registry of the
+    /// client is used then
+    pub fn client(mut self, client: Arc<HttpClient>) -> Installer {
+        self.client = Some(client);
+
+        return self;
+    }
+
+    /// allows asking questions, e.g. which version to use for conflicting peers
+    pub fn interactive(mut self, interactive: bool) -> Installer {
+        self.interactive = interactive;
+
+        return self;
+    }
+
+    /// installs exactly what lockfile has, from clean node_modules, fails when lockfile
+    /// is out of sync with package.json and never writes it, e.g. for CI
+    pub fn frozen_lockfile(mut self, frozen_lockfile: bool) -> Installer {
+        self.frozen_lockfile = frozen_lockfile;
+
+        return self;
+    }
+
+    /// overrides store of extracted packages, `None` extracts every package right into
+    /// node_modules
+    pub fn store(mut self, store: Option<LocalStore>) -> Installer {
+        self.store = store;
+
+        return self;
+    }
+
+    /// selects node_modules layout, defaults to `RAZEE_NODE_LINKER` (or `node-linker`
+    /// from .npmrc) or hoisted one
+    pub fn node_linker(mut self, node_linker: NodeLinker) -> Installer {
+        self.node_linker = node_linker;
+
+        return self;
+    }
+
+    /// verifies registry signatures (and attestations) of every package before it is
+    /// fetched, defaults to `RAZEE_VERIFY_SIGNATURES`
+    pub fn verify_signatures(mut self, verify_signatures: bool) -> Installer {
+        self.signatures.verify_signatures = verify_signatures;
+
+        return self;
+    }
+
+    /// scopes (e.g. `@company`), packages of which are only installed with valid
+    /// provenance, defaults to `RAZEE_REQUIRE_PROVENANCE` (comma separated)
+    pub fn require_provenance(mut self, scopes: Vec<String>) -> Installer {
+        self.signatures.require_provenance = scopes;
+
+        return self;
+    }
+
+    /// skips install scripts of every package (and root one), defaults to
+    /// `RAZEE_IGNORE_SCRIPTS`
+    pub fn ignore_scripts(mut self, ignore_scripts: bool) -> Installer {
+        self.scripts.ignore_scripts = ignore_scripts;
+
+        return self;
+    }
+
+    /// only these packages can run install scripts, others are skipped and reported,
+    /// defaults to `trustedDependencies` of package.json and `RAZEE_TRUSTED_DEPENDENCIES`
+    pub fn trusted_dependencies(mut self, names: Vec<String>) -> Installer {
+        self.scripts.trusted_dependencies = Some(names);
+
+        return self;
+    }
+
+    /// ignores locked versions of `packages` (every package, when empty) and resolves them
+    /// to the highest ones, that ranges of package.json still allow
+    pub fn update(mut self, packages: Vec<String>) -> Installer {
+        self.update = Some(packages);
+
+        return self;
+    }
+
+    /// resolves lockfile again, preferring `seed` versions over the highest ones, e.g. to
+    /// collapse duplicates
+    pub fn seed(mut self, seed: ResolutionSeed) -> Installer {
+        self.seed = Some(seed);
+
+        return self;
+    }
+
+    /// skips dev dependencies of root package and workspaces (and packages only they need),
+    /// lockfile keeps them, so it is not written, when it has to change. Defaults to
+    /// `NODE_ENV=production` (or `omit=dev` of .npmrc), like in npm
+    pub fn production(mut self, production: bool) -> Installer {
+        self.production = production;
+
+        return self;
+    }
+
+    /// fails install, when root package or any dependency doesn't support current node
+    /// (by `engines.node`), optional ones are skipped, defaults to `RAZEE_ENGINE_STRICT` (or
+    /// `engine-strict` of .npmrc)
+    pub fn engine_strict(mut self, engine_strict: bool) -> Installer {
+        self.engine_strict = engine_strict;
+
+        return self;
+    }
+
+    /// refuses to install, when `packageManager` of package.json is other manager (or other
+    /// version of razee), defaults to `RAZEE_STRICT_PACKAGE_MANAGER` (or
+    /// `package-manager-strict` of .npmrc)
+    pub fn strict_package_manager(mut self, strict_package_manager: bool) -> Installer {
+        self.strict_package_manager = strict_package_manager;
+
+        return self;
+    }
+
+    /// how many packages can be extracted at once, defaults to number of cpus, concurrency
+    /// of downloads is set on [`HttpClient`]
+    pub fn fs_concurrency(mut self, fs_concurrency: usize) -> Installer {
+        self.fs_concurrency = fs_concurrency.max(1);
+
+        return self;
+    }
+
+    /// registers hook, that will be notified about every install event
+    pub fn hook(mut self, hook: impl InstallHook + 'static) -> Installer {
+        self.hooks.add(Box::new(hook));
+
+        return self;
+    }
+
+    pub async fn install(self) -> InstallReport {
+        return self.run(true).await;
+    }
+
+    /// only resolves dependency graph, nothing is downloaded or written to node_modules
+    pub async fn resolve(self) -> ResolutionGraph {
+        return self.run(false).await.graph;
+    }
+
+    async fn run(self, link: bool) -> InstallReport {
+        // held until install is finished
+        let _node_modules_lock = link.then(|| DirLock::acquire(Path::new(NODE_MODULES)));
+
+        // prune waits for installs, that link from store
+        let _store_lock = self.store.as_ref().filter(|_| link).map(|store| {
+            let lock = store.lock_shared();
+            let project_dir = env::current_dir().expect("cannot read current dir");
+
+            if let Err(error) = store.add_project(&project_dir) {
+                eprintln!("warning: cannot add project to store: {error}");
+            }
+
+            return lock;
+        });
+
+        if link {
+            cancel::listen();
+        }
+
+        let started_at = Instant::now();
+        let installed_before = report::installed_packages();
+        let manifest = self.manifest;
+        let engines = EngineCheck::detect(self.engine_strict);
+
+        package_manager::check(manifest.package_manager.as_deref(), self.strict_package_manager);
+
+        if let Some(mismatch) = engines.mismatch(manifest.engines.as_ref()) {
+            match engines.strict {
+                true => panic!("{} requires {mismatch}", manifest.name),
+                false => github_actions::warning(&format!("{} requires {mismatch}", manifest.name)),
+            }
+        }
+
+        let workspaces = workspaces::manifests(Path::new("."), &manifest);
+        let mut lockfile = Lockfile::new(&manifest);
+        let existing_lockfile = Lockfile::read(Path::new(LOCKFILE));
+        let locked_dependencies = existing_lockfile
+            .as_ref()
+            .filter(|_| self.update.is_none() && self.seed.is_none())
+            .and_then(|lockfile| {
+                let mut locked = lockfile.locked_dependencies(&manifest, &workspaces)?;
+
+                if self.production {
+                    let kept = prune::production_packages(lockfile);
+
+                    locked.retain(|(install_path, _)| {
+                        kept.contains(&format!("{NODE_MODULES}/{install_path}"))
+                    });
+                }
+
+                return Some(locked);
+            });
+
+        lockfile.lock_workspaces(&workspaces);
+
+        if self.frozen_lockfile && locked_dependencies.is_none() {
+            panic!(
+                "{LOCKFILE} is missing or out of sync with package.json, run `razee` to update it"
+            );
+        }
+
+        if link && self.frozen_lockfile {
+            clear_node_modules();
+        }
+
+        let processed_deps: ProcessedDeps = Arc::new(FrozenMap::new());
+        let mut root_specs = vec![];
+        let mut root_edges = vec![];
+        let mut root_optional = HashSet::new();
+        let mut root_required = HashSet::new();
+
+        // workspace packages share root node_modules, so their dependencies are resolved
+        // together with root ones, in one pass
+        let members = workspaces
+            .iter()
+            .map(|(path, workspace)| (format!(" of {path}"), Path::new(path), workspace));
+        let root = [(String::new(), Path::new(""), &manifest)];
+
+        for (suffix, base, member) in root.into_iter().chain(members) {
+            let sections = [
+                ("dependencies", &member.dependencies),
+                ("devDependencies", &member.dev_dependencies),
+                ("optionalDependencies", &member.optional_dependencies),
+            ];
+
+            for (section, deps) in sections {
+                if self.production && section == "devDependencies" {
+                    continue;
+                }
+
+                let optional = section == "optionalDependencies";
+
+                for (name, version) in deps.iter().flatten() {
+                    let is_also_optional = member
+                        .optional_dependencies
+                        .as_ref()
+                        .is_some_and(|optional_deps| optional_deps.contains_key(name));
+
+                    if section == "dependencies" && is_also_optional {
+                        continue;
+                    }
+
+                    match optional {
+                        true => root_optional.insert(name.clone()),
+                        false => root_required.insert(name.clone()),
+                    };
+
+                    let dep = match workspaces
+                        .iter()
+                        .find(|(_, workspace)| &workspace.name == name)
+                    {
+                        // workspace packages are linked, whatever range they are requested with
+                        Some((_, workspace)) => {
+                            let workspace_version = workspace.version.as_deref().unwrap_or("0.0.0");
+
+                            if !workspaces::allows(version, workspace_version) {
+                                panic!(
+                                    "{name} is requested as {version} in {section}{suffix}, \
+                                    but workspace package has version {workspace_version}"
+                                );
+                            }
+
+                            Dep {
+                                name: name.clone(),
+                                version: "workspace:*".to_string(),
+                            }
+                        }
+                        None => Dep {
+                            name: name.clone(),
+                            version: portal::rebase(version, base),
+                        },
+                    };
+
+                    root_edges.push(GraphEdge {
+                        from: member.name.clone(),
+                        to: name.clone(),
+                        range: version.clone(),
+                        deduped: false,
+                    });
+                    root_specs.push((format!("{section}{suffix}"), dep));
+                }
+            }
+        }
+
+        // every workspace package is linked into root node_modules, like npm does
+        for (_, workspace) in workspaces.iter() {
+            root_specs.push((
+                "workspaces".to_string(),
+                Dep {
+                    name: workspace.name.clone(),
+                    version: "workspace:*".to_string(),
+                },
+            ));
+        }
+
+        let (needs_processing, spec_conflicts) = resolver::merge_root_specs(root_specs);
+
+        // package is optional, only when nobody requires it
+        root_optional.retain(|name| !root_required.contains(name));
+
+        // progress is logged to stderr, so output of commands like `graph` can be piped
+        eprintln!();
+
+        let seed = match (self.seed, &self.update, &existing_lockfile) {
+            (Some(seed), _, _) => seed,
+            (None, Some(packages), Some(existing_lockfile)) if !packages.is_empty() => {
+                import::from_lockfile(existing_lockfile, packages)
+            }
+            _ => import::read_seed(),
+        };
+
+        let overrides = Overrides::from_manifest(&manifest);
+        let context = Arc::new(InstallContext {
+            seed,
+            lockfile: existing_lockfile,
+            patched_dependencies: manifest.patched_dependencies.unwrap_or_default(),
+            workspaces: workspaces
+                .iter()
+                .map(|(path, workspace)| (workspace.name.clone(), path.clone()))
+                .collect(),
+            store: self.store,
+            node_linker: self.node_linker,
+            signatures: self.signatures,
+            scripts: self.scripts,
+            platform: Platform::from_env(),
+            engines,
+            fs_limit: Semaphore::new(self.fs_concurrency),
+            overrides,
+            hooks: self.hooks,
+            link,
+            peers: self.peers,
+            warnings: FrozenVec::new(),
+            skipped: FrozenVec::new(),
+            other_platform: FrozenVec::new(),
+            linked: FrozenVec::new(),
+            edges: FrozenVec::new(),
+            resolution_time: Cell::default(),
+            fetching_time: Cell::default(),
+        });
+
+        for conflict in spec_conflicts {
+            context.warn(conflict);
+        }
+
+        for edge in root_edges {
+            context.edges.push(Box::new(edge));
+        }
+
+        let http_client = self
+            .client
+            .unwrap_or_else(|| Arc::new(HttpClient::with_registry(self.registry)));
+
+        if let Some(locked_dependencies) = locked_dependencies {
+            eprintln!("{LOCKFILE} is up to date, installing locked versions");
+
+            join_all(
+                locked_dependencies
+                    .into_iter()
+                    .map(|(install_path, package)| {
+                        install_locked(
+                            package,
+                            install_path,
+                            processed_deps.clone(),
+                            context.clone(),
+                            http_client.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .await;
+        } else {
+            let needs_processing: Vec<Dep> = needs_processing
+                .iter()
+                .map(|dep| context.overrides.apply(dep))
+                .collect();
+            let root_packages = join_all(
+                needs_processing
+                    .iter()
+                    .map(|dep| {
+                        let optional = root_optional.contains(&dep.name);
+
+                        resolve_optional(dep, optional, &context, http_client.clone())
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .await;
+
+            // root dependencies take top of node_modules before any transitive one
+            for (dep, package) in needs_processing.iter().zip(root_packages.iter()) {
+                if let Some(package) = package {
+                    processed_deps.insert(dep.name.clone(), Box::new(package.clone()));
+                }
+            }
+
+            join_all(
+                needs_processing
+                    .iter()
+                    .zip(root_packages)
+                    .filter_map(|(dep, package)| Some((dep, package?)))
+                    .map(|(dep, package)| {
+                        process_package(
+                            package,
+                            dep.name.clone(),
+                            context.overrides.clone(),
+                            processed_deps.clone(),
+                            context.clone(),
+                            http_client.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .await;
+        }
+
+        if cancel::is_cancelled() {
+            cancelled(&context, &processed_deps);
+        }
+
+        if context.peers == PeerMode::AutoInstall {
+            // installed peers can have peers too
+            loop {
+                let missing_peers = peers::missing_peers(&context, &processed_deps);
+
+                if missing_peers.is_empty() {
+                    break;
+                }
+
+                let mut needs_processing = vec![];
+
+                for edge in missing_peers {
+                    needs_processing.push(Dep {
+                        name: edge.to.clone(),
+                        version: edge.range.clone(),
+                    });
+
+                    context.edges.push(Box::new(edge));
+                }
+
+                join_all(
+                    needs_processing
+                        .iter()
+                        .map(|dep| {
+                            process_dep(
+                                dep,
+                                None,
+                                false,
+                                context.overrides.clone(),
+                                processed_deps.clone(),
+                                context.clone(),
+                                http_client.clone(),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .await;
+            }
+        }
+
+        let unmet_peers = match context.peers {
+            PeerMode::Ignore => vec![],
+            _ => peers::unmet_peers(&context, &processed_deps),
+        };
+
+        // strict installs fail on conflicts instead of asking, ignored peers have none
+        let conflicts_resolved = self.interactive
+            && !matches!(context.peers, PeerMode::Strict | PeerMode::Ignore)
+            && peers::resolve_conflicts(&unmet_peers, &context, &processed_deps, &http_client)
+                .await;
+
+        if conflicts_resolved {
+            println!("run razee again to install with saved overrides");
+        } else if context.peers == PeerMode::Strict && !unmet_peers.is_empty() {
+            let unmet_peers: Vec<String> = unmet_peers.iter().map(|p| p.to_string()).collect();
+
+            panic!("unmet peer dependencies:\n  {}", unmet_peers.join("\n  "));
+        } else {
+            for unmet_peer in unmet_peers.iter() {
+                context.warn(unmet_peer.to_string());
+            }
+        }
+
+        http_client.flush_store().await;
+
+        // signal can come while peers are installed, graph is incomplete then
+        if cancel::is_cancelled() {
+            cancelled(&context, &processed_deps);
+        }
+
+        let processed_deps = Arc::try_unwrap(processed_deps)
+            .ok()
+            .expect("install is finished, but packages are still processed");
+        let context = Arc::try_unwrap(context)
+            .ok()
+            .expect("install is finished, but context is still used");
+
+        let mut packages: HashMap<String, Dependency> = processed_deps
+            .into_map()
+            .into_iter()
+            .map(|(install_path, package)| (install_path, *package))
+            .collect();
+
+        for install_path in context.skipped.iter() {
+            if let Some(package) = packages.remove(install_path) {
+                remove_skipped(install_path, &package, &context);
+            }
+        }
+
+        // not installed, but locked, so lockfile works on every platform
+        let other_platform: Vec<(String, Dependency)> = context
+            .other_platform
+            .iter()
+            .filter_map(|install_path| {
+                Some((install_path.to_string(), packages.remove(install_path)?))
+            })
+            .collect();
+
+        if link && context.node_linker == NodeLinker::Isolated {
+            let direct: Vec<&str> = needs_processing
+                .iter()
+                .map(|dep| dep.name.as_str())
+                .collect();
+
+            linker::link_isolated(&packages, &direct, &context);
+        }
+
+        // bins of every package are linked by now, scripts can use them
+        if link {
+            for install_path in lifecycle::run_install_scripts(&packages, &context) {
+                packages.remove(&install_path);
+            }
+        }
+
+        if link && !context.skipped.is_empty() {
+            remove::remove_dangling_bins(&Path::new(NODE_MODULES).join(".bin"));
+        }
+
+        let graph = ResolutionGraph {
+            root: manifest.name,
+            packages,
+            edges: context
+                .edges
+                .into_vec()
+                .into_iter()
+                .map(|edge| *edge)
+                .collect(),
+        };
+
+        // graph without dev dependencies would drop them from lockfile
+        if link && !self.frozen_lockfile && !self.production {
+            lockfile.lock_graph(&graph);
+
+            for (install_path, package) in other_platform.iter() {
+                lockfile.lock_package(install_path, package);
+            }
+
+            lockfile.write(Path::new(LOCKFILE));
+        }
+
+        let (added, removed) = report::diff_installed(&installed_before, &graph);
+
+        return InstallReport {
+            graph,
+            added,
+            removed,
+            unmet_peers,
+            warnings: context.warnings.into_vec(),
+            timings: InstallTimings {
+                total: started_at.elapsed(),
+                resolution: context.resolution_time.get(),
+                fetching: context.fetching_time.get(),
+            },
+        };
+    }
+}
+
+/// resolves `dep`, requested by package installed at `parent_path` (root, when `None`),
+/// with `overrides` of that package applied, and installs it where that package can find it
+#[async_recursion(?Send)]
+async fn process_dep(
+    dep: &Dep,
+    parent_path: Option<String>,
+    optional: bool,
+    overrides: Overrides,
+    processed_deps: ProcessedDeps,
+    context: Arc<InstallContext>,
+    client: Arc<HttpClient>,
+) {
+    if cancel::is_cancelled() {
+        return;
+    }
+
+    let dep = overrides.apply(dep);
+    let Some(package) = resolve_optional(&dep, optional, &context, client.clone()).await else {
+        return;
+    };
+
+    let Some(install_path) = place(&processed_deps, parent_path.as_deref(), &dep.name, &package)
+    else {
+        return;
+    };
+
+    process_package(package, install_path, overrides, processed_deps, context, client).await;
+}
+
+async fn resolve_dep(dep: &Dep, context: &InstallContext, client: Arc<HttpClient>) -> Dependency {
+    let resolution_started_at = Instant::now();
+    let package = fetch_dep(dep, context, client).await;
+
+    context
+        .resolution_time
+        .set(context.resolution_time.get() + resolution_started_at.elapsed());
+
+    logger::log_processed(&dep.name);
+
+    return package;
+}
+
+/// resolves `dep`, optional one is `None`, when it can't be resolved
+async fn resolve_optional(
+    dep: &Dep,
+    optional: bool,
+    context: &InstallContext,
+    client: Arc<HttpClient>,
+) -> Option<Dependency> {
+    if !optional {
+        return Some(resolve_dep(dep, context, client).await);
+    }
+
+    let package = tolerate(&dep.name, resolve_dep(dep, context, client), context).await?;
+
+    return Some(Dependency {
+        optional: true,
+        ..package
+    });
+}
+
+thread_local! {
+    /// set while step of optional package is polled, its panics are not printed
+    static TOLERATING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// runs `install` step of optional package, failure of which (razee fails by panicking) is
+/// reported instead of failing whole install, e.g. native addon, that doesn't build here
+async fn tolerate<T>(
+    name: &str,
+    install: impl Future<Output = T>,
+    context: &InstallContext,
+) -> Option<T> {
+    static QUIET_PANICS: Once = Once::new();
+
+    QUIET_PANICS.call_once(|| {
+        let hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            if !TOLERATING.get() {
+                hook(info);
+            }
+        }));
+    });
+
+    let mut install = Box::pin(AssertUnwindSafe(install).catch_unwind());
+    let result = future::poll_fn(|cx| {
+        let tolerating = TOLERATING.replace(true);
+        let poll = install.as_mut().poll(cx);
+
+        TOLERATING.set(tolerating);
+
+        return poll;
+    })
+    .await;
+
+    return match result {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            context.warn(format!(
+                "skipped optional dependency {name}: {}",
+                github_actions::panic_message(&*payload)
+            ));
+
+            None
+        }
+    };
+}
+
+/// removes whatever was written for optional package, that failed to install, its
+/// dependencies, that were already installed, are kept
+fn remove_skipped(install_path: &str, package: &Dependency, context: &InstallContext) {
+    if !context.link || portal::is_portal(&package.dist.tarball) {
+        return;
+    }
+
+    let dir = lifecycle::package_dir(install_path, package, context);
+
+    if let Err(error) = remove::remove_package(&dir) {
+        context.warn(format!("cannot remove {}: {error}", dir.display()));
+    }
+}
+
+/// links package, optional one is skipped (and `false` is returned), when it fails
+async fn link_optional(
+    package: &Dependency,
+    install_path: &str,
+    context: &InstallContext,
+    client: Arc<HttpClient>,
+) -> bool {
+    if !package.optional {
+        link_package(package, install_path, context, client).await;
+
+        return true;
+    }
+
+    let linked = link_package(package, install_path, context, client);
+
+    if tolerate(&package.name, linked, context).await.is_none() {
+        context.skipped.push(install_path.to_string());
+
+        return false;
+    }
+
+    return true;
+}
+
+/// installs resolved package at `install_path` (relative to node_modules), and then
+/// its dependencies, that are not reachable from there yet, `overrides` are the ones of
+/// its dependent
+#[async_recursion(?Send)]
+async fn process_package(
+    package: Dependency,
+    install_path: String,
+    overrides: Overrides,
+    processed_deps: ProcessedDeps,
+    context: Arc<InstallContext>,
+    client: Arc<HttpClient>,
+) {
+    if cancel::is_cancelled() {
+        return;
+    }
+
+    processed_deps.insert(install_path.clone(), Box::new(package.clone()));
+
+    if is_for_other_platform(&package, &install_path, &context)
+        || !supports_engines(&package, &install_path, &context)
+    {
+        return;
+    }
+
+    let overrides = overrides.enter(&package.name, &package.version);
+    let mut needs_processing = vec![];
+    let optional_deps = package.optional_dependencies.clone().unwrap_or_default();
+    let deps = package.dependencies.iter().flatten().chain(
+        optional_deps
+            .iter()
+            .filter(|(name, _)| !package.dependencies.iter().flatten().any(|(k, _)| k == *name)),
+    );
+
+    let package_dir = portal::package_dir(&package);
+
+    for (k, v) in deps {
+        let range = overrides.version_for(k, v).unwrap_or(v);
+        let deduped = is_reachable(&processed_deps, &install_path, k, range);
+
+        context.edges.push(Box::new(GraphEdge {
+            from: package.name.clone(),
+            to: k.to_owned(),
+            range: v.to_owned(),
+            deduped,
+        }));
+
+        if !deduped {
+            // everything under optional package is optional too
+            let optional = package.optional || optional_deps.contains_key(k);
+
+            needs_processing.push((
+                Dep {
+                    name: k.to_owned(),
+                    version: match &package_dir {
+                        Some(package_dir) => portal::rebase(v, package_dir),
+                        None => v.to_owned(),
+                    },
+                },
+                optional,
+            ));
+        }
+    }
+
+    if context.link && !link_optional(&package, &install_path, &context, client.clone()).await {
+        return;
+    }
+
+    join_all(
+        needs_processing
+            .iter()
+            .map(|(dep, optional)| {
+                process_dep(
+                    dep,
+                    Some(install_path.clone()),
+                    *optional,
+                    overrides.clone(),
+                    processed_deps.clone(),
+                    context.clone(),
+                    client.clone(),
+                )
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await;
+}
+
+/// packages for other platforms (e.g. every `@esbuild/*` but one) are resolved, but
+/// neither installed, nor their dependencies, only required ones are reported
+fn is_for_other_platform(
+    package: &Dependency,
+    install_path: &str,
+    context: &InstallContext,
+) -> bool {
+    let Some(mismatch) = context.platform.mismatch(package) else {
+        return false;
+    };
+
+    let message = format!(
+        "skipped {}@{}, it is only for {mismatch}",
+        package.name, package.version
+    );
+
+    match package.optional {
+        true => logger::log_debug(&message),
+        false => context.warn(message),
+    }
+
+    context.other_platform.push(install_path.to_string());
+
+    return true;
+}
+
+/// packages, that don't support current node, are reported, `engine-strict` fails install
+/// on them instead, and skips optional ones
+fn supports_engines(package: &Dependency, install_path: &str, context: &InstallContext) -> bool {
+    let Some(mismatch) = context.engines.mismatch(package.engines.as_ref()) else {
+        return true;
+    };
+
+    let label = format!("{}@{}", package.name, package.version);
+
+    if !context.engines.strict {
+        context.warn(format!("{label} requires {mismatch}"));
+
+        return true;
+    }
+
+    if !package.optional {
+        panic!("{label} requires {mismatch}");
+    }
+
+    context.warn(format!("skipped optional dependency {label}: requires {mismatch}"));
+    context.skipped.push(install_path.to_string());
+
+    return false;
+}
+
+/// whether package, that node would find for `name` from `parent_path`, satisfies `range`,
+/// tags and urls are satisfied by whatever is there
+fn is_reachable(
+    processed_deps: &ProcessedDeps,
+    parent_path: &str,
+    name: &str,
+    range: &str,
+) -> bool {
+    let Some(nearest) = resolver::lookup_paths(Some(parent_path), name)
+        .iter()
+        .find_map(|path| processed_deps.get(path))
+    else {
+        return false;
+    };
+
+    return match (Range::parse(range), Version::parse(&nearest.version)) {
+        (Ok(range), Ok(version)) => range.satisfies(&version),
+        _ => true,
+    };
+}
+
+/// where resolved `package` is installed: top of node_modules, when it is free, otherwise
+/// node_modules of its dependent (so conflicting versions don't share one copy), `None`
+/// when the same version is already reachable (it was installed while this one was resolved)
+fn place(
+    processed_deps: &ProcessedDeps,
+    parent_path: Option<&str>,
+    name: &str,
+    package: &Dependency,
+) -> Option<String> {
+    let nearest = resolver::lookup_paths(parent_path, name)
+        .into_iter()
+        .find_map(|path| Some((processed_deps.get(&path)?, path)));
+
+    match (nearest, parent_path) {
+        (None, _) => return Some(name.to_string()),
+        (Some((installed, _)), _) if installed.version == package.version => return None,
+        // nothing is nested under root, e.g. auto installed peers use what is at the top
+        (Some(_), None) => return None,
+        (Some(_), Some(parent_path)) => {
+            return Some(format!("{parent_path}/{NODE_MODULES}/{name}"));
+        }
+    }
+}
+
+/// removes everything from node_modules, except lock file, which is held by this process
+fn clear_node_modules() {
+    let Ok(entries) = fs::read_dir(NODE_MODULES) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        if entry.file_name() == LOCK_FILE {
+            continue;
+        }
+
+        let path = entry.path();
+        let removed = match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => retry(&path, || fs::remove_dir_all(&path)),
+            _ => retry(&path, || fs::remove_file(&path)),
+        };
+
+        removed.expect(format!("cannot clear {NODE_MODULES}").as_str());
+    }
+}
+
+/// installs package from lockfile, which is already resolved, so there is nothing
+/// to fetch from registry, except tarball
+async fn install_locked(
+    package: Dependency,
+    install_path: String,
+    processed_deps: ProcessedDeps,
+    context: Arc<InstallContext>,
+    client: Arc<HttpClient>,
+) {
+    if cancel::is_cancelled() {
+        return;
+    }
+
+    for (name, range) in package.dependencies.iter().flatten() {
+        context.edges.push(Box::new(GraphEdge {
+            from: package.name.clone(),
+            to: name.clone(),
+            range: range.clone(),
+            deduped: false,
+        }));
+    }
+
+    processed_deps.insert(install_path.clone(), Box::new(package.clone()));
+
+    let installable = !is_for_other_platform(&package, &install_path, &context)
+        && supports_engines(&package, &install_path, &context);
+
+    if context.link && installable {
+        link_optional(&package, &install_path, &context, client).await;
+    }
+
+    logger::log_processed(&package.name);
+}
+
+/// reports what cancelled install has done and exits, packages in node_modules are
+/// complete, so next install continues from there
+fn cancelled(context: &InstallContext, processed_deps: &ProcessedDeps) -> ! {
+    eprintln!(
+        "install cancelled: {} packages resolved, {} written into {NODE_MODULES}",
+        processed_deps.len(),
+        context.linked.len()
+    );
+    eprintln!("lockfile is left untouched, run razee again to finish install");
+
+    cancel::exit();
+}
+
+/// downloads package into node_modules, applies its patch and links its bins
+async fn link_package(
+    package: &Dependency,
+    install_path: &str,
+    context: &InstallContext,
+    client: Arc<HttpClient>,
+) {
+    let dep_dir = match context.node_linker {
+        NodeLinker::Hoisted => format!("{NODE_MODULES}/{install_path}"),
+        NodeLinker::Isolated => linker::virtual_dir(package).to_string_lossy().to_string(),
+    };
+    // bins of nested packages are only for their dependents, which don't run them by name,
+    // isolated layout links bins of direct dependencies only, once everything is extracted
+    let is_hoisted = install_path == package.name && context.node_linker == NodeLinker::Hoisted;
+
+    if portal::is_portal(&package.dist.tarball) {
+        if let Err(error) = portal::link(package) {
+            context.warn(format!("cannot link {}: {error}", package.dist.tarball));
+            return;
+        }
+
+        context.linked.push(package.name.clone());
+
+        if let Some(bin) = &package.bin {
+            bin_linker::link_bins(&package.name, bin, context);
+        }
+
+        return;
+    }
+
+    let fetching_started_at = Instant::now();
+    let downloaded = download_tarball(package, &dep_dir, context, client).await;
+
+    context
+        .fetching_time
+        .set(context.fetching_time.get() + fetching_started_at.elapsed());
+
+    if !downloaded {
+        return;
+    }
+
+    context.linked.push(package.name.clone());
+
+    let patch_key = format!("{}@{}", package.name, package.version);
+
+    if let Some(patch_path) = context.patched_dependencies.get(&patch_key) {
+        patch::apply(Path::new(patch_path), &dep_dir);
+    }
+
+    if let Some(bin) = package.bin.as_ref().filter(|_| is_hoisted) {
+        bin_linker::link_bins(&package.name, bin, context);
+    }
+}
@@ -0,0 +1,147 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use openssl::sha::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::io::{self, Read};
+
+use crate::DependencyDist;
+
+/// algorithms of subresource integrity, from the weakest one
+const ALGORITHMS: [&str; 4] = ["sha1", "sha256", "sha384", "sha512"];
+
+/// integrity of tarball, old packages only have sha1 `shasum` (hex) instead
+pub(crate) fn expected(dist: &DependencyDist) -> Option<String> {
+    if !dist.integrity.trim().is_empty() {
+        return Some(dist.integrity.clone());
+    }
+
+    let shasum = dist.shasum.as_deref()?;
+    let digest = (0..shasum.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(shasum.get(index..index + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    return Some(format!("sha1-{}", STANDARD.encode(digest)));
+}
+
+/// checks `content` against the strongest algorithm of `integrity` (it can list several
+/// hashes, e.g. `sha512-... sha1-...`), integrity without known algorithms can't be checked
+pub(crate) fn check(content: impl Read, integrity: &str) -> Result<(), String> {
+    let hashes: Vec<(&str, &str)> = integrity
+        .split_whitespace()
+        .filter_map(|hash| hash.split_once("-"))
+        // options, like `sha512-...?foo`, don't change the digest
+        .map(|(algorithm, digest)| (algorithm, digest.split('?').next().unwrap_or(digest)))
+        .collect();
+
+    let Some(algorithm) = ALGORITHMS.iter().rev().find(|algorithm| {
+        hashes
+            .iter()
+            .any(|(hash_algorithm, _)| hash_algorithm == *algorithm)
+    }) else {
+        return Ok(());
+    };
+
+    let digest = hash(content, algorithm).map_err(|error| error.to_string())?;
+
+    let matches = hashes
+        .iter()
+        .any(|(hash_algorithm, expected)| hash_algorithm == algorithm && *expected == digest);
+
+    if !matches {
+        return Err(format!(
+            "integrity mismatch, expected {integrity}, got {algorithm}-{digest}"
+        ));
+    }
+
+    return Ok(());
+}
+
+/// base64 digest of content
+fn hash(mut content: impl Read, algorithm: &str) -> io::Result<String> {
+    let digest = match algorithm {
+        "sha512" => digest::<Sha512>(&mut content)?,
+        "sha384" => digest::<Sha384>(&mut content)?,
+        "sha256" => digest::<Sha256>(&mut content)?,
+        _ => {
+            let mut sha1 = Sha1::new();
+
+            read_chunks(&mut content, |chunk| sha1.update(chunk))?;
+
+            sha1.finish().to_vec()
+        }
+    };
+
+    return Ok(STANDARD.encode(digest));
+}
+
+fn digest<D: Digest>(content: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut hasher = D::new();
+
+    read_chunks(content, |chunk| hasher.update(chunk))?;
+
+    return Ok(hasher.finalize().to_vec());
+}
+
+fn read_chunks(content: &mut impl Read, mut consume: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut buffer = [0; 64 * 1024];
+
+    loop {
+        let read = content.read(&mut buffer)?;
+
+        if read == 0 {
+            return Ok(());
+        }
+
+        consume(&buffer[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha1_of_known_inputs() {
+        assert_eq!(
+            hash(&b"abc"[..], "sha1").unwrap(),
+            "qZk+NkcGgWq6PiVxeFDCbJzQ2J0="
+        );
+        assert_eq!(
+            hash(&[b'a'; 1000][..], "sha1").unwrap(),
+            STANDARD.encode([
+                0x29, 0x1e, 0x9a, 0x6c, 0x66, 0x99, 0x49, 0x49, 0xb5, 0x7b, 0xa5, 0xe6, 0x50, 0x36,
+                0x1e, 0x98, 0xfc, 0x36, 0xb1, 0xba
+            ])
+        );
+    }
+
+    #[test]
+    fn checks_strongest_algorithm() {
+        let sha512 = format!("sha512-{}", hash(&b"tarball"[..], "sha512").unwrap());
+        // wrong sha1 is ignored, when there is sha512
+        let integrity = format!("sha1-AAAA {sha512}");
+
+        assert_eq!(check(&b"tarball"[..], &integrity), Ok(()));
+        assert!(check(&b"other"[..], &integrity)
+            .unwrap_err()
+            .starts_with("integrity mismatch"));
+    }
+
+    #[test]
+    fn shasum_is_used_without_integrity() {
+        let dist = DependencyDist {
+            integrity: String::new(),
+            shasum: Some("a9993e364706816aba3e25717850c26c9cd0d89d".to_string()),
+            tarball: String::new(),
+            file_count: None,
+            unpacked_size: None,
+            signatures: vec![],
+            attestations: None,
+        };
+
+        let integrity = expected(&dist).unwrap();
+
+        assert_eq!(integrity, "sha1-qZk+NkcGgWq6PiVxeFDCbJzQ2J0=");
+        assert_eq!(check(&b"abc"[..], &integrity), Ok(()));
+    }
+}
@@ -0,0 +1,291 @@
+//! Razee's install engine: resolves dependencies of a [`Manifest`] against npm
+//! registry, fetches their tarballs and links them into `node_modules`.
+//!
+//! ```no_run
+//! # async fn run() {
+//! let report = razee::install(razee::InstallOptions::default()).await;
+//!
+//! println!("installed {} packages", report.graph.len());
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub mod access;
+pub mod add;
+pub mod audit;
+mod bin_linker;
+mod cancel;
+pub mod cache;
+pub mod check;
+pub mod create;
+pub mod dedupe;
+pub mod deprecate;
+pub mod diff;
+pub mod dist_tag;
+pub mod dlx;
+mod engines;
+mod fetcher;
+mod fs_retry;
+pub mod fund;
+mod git;
+pub mod github_actions;
+pub mod graph;
+pub mod health;
+pub mod hooks;
+pub mod http_client;
+pub mod import;
+pub mod init;
+mod installer;
+mod integrity;
+mod lifecycle;
+pub mod linker;
+mod local_dir;
+pub mod local_tarball;
+mod lock;
+pub mod lockfile;
+mod logger;
+pub mod ls;
+pub mod manifest;
+mod metadata_cache;
+mod npmrc;
+pub mod owner;
+mod overrides;
+mod package_manager;
+pub mod patch;
+pub mod peers;
+mod permissions;
+mod platform;
+pub mod plan;
+mod portal;
+mod prompt;
+pub mod prune;
+pub mod publish;
+pub mod registry;
+pub mod registry_api;
+pub mod remove;
+mod report;
+mod resolver;
+pub mod run;
+pub mod scripts;
+pub mod self_update;
+mod signatures;
+pub mod store;
+pub mod unpublish;
+pub mod update;
+pub mod watch;
+pub mod workspaces;
+
+pub use installer::Installer;
+pub use manifest::{parse_root_package, Manifest};
+pub use report::{InstallOptions, InstallReport, InstallTimings};
+pub use resolver::{GraphEdge, ResolutionGraph};
+
+pub type DependenciesMap = HashMap<String, String>;
+
+/// package document (packument) from registry, used to resolve versions, registry
+/// sends abbreviated one (without `time`, readmes, etc.) for installs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryPackage {
+    pub name: String,
+    #[serde(default)]
+    pub time: HashMap<String, String>,
+    #[serde(rename = "dist-tags", default)]
+    pub dist_tags: HashMap<String, String>,
+    /// version documents, kept as is, so one broken version doesn't break whole package
+    #[serde(default)]
+    pub versions: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyDist {
+    /// subresource integrity, registry doesn't have it for packages published before 2017
+    #[serde(default)]
+    pub integrity: String,
+    /// sha1 (hex) of tarball, for packages without integrity
+    pub shasum: Option<String>,
+    pub tarball: String,
+    #[serde(rename = "fileCount")]
+    pub file_count: Option<i16>,
+    #[serde(rename = "unpackedSize")]
+    pub unpacked_size: Option<u64>,
+    /// registry signatures of `name@version:integrity`
+    #[serde(default)]
+    pub signatures: Vec<DistSignature>,
+    /// packages published with provenance have sigstore attestations
+    pub attestations: Option<DistAttestations>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistSignature {
+    pub keyid: String,
+    /// base64 of DER encoded ECDSA signature
+    pub sig: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistAttestations {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PackageBin {
+    Single(String),
+    Multiple(HashMap<String, String>),
+}
+
+impl PackageBin {
+    /// returns (bin name, bin path) pairs, single bin is named after package (without scope)
+    pub fn entries(&self, package_name: &str) -> Vec<(String, String)> {
+        match self {
+            PackageBin::Single(path) => {
+                let bin_name = package_name.split("/").last().unwrap_or(package_name);
+
+                return vec![(bin_name.to_string(), path.to_owned())];
+            }
+            PackageBin::Multiple(bins) => {
+                return bins
+                    .iter()
+                    .map(|(name, path)| (name.to_owned(), path.to_owned()))
+                    .collect();
+            }
+        }
+    }
+}
+
+/// `funding` field of package.json: url, `{ type, url }` or list of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PackageFunding {
+    Url(String),
+    Source {
+        #[serde(rename = "type")]
+        kind: Option<String>,
+        url: String,
+    },
+    Multiple(Vec<PackageFunding>),
+}
+
+impl PackageFunding {
+    pub fn urls(&self) -> Vec<&str> {
+        match self {
+            PackageFunding::Url(url) => return vec![url],
+            PackageFunding::Source { url, .. } => return vec![url],
+            PackageFunding::Multiple(sources) => {
+                return sources.iter().flat_map(PackageFunding::urls).collect();
+            }
+        }
+    }
+}
+
+/// specific version of the package from registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    pub version: String,
+    pub dependencies: Option<DependenciesMap>,
+    #[serde(rename = "devDependencies")]
+    pub dev_dependencies: Option<DependenciesMap>,
+    /// can fail to install, registry lists them in `dependencies` too
+    #[serde(rename = "optionalDependencies")]
+    pub optional_dependencies: Option<DependenciesMap>,
+    /// installed only because of optional dependencies, so install goes on, when it fails
+    #[serde(skip)]
+    pub optional: bool,
+    pub dist: DependencyDist,
+    pub bin: Option<PackageBin>,
+    #[serde(rename = "peerDependencies")]
+    pub peer_dependencies: Option<DependenciesMap>,
+    #[serde(rename = "peerDependenciesMeta")]
+    pub peer_dependencies_meta: Option<HashMap<String, PeerDependencyMeta>>,
+    pub funding: Option<PackageFunding>,
+    /// platforms package can be installed on, like `darwin` or `!win32`, as node names them
+    #[serde(default, deserialize_with = "platform::deserialize_list")]
+    pub os: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "platform::deserialize_list")]
+    pub cpu: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "platform::deserialize_list")]
+    pub libc: Option<Vec<String>>,
+    /// `{ "node": "<range>" }`, kept as is, old packages have lists there
+    pub engines: Option<serde_json::Value>,
+    /// name of package in registry, when it is installed under `name` as an alias
+    /// (`"foo": "npm:bar@^2"`)
+    #[serde(skip)]
+    pub alias_of: Option<String>,
+}
+
+impl Dependency {
+    /// name package is published under
+    pub fn registry_name(&self) -> &str {
+        return self.alias_of.as_deref().unwrap_or(&self.name);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeerDependencyMeta {
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// requested dependency, as written in package.json
+#[derive(Debug, Clone)]
+pub struct Dep {
+    pub name: String,
+    pub version: String,
+}
+
+pub const NODE_MODULES: &str = "node_modules";
+
+/// installs project dependencies, same as running `razee` in the project dir
+pub async fn install(options: InstallOptions) -> InstallReport {
+    let manifest = options.manifest.unwrap_or_else(parse_root_package);
+    let mut installer = Installer::new(manifest)
+        .interactive(options.interactive)
+        .frozen_lockfile(options.frozen_lockfile);
+
+    if let Some(fs_concurrency) = options.fs_concurrency {
+        installer = installer.fs_concurrency(fs_concurrency);
+    }
+
+    if options.verify_signatures {
+        installer = installer.verify_signatures(true);
+    }
+
+    if options.ignore_scripts {
+        installer = installer.ignore_scripts(true);
+    }
+
+    if options.strict_peer_dependencies {
+        installer = installer.peers(peers::PeerMode::Strict);
+    } else if options.auto_install_peers {
+        installer = installer.peers(peers::PeerMode::AutoInstall);
+    }
+
+    if options.engine_strict {
+        installer = installer.engine_strict(true);
+    }
+
+    if options.strict_package_manager {
+        installer = installer.strict_package_manager(true);
+    }
+
+    if let Some(production) = options.production {
+        installer = installer.production(production);
+    }
+
+    if let Some(packages) = options.update {
+        installer = installer.update(packages);
+    }
+
+    if let Some(registry) = options.registry {
+        installer = installer.registry(registry);
+    }
+
+    if let Some(client) = options.client {
+        installer = installer.client(client);
+    }
+
+    return installer.install().await;
+}
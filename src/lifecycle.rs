@@ -0,0 +1,439 @@
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    hooks::Hooks,
+    installer::InstallContext,
+    linker::{self, NodeLinker},
+    manifest::PACKAGE_JSON,
+    npmrc, parse_root_package, portal, remove, resolver, scripts, Dependency, NODE_MODULES,
+};
+
+/// scripts, that npm runs for installed packages, in order
+const INSTALL_SCRIPTS: [&str; 3] = ["preinstall", "install", "postinstall"];
+/// root package (and workspaces) are also prepared, e.g. for git hooks
+const ROOT_SCRIPTS: [&str; 4] = ["preinstall", "install", "postinstall", "prepare"];
+/// which install scripts run
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ScriptPolicy {
+    /// skips scripts of every package, root one too
+    pub ignore_scripts: bool,
+    /// names of packages, that can run install scripts, every package can without the list
+    pub trusted_dependencies: Option<Vec<String>>,
+}
+
+impl ScriptPolicy {
+    /// `RAZEE_IGNORE_SCRIPTS=true` (or `ignore-scripts` of .npmrc),
+    /// `RAZEE_TRUSTED_DEPENDENCIES=esbuild,sharp` is added to
+    /// `trustedDependencies` of package.json
+    pub(crate) fn from_env(trusted_dependencies: Option<&Vec<String>>) -> ScriptPolicy {
+        let from_env = env::var("RAZEE_TRUSTED_DEPENDENCIES").ok().map(|names| {
+            names
+                .split(",")
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect::<Vec<_>>()
+        });
+
+        let trusted_dependencies = match (from_env, trusted_dependencies) {
+            (None, None) => None,
+            (from_env, trusted) => Some(
+                from_env
+                    .into_iter()
+                    .flatten()
+                    .chain(trusted.into_iter().flatten().cloned())
+                    .collect(),
+            ),
+        };
+
+        return ScriptPolicy {
+            ignore_scripts: npmrc::flag("RAZEE_IGNORE_SCRIPTS", "ignore-scripts"),
+            trusted_dependencies,
+        };
+    }
+
+    fn allows(&self, package_name: &str) -> bool {
+        if self.ignore_scripts {
+            return false;
+        }
+
+        return self
+            .trusted_dependencies
+            .as_ref()
+            .is_none_or(|trusted| trusted.iter().any(|name| name == package_name));
+    }
+}
+
+/// `install_path@version` of packages, scripts of which already ran, so they don't run
+/// on every install, only when package is installed (or changed)
+const SCRIPTS_STATE: &str = ".razee-scripts";
+
+/// runs install scripts of `packages` (keyed by install path), dependencies before their
+/// dependents, in dirs they are installed to, and then scripts of the root package,
+/// install fails when any script fails, except scripts of optional packages, which are
+/// removed then, their install paths are returned
+pub(crate) fn run_install_scripts(
+    packages: &HashMap<String, Dependency>,
+    context: &InstallContext,
+) -> Vec<String> {
+    let state_path = Path::new(NODE_MODULES).join(SCRIPTS_STATE);
+    let state = fs::read_to_string(&state_path).unwrap_or_default();
+    let mut done: Vec<String> = state.lines().map(String::from).collect();
+
+    // packages, that are no longer installed, are forgotten
+    done.retain(|key| {
+        key.rsplit_once("@").is_some_and(|(install_path, version)| {
+            packages
+                .get(install_path)
+                .is_some_and(|package| package.version == version)
+        })
+    });
+
+    let mut ran_dirs = HashSet::new();
+    let mut skipped = vec![];
+    let mut failed = vec![];
+
+    for install_path in dependency_order(packages) {
+        let package = &packages[&install_path];
+        let key = format!("{install_path}@{}", package.version);
+
+        if done.contains(&key) {
+            continue;
+        }
+
+        let dir = package_dir(&install_path, package, context);
+        let label = format!("{}@{}", package.name, package.version);
+
+        // isolated layout has one dir for every install path of the same version
+        let package_scripts = match ran_dirs.insert(dir.clone()) {
+            true => install_scripts(&dir, &INSTALL_SCRIPTS),
+            false => vec![],
+        };
+
+        // not remembered, so scripts run once package is trusted
+        if !package_scripts.is_empty() && !context.scripts.allows(&package.name) {
+            skipped.push(label);
+            continue;
+        }
+
+        let failure = package_scripts.iter().find_map(|(name, script)| {
+            run(&dir, &label, package, name, script, &context.hooks).err()
+        });
+
+        if let Some(failure) = failure {
+            context.warn(format!("skipped optional dependency {label}: {failure}"));
+
+            if let Err(error) = remove::remove_package(&dir) {
+                context.warn(format!("cannot remove {}: {error}", dir.display()));
+            }
+
+            failed.push(install_path);
+            continue;
+        }
+
+        done.push(key);
+
+        // written right away, so scripts don't run again after failure of later ones
+        if !package_scripts.is_empty() {
+            write_state(&state_path, &done, context);
+        }
+    }
+
+    write_state(&state_path, &done, context);
+
+    if !skipped.is_empty() && context.scripts.ignore_scripts {
+        println!("skipped install scripts of {}", skipped.join(", "));
+    } else if !skipped.is_empty() {
+        context.warn(format!(
+            "skipped install scripts of {}, they are not in trustedDependencies of {PACKAGE_JSON}",
+            skipped.join(", ")
+        ));
+    }
+
+    if context.scripts.ignore_scripts {
+        return failed;
+    }
+
+    let root_scripts = install_scripts(Path::new("."), &ROOT_SCRIPTS);
+
+    if root_scripts.is_empty() {
+        return failed;
+    }
+
+    // hooks see root package as linked dir
+    let root = portal::read_dependency(&parse_root_package().name, "portal:.");
+
+    for (name, script) in root_scripts {
+        let _ = run(
+            Path::new("."),
+            "root package",
+            &root,
+            &name,
+            &script,
+            &context.hooks,
+        );
+    }
+
+    return failed;
+}
+
+fn write_state(state_path: &Path, done: &[String], context: &InstallContext) {
+    if let Err(error) = fs::write(state_path, done.join("\n")) {
+        context.warn(format!("cannot write {}: {error}", state_path.display()));
+    }
+}
+
+/// failure of optional package is returned, others fail the install
+fn run(
+    dir: &Path,
+    label: &str,
+    package: &Dependency,
+    name: &str,
+    script: &str,
+    hooks: &Hooks,
+) -> Result<(), String> {
+    println!("{label} {name}");
+
+    hooks.before_script(package, name);
+
+    let status = scripts::run_script(dir, name, script, &[]);
+
+    hooks.after_script(package, name, status.success());
+
+    if status.success() {
+        return Ok(());
+    }
+
+    let failure = format!(
+        "{name} script of {label} failed with exit code {}",
+        scripts::exit_code(status)
+    );
+
+    if !package.optional {
+        panic!("{failure}");
+    }
+
+    return Err(failure);
+}
+
+/// install paths, where every package comes after packages it depends on (cycles are
+/// broken where they are found), sorted otherwise, so scripts always run in the same order
+fn dependency_order(packages: &HashMap<String, Dependency>) -> Vec<String> {
+    let mut install_paths: Vec<&String> = packages.keys().collect();
+    let mut visited = HashSet::new();
+    let mut order = vec![];
+
+    install_paths.sort();
+
+    for install_path in install_paths {
+        visit(install_path, packages, &mut visited, &mut order);
+    }
+
+    return order;
+}
+
+fn visit(
+    install_path: &str,
+    packages: &HashMap<String, Dependency>,
+    visited: &mut HashSet<String>,
+    order: &mut Vec<String>,
+) {
+    if !visited.insert(install_path.to_string()) {
+        return;
+    }
+
+    let mut dependencies: Vec<&String> = packages[install_path]
+        .dependencies
+        .iter()
+        .flatten()
+        .map(|(name, _)| name)
+        .collect();
+
+    dependencies.sort();
+
+    for name in dependencies {
+        // same package, that node would find from the dependent
+        let dependency_path = resolver::lookup_paths(Some(install_path), name)
+            .into_iter()
+            .find(|path| packages.contains_key(path));
+
+        if let Some(dependency_path) = dependency_path {
+            visit(&dependency_path, packages, visited, order);
+        }
+    }
+
+    order.push(install_path.to_string());
+}
+
+/// where package is installed, relative to project root
+pub(crate) fn package_dir(
+    install_path: &str,
+    package: &Dependency,
+    context: &InstallContext,
+) -> PathBuf {
+    if portal::is_portal(&package.dist.tarball) {
+        return portal::portal_dir(&package.dist.tarball);
+    }
+
+    return match context.node_linker {
+        NodeLinker::Hoisted => Path::new(NODE_MODULES).join(install_path),
+        NodeLinker::Isolated => linker::virtual_dir(package),
+    };
+}
+
+/// `names` scripts from package.json in `dir`, native addons without own install script are
+/// built with `node-gyp rebuild`, like npm does
+fn install_scripts(dir: &Path, names: &[&str]) -> Vec<(String, String)> {
+    let Ok(content) = fs::read_to_string(dir.join(PACKAGE_JSON)) else {
+        return vec![];
+    };
+    let Ok(manifest) = serde_json::from_str::<Value>(&content) else {
+        return vec![];
+    };
+
+    let script = |name: &str| {
+        manifest
+            .get("scripts")
+            .and_then(|scripts| scripts.get(name))
+            .and_then(Value::as_str)
+            .filter(|script| !script.trim().is_empty())
+            .map(String::from)
+    };
+
+    let has_gyp_build = script("install").is_none()
+        && script("preinstall").is_none()
+        && dir.join("binding.gyp").is_file();
+
+    return names
+        .iter()
+        .filter_map(|name| match script(name) {
+            Some(script) => Some((name.to_string(), script)),
+            None if *name == "install" && has_gyp_build => {
+                Some((name.to_string(), "node-gyp rebuild".to_string()))
+            }
+            None => None,
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        hooks::{HookResult, InstallHook},
+        DependencyDist,
+    };
+
+    fn package(name: &str, dependencies: &[&str]) -> Dependency {
+        return Dependency {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Some(
+                dependencies
+                    .iter()
+                    .map(|name| (name.to_string(), "*".to_string()))
+                    .collect(),
+            ),
+            dev_dependencies: None,
+            optional_dependencies: None,
+            optional: false,
+            dist: DependencyDist {
+                integrity: String::new(),
+                shasum: None,
+                tarball: String::new(),
+                file_count: None,
+                unpacked_size: None,
+                signatures: vec![],
+                attestations: None,
+            },
+            bin: None,
+            peer_dependencies: None,
+            peer_dependencies_meta: None,
+            funding: None,
+            os: None,
+            cpu: None,
+            libc: None,
+            engines: None,
+            alias_of: None,
+        };
+    }
+
+    #[test]
+    fn dependencies_come_first() {
+        let packages = HashMap::from([
+            ("a".to_string(), package("a", &["b"])),
+            ("b".to_string(), package("b", &["c"])),
+            // nested c is the one, that b finds
+            ("c".to_string(), package("c", &[])),
+            ("b/node_modules/c".to_string(), package("c", &["a"])),
+        ]);
+
+        assert_eq!(
+            dependency_order(&packages),
+            ["b/node_modules/c", "b", "a", "c"]
+        );
+    }
+
+    struct ScriptLog(Rc<RefCell<Vec<String>>>);
+
+    impl InstallHook for ScriptLog {
+        fn before_script(&self, package: &Dependency, script_name: &str) -> HookResult {
+            self.0
+                .borrow_mut()
+                .push(format!("{} {script_name}", package.name));
+
+            return Ok(());
+        }
+
+        fn after_script(
+            &self,
+            package: &Dependency,
+            script_name: &str,
+            success: bool,
+        ) -> HookResult {
+            self.0
+                .borrow_mut()
+                .push(format!("{} {script_name} {success}", package.name));
+
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn hooks_see_install_scripts() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut hooks = Hooks::default();
+        let mut optional = package("native", &[]);
+
+        hooks.add(Box::new(ScriptLog(log.clone())));
+        optional.optional = true;
+
+        let dir = env::temp_dir();
+
+        assert!(run(&dir, "native@1.0.0", &optional, "install", "exit 0", &hooks).is_ok());
+        assert!(run(
+            &dir,
+            "native@1.0.0",
+            &optional,
+            "postinstall",
+            "exit 3",
+            &hooks
+        )
+        .is_err());
+        assert_eq!(
+            *log.borrow(),
+            [
+                "native install",
+                "native install true",
+                "native postinstall",
+                "native postinstall false"
+            ]
+        );
+    }
+}
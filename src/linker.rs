@@ -0,0 +1,184 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    bin_linker, fs_retry::retry, installer::InstallContext, npmrc, patch, portal, resolver,
+    Dependency, NODE_MODULES,
+};
+
+/// dir inside node_modules, where isolated layout keeps every package
+pub const VIRTUAL_STORE: &str = ".razee";
+
+/// how packages are laid out in node_modules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeLinker {
+    /// every package is at the top of node_modules, unless its version conflicts with
+    /// the one there (npm, yarn classic)
+    #[default]
+    Hoisted,
+    /// only direct dependencies are at the top, they are symlinks into
+    /// `node_modules/.razee/<name>@<version>/node_modules/<name>`, where dependencies of
+    /// every package are symlinked next to it (pnpm), so packages can't use what they
+    /// don't depend on
+    Isolated,
+}
+
+impl NodeLinker {
+    /// `RAZEE_NODE_LINKER`, or `node-linker` from .npmrc
+    pub fn from_env() -> NodeLinker {
+        let linker = env::var("RAZEE_NODE_LINKER")
+            .ok()
+            .or_else(|| npmrc::get("node-linker"));
+
+        return match linker.as_deref() {
+            Some("hoisted") | None => NodeLinker::Hoisted,
+            Some("isolated") => NodeLinker::Isolated,
+            Some(other) => panic!("unknown node-linker: {other}, expected hoisted|isolated"),
+        };
+    }
+}
+
+/// where isolated layout extracts `package`, relative to project root
+pub(crate) fn virtual_dir(package: &Dependency) -> PathBuf {
+    return PathBuf::from(NODE_MODULES)
+        .join(VIRTUAL_STORE)
+        .join(format!(
+            "{}@{}",
+            patch::escape_name(&package.name),
+            package.version
+        ))
+        .join(NODE_MODULES)
+        .join(&package.name);
+}
+
+/// node_modules, where dependencies of `package` are linked to, portals (and workspace
+/// packages) get their own
+fn modules_dir(package: &Dependency) -> PathBuf {
+    if portal::is_portal(&package.dist.tarball) {
+        return portal::portal_dir(&package.dist.tarball).join(NODE_MODULES);
+    }
+
+    let dir = virtual_dir(package);
+    let depth = package.name.split("/").count();
+
+    return dir
+        .ancestors()
+        .nth(depth)
+        .expect("virtual dir is inside node_modules")
+        .to_path_buf();
+}
+
+/// links extracted `packages` (keyed by their hoisted install path) into isolated layout:
+/// `direct` dependencies at the top of node_modules (with their bins), dependencies of
+/// every package next to it, whatever else is at the top (e.g. from hoisted install) is
+/// removed
+pub(crate) fn link_isolated(
+    packages: &HashMap<String, Dependency>,
+    direct: &[&str],
+    context: &InstallContext,
+) {
+    remove_phantoms(direct, context);
+
+    for name in direct {
+        let Some(package) = packages.get(*name) else {
+            continue;
+        };
+
+        // portals are already linked at the top
+        if !portal::is_portal(&package.dist.tarball) {
+            let link_path = PathBuf::from(NODE_MODULES).join(name);
+
+            if let Err(error) = portal::link_dir(&virtual_dir(package), &link_path) {
+                context.warn(format!("cannot link {name}: {error}"));
+                continue;
+            }
+        }
+
+        if let Some(bin) = &package.bin {
+            bin_linker::link_bins(name, bin, context);
+        }
+    }
+
+    for (install_path, package) in packages.iter() {
+        let modules_dir = modules_dir(package);
+
+        for name in package.dependencies.iter().flatten().map(|(name, _)| name) {
+            // same package, that node would find in hoisted layout
+            let dependency = resolver::lookup_paths(Some(install_path), name)
+                .iter()
+                .find_map(|path| packages.get(path));
+
+            let Some(dependency) = dependency else {
+                continue;
+            };
+
+            let target = match portal::is_portal(&dependency.dist.tarball) {
+                true => portal::portal_dir(&dependency.dist.tarball),
+                false => virtual_dir(dependency),
+            };
+
+            if let Err(error) = portal::link_dir(&target, &modules_dir.join(name)) {
+                context.warn(format!("cannot link {name} for {}: {error}", package.name));
+            }
+        }
+    }
+}
+
+/// top level packages, that are not direct dependencies, would let code use packages
+/// without depending on them
+fn remove_phantoms(direct: &[&str], context: &InstallContext) {
+    let Ok(entries) = fs::read_dir(NODE_MODULES) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        // .bin, .razee, lock file
+        if name.starts_with(".") {
+            continue;
+        }
+
+        let paths = match name.starts_with("@") {
+            true => fs::read_dir(entry.path())
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|scoped| scoped.path())
+                .collect(),
+            false => vec![entry.path()],
+        };
+
+        for path in paths {
+            let name = path
+                .strip_prefix(NODE_MODULES)
+                .expect("entry is inside node_modules")
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if direct.contains(&name.as_str()) {
+                continue;
+            }
+
+            if let Err(error) = remove(&path) {
+                context.warn(format!("cannot remove {}: {error}", path.display()));
+            }
+        }
+    }
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    let is_dir = path
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.is_dir());
+
+    return match is_dir {
+        true => retry(path, || fs::remove_dir_all(path)),
+        false => retry(path, || fs::remove_file(path)),
+    };
+}
@@ -0,0 +1,85 @@
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{local_tarball, manifest::PACKAGE_JSON, publish, Dependency};
+
+/// `file:../shared-lib` (or plain `../shared-lib`) is local package dir, that is copied
+/// into node_modules, like it was installed from registry, `link:` symlinks dir instead
+pub(crate) fn is_local_dir(spec: &str) -> bool {
+    if local_tarball::is_local_tarball(spec) {
+        return false;
+    }
+
+    return spec.starts_with("file:")
+        || spec.starts_with("./")
+        || spec.starts_with("../")
+        || spec.starts_with("/");
+}
+
+/// normalizes `./shared-lib` and `file:./shared-lib` into `file:shared-lib`
+fn to_spec(spec: &str) -> String {
+    let path = spec.strip_prefix("file:").unwrap_or(spec);
+
+    return format!("file:{}", path.trim_start_matches("./"));
+}
+
+fn dir(spec: &str) -> &Path {
+    return Path::new(spec.strip_prefix("file:").unwrap_or(spec));
+}
+
+/// reads package.json of the dir, `dist.tarball` keeps the spec, dir is packed again on
+/// every install, so there is no integrity to lock
+pub(crate) fn read_dependency(name: &str, spec: &str) -> Dependency {
+    let spec = to_spec(spec);
+    let mut manifest = read_manifest(dir(&spec));
+
+    // package is installed under the name it was requested with
+    manifest["name"] = Value::from(name);
+
+    if manifest.get("version").and_then(Value::as_str).is_none() {
+        manifest["version"] = Value::from("0.0.0");
+    }
+
+    manifest["dist"] = json!({
+        "tarball": spec,
+        "integrity": "",
+    });
+
+    return serde_json::from_value(manifest)
+        .expect(format!("invalid {PACKAGE_JSON} in {spec}").as_str());
+}
+
+/// dir packed like `npm pack` would pack it (so only published files are copied) into
+/// temp dir
+pub(crate) fn tarball_file(spec: &str) -> PathBuf {
+    let dir = dir(spec);
+    let packed = publish::pack_dir(dir, &read_manifest(dir));
+    let tarball_dir = env::temp_dir().join("razee-local");
+    let file_name = spec
+        .chars()
+        .map(|char| match char.is_ascii_alphanumeric() {
+            true => char,
+            false => '-',
+        })
+        .collect::<String>();
+    let tarball_path = tarball_dir.join(format!("{file_name}-{}.tgz", process::id()));
+
+    fs::create_dir_all(&tarball_dir)
+        .and_then(|_| fs::write(&tarball_path, packed))
+        .expect(format!("cannot pack {}", dir.display()).as_str());
+
+    return tarball_path;
+}
+
+fn read_manifest(dir: &Path) -> Value {
+    let manifest_path = dir.join(PACKAGE_JSON);
+    let content = fs::read_to_string(&manifest_path)
+        .expect(format!("{} has no {PACKAGE_JSON}", dir.display()).as_str());
+
+    return serde_json::from_str(&content)
+        .expect(format!("cannot parse {}", manifest_path.display()).as_str());
+}
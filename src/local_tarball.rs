@@ -0,0 +1,98 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use sha2::{Digest, Sha512};
+use std::{fs, io::Cursor, io::Read};
+use tar::Archive;
+
+use crate::Dependency;
+
+/// `file:` spec (or plain path) pointing to packed package, e.g. `file:vendor/some-pkg-1.2.3.tgz`,
+/// tarballs are always gzipped, like ones from registry, so plain `.tar` is not one
+pub fn is_local_tarball(spec: &str) -> bool {
+    if is_tarball_url(spec) {
+        return false;
+    }
+
+    let path = spec.strip_prefix("file:").unwrap_or(spec);
+
+    return [".tgz", ".tar.gz"]
+        .iter()
+        .any(|extension| path.ends_with(extension));
+}
+
+/// normalizes `./vendor/pkg.tgz` and `file:./vendor/pkg.tgz` into `file:vendor/pkg.tgz`
+pub fn to_spec(path: &str) -> String {
+    let path = path.strip_prefix("file:").unwrap_or(path);
+
+    return format!("file:{}", path.trim_start_matches("./"));
+}
+
+pub fn read(spec: &str) -> Bytes {
+    let path = spec.strip_prefix("file:").unwrap_or(spec);
+    let tarball = fs::read(path).expect(format!("cannot read local tarball {path}").as_str());
+
+    return Bytes::from(tarball);
+}
+
+/// subresource integrity string, same as npm puts into lockfile
+pub fn integrity(tarball: &[u8]) -> String {
+    return format!("sha512-{}", STANDARD.encode(Sha512::digest(tarball)));
+}
+
+/// reads package.json packed into tarball and turns it into dependency, that can be
+/// installed like one from registry, `dist.tarball` points back to the file
+pub(crate) fn read_dependency(spec: &str) -> Dependency {
+    let spec = to_spec(spec);
+    let tarball = read(&spec);
+
+    return to_dependency(&tarball, &spec);
+}
+
+/// `https://example.com/pkg-1.0.0.tgz`, tarball, that is not in registry
+pub(crate) fn is_tarball_url(spec: &str) -> bool {
+    return spec.starts_with("https://") || spec.starts_with("http://");
+}
+
+/// dependency from package.json inside `tarball`, which is locked as `resolved` with its
+/// integrity
+pub(crate) fn to_dependency(tarball: &Bytes, resolved: &str) -> Dependency {
+    let mut manifest = read_embedded_manifest(tarball)
+        .expect(format!("{resolved} has no package.json inside").as_str());
+
+    manifest["dist"] = serde_json::json!({
+        "tarball": resolved,
+        "integrity": integrity(tarball),
+    });
+
+    return serde_json::from_value(manifest)
+        .expect(format!("invalid package.json inside {resolved}").as_str());
+}
+
+/// package.json in tarball's root folder, which is usually, but not always, `package/`
+fn read_embedded_manifest(tarball: &Bytes) -> Option<Value> {
+    let mut archive = Archive::new(GzDecoder::new(Cursor::new(tarball)));
+
+    for entry in archive.entries().ok()? {
+        let Ok(mut entry) = entry else {
+            continue;
+        };
+
+        let is_root_manifest = entry.path().is_ok_and(|path| {
+            path.components().count() == 2 && path.file_name() == Some("package.json".as_ref())
+        });
+
+        if !is_root_manifest {
+            continue;
+        }
+
+        let mut content = String::new();
+
+        entry.read_to_string(&mut content).ok()?;
+
+        return serde_json::from_str(&content).ok();
+    }
+
+    return None;
+}
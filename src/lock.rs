@@ -0,0 +1,109 @@
+use chrono::{SecondsFormat, Utc};
+use std::{
+    env,
+    fs::{self, File, TryLockError},
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+pub(crate) const LOCK_FILE: &str = ".razee.lock";
+
+/// advisory lock of directory, that is mutated by razee (node_modules, store), so two
+/// processes don't interleave writes, released when dropped (or when process dies)
+pub(crate) struct DirLock {
+    file: File,
+    path: PathBuf,
+    shared: bool,
+}
+
+impl DirLock {
+    /// locks `dir`, waits for other process to release it, if needed
+    pub(crate) fn acquire(dir: &Path) -> DirLock {
+        return DirLock::lock(dir, false);
+    }
+
+    /// locks `dir` together with other shared holders, only waits for exclusive one,
+    /// e.g. installs share store, while prune needs it exclusively
+    pub(crate) fn acquire_shared(dir: &Path) -> DirLock {
+        return DirLock::lock(dir, true);
+    }
+
+    fn lock(dir: &Path, shared: bool) -> DirLock {
+        fs::create_dir_all(dir).expect(format!("cannot create {}", dir.display()).as_str());
+
+        let path = dir.join(LOCK_FILE);
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .expect(format!("cannot open {}", path.display()).as_str());
+
+        let locked = match shared {
+            true => file.try_lock_shared(),
+            false => file.try_lock(),
+        };
+
+        match locked {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = fs::read_to_string(&path).unwrap_or_default();
+                let holder = match holder.trim() {
+                    "" => "another process".to_string(),
+                    holder => holder.to_string(),
+                };
+
+                eprintln!("waiting for {} to be released by {holder}", dir.display());
+
+                let locked = match shared {
+                    true => file.lock_shared(),
+                    false => file.lock(),
+                };
+
+                locked.expect(format!("cannot lock {}", path.display()).as_str());
+            }
+            Err(TryLockError::Error(error)) => {
+                panic!("cannot lock {}: {error}", path.display());
+            }
+        }
+
+        // shared holders would overwrite each other
+        if shared {
+            return DirLock { file, path, shared };
+        }
+
+        // whoever waits for the lock, can tell what holds it
+        let holder = format!(
+            "razee (pid {}, `{}`) since {}",
+            process::id(),
+            env::args().collect::<Vec<_>>().join(" "),
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+
+        let written = file
+            .set_len(0)
+            .and_then(|_| file.seek(SeekFrom::Start(0)))
+            .and_then(|_| file.write_all(holder.as_bytes()));
+
+        if let Err(error) = written {
+            eprintln!("warning: cannot write {}: {error}", path.display());
+        }
+
+        return DirLock { file, path, shared };
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        // lock file stays, removing it would let next process lock different file
+        if !self.shared {
+            let _ = self.file.set_len(0);
+        }
+
+        if let Err(error) = self.file.unlock() {
+            eprintln!("warning: cannot unlock {}: {error}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use node_semver::{Range, Version};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    process,
+};
+
+use crate::{
+    resolver, DependenciesMap, Dependency, DependencyDist, Manifest, PackageBin, PackageFunding,
+    ResolutionGraph, NODE_MODULES,
+};
+
+pub const LOCKFILE: &str = "package-lock.json";
+const LOCKFILE_VERSION: u8 = 3;
+
+type LockedDependencies = BTreeMap<String, String>;
+
+/// npm compatible (lockfileVersion 3) lockfile, packages are keyed by their
+/// install path, e.g. `node_modules/@types/node`, root package is keyed by ""
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(rename = "lockfileVersion")]
+    pub lockfile_version: u8,
+    #[serde(default)]
+    pub requires: bool,
+    #[serde(default)]
+    pub packages: BTreeMap<String, LockedPackage>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LockedPackage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+    /// installed only because of optional dependencies
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<LockedDependencies>,
+    #[serde(rename = "devDependencies", skip_serializing_if = "Option::is_none")]
+    pub dev_dependencies: Option<LockedDependencies>,
+    #[serde(
+        rename = "optionalDependencies",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub optional_dependencies: Option<LockedDependencies>,
+    #[serde(rename = "peerDependencies", skip_serializing_if = "Option::is_none")]
+    pub peer_dependencies: Option<LockedDependencies>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bin: Option<PackageBin>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub funding: Option<PackageFunding>,
+    /// kept for packages, that are not installed on every platform
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub libc: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engines: Option<Value>,
+    /// root package only, packages are resolved again, when they change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolutions: Option<LockedDependencies>,
+}
+
+impl Lockfile {
+    /// creates lockfile with only root package entry
+    pub fn new(package: &Manifest) -> Lockfile {
+        let root = to_locked_manifest(package);
+
+        return Lockfile {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            lockfile_version: LOCKFILE_VERSION,
+            requires: true,
+            packages: BTreeMap::from([("".to_string(), root)]),
+        };
+    }
+
+    /// reads lockfile if it exists
+    pub fn read(path: &Path) -> Option<Lockfile> {
+        if !path.exists() {
+            return None;
+        }
+
+        let file = fs::File::open(path).expect("cannot open lockfile");
+        let lockfile = serde_json::from_reader(BufReader::new(file))
+            .expect(format!("cannot parse lockfile: {}", path.display()).as_str());
+
+        return Some(lockfile);
+    }
+
+    /// adds entries of workspace packages, keyed by their path, like npm does
+    pub fn lock_workspaces(&mut self, workspaces: &[(String, Manifest)]) {
+        for (path, workspace) in workspaces {
+            self.packages
+                .insert(path.clone(), to_locked_manifest(workspace));
+        }
+    }
+
+    /// replaces locked packages (except root and workspaces) with resolved ones
+    pub fn lock_graph(&mut self, graph: &ResolutionGraph) {
+        self.packages
+            .retain(|path, _| !path.starts_with(&format!("{NODE_MODULES}/")));
+
+        for (install_path, package) in graph.packages.iter() {
+            self.lock_package(install_path, package);
+        }
+    }
+
+    /// adds resolved package, e.g. one that is not installed on this platform, but is
+    /// on others
+    pub fn lock_package(&mut self, install_path: &str, package: &Dependency) {
+        self.packages.insert(
+            format!("{NODE_MODULES}/{install_path}"),
+            LockedPackage {
+                // aliased package keeps its own name, like npm locks it
+                name: package.alias_of.clone(),
+                version: Some(package.version.clone()),
+                resolved: Some(package.dist.tarball.clone()),
+                integrity: Some(package.dist.integrity.clone())
+                    .filter(|integrity| !integrity.is_empty()),
+                optional: package.optional,
+                dependencies: to_locked_dependencies(&package.dependencies),
+                optional_dependencies: to_locked_dependencies(&package.optional_dependencies),
+                peer_dependencies: to_locked_dependencies(&package.peer_dependencies),
+                bin: package.bin.clone(),
+                funding: package.funding.clone(),
+                os: package.os.clone(),
+                cpu: package.cpu.clone(),
+                libc: package.libc.clone(),
+                engines: package.engines.clone(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// locked packages with their install paths (relative to node_modules), that can be
+    /// installed as they are, when lockfile is up to date with `manifest` and its
+    /// `workspaces`, otherwise `None`, so everything is resolved again
+    pub fn locked_dependencies(
+        &self,
+        manifest: &Manifest,
+        workspaces: &[(String, Manifest)],
+    ) -> Option<Vec<(String, Dependency)>> {
+        let members = [("", manifest)].into_iter().chain(
+            workspaces
+                .iter()
+                .map(|(path, workspace)| (path.as_str(), workspace)),
+        );
+
+        for (path, member) in members.clone() {
+            let locked = self.packages.get(path)?;
+            let expected = to_locked_manifest(member);
+
+            if locked.dependencies != expected.dependencies
+                || locked.dev_dependencies != expected.dev_dependencies
+                || locked.optional_dependencies != expected.optional_dependencies
+                || locked.overrides != expected.overrides
+                || locked.resolutions != expected.resolutions
+            {
+                return None;
+            }
+        }
+
+        let workspace_names: Vec<&str> = workspaces
+            .iter()
+            .map(|(_, workspace)| workspace.name.as_str())
+            .collect();
+        let root_specs = members
+            .flat_map(|(_, member)| {
+                [
+                    (&member.dependencies, false),
+                    (&member.dev_dependencies, false),
+                    (&member.optional_dependencies, true),
+                ]
+            })
+            .flat_map(|(deps, optional)| deps.iter().flatten().map(move |spec| (spec, optional)))
+            .filter(|((name, _), _)| !workspace_names.contains(&name.as_str()));
+
+        // lockfile could be edited by hand, or only partially (e.g. by `razee add`)
+        for ((name, range), optional) in root_specs {
+            let locked = match self.packages.get(&format!("{NODE_MODULES}/{name}")) {
+                Some(locked) => locked,
+                // optional dependency, that failed to install
+                None if optional => continue,
+                None => return None,
+            };
+            let version = Version::parse(locked.version.as_ref()?).ok()?;
+            let range = match resolver::parse_alias(range) {
+                Some((alias_of, _)) if locked.name.as_deref() != Some(alias_of) => return None,
+                Some((_, range)) => range,
+                None => range,
+            };
+
+            if let Ok(range) = Range::parse(range) {
+                if !range.satisfies(&version) {
+                    return None;
+                }
+            }
+        }
+
+        let mut dependencies = vec![];
+
+        for (path, locked) in self.packages.iter() {
+            if path.is_empty() {
+                continue;
+            }
+
+            let Some(install_path) = path.strip_prefix(&format!("{NODE_MODULES}/")) else {
+                // workspace, that is no longer in package.json
+                workspaces.iter().find(|(workspace, _)| workspace == path)?;
+
+                continue;
+            };
+
+            // nested packages are keyed by `dependent/node_modules/name`
+            let name = install_path
+                .rsplit_once(&format!("/{NODE_MODULES}/"))
+                .map_or(install_path, |(_, name)| name);
+
+            let from_locked = |deps: &Option<LockedDependencies>| {
+                deps.as_ref()
+                    .map(|deps| deps.clone().into_iter().collect::<DependenciesMap>())
+            };
+
+            let dependency = Dependency {
+                name: name.to_string(),
+                version: locked.version.clone()?,
+                dependencies: from_locked(&locked.dependencies),
+                dev_dependencies: None,
+                optional_dependencies: from_locked(&locked.optional_dependencies),
+                optional: locked.optional,
+                dist: DependencyDist {
+                    integrity: locked.integrity.clone().unwrap_or_default(),
+                    shasum: None,
+                    tarball: locked.resolved.clone()?,
+                    file_count: None,
+                    unpacked_size: None,
+                    signatures: vec![],
+                    attestations: None,
+                },
+                bin: locked.bin.clone(),
+                peer_dependencies: from_locked(&locked.peer_dependencies),
+                peer_dependencies_meta: None,
+                funding: locked.funding.clone(),
+                os: locked.os.clone(),
+                cpu: locked.cpu.clone(),
+                libc: locked.libc.clone(),
+                engines: locked.engines.clone(),
+                alias_of: locked.name.clone(),
+            };
+
+            dependencies.push((install_path.to_string(), dependency));
+        }
+
+        return Some(dependencies);
+    }
+
+    /// lockfile is written aside and moved in place, so interrupted install never
+    /// leaves half of it
+    pub fn write(&self, path: &Path) {
+        let temp_path = path.with_extension(format!("json.{}.tmp", process::id()));
+        let file = fs::File::create(&temp_path).expect("cannot create lockfile");
+        let mut writer = BufWriter::new(file);
+
+        serde_json::to_writer_pretty(&mut writer, self).expect("cannot serialize lockfile");
+        writer.write_all(b"\n").expect("cannot write lockfile");
+        writer
+            .into_inner()
+            .map_err(|error| error.into_error())
+            .and_then(|file| file.sync_all())
+            .expect("cannot write lockfile");
+
+        fs::rename(&temp_path, path).expect("cannot replace lockfile");
+    }
+}
+
+/// entry of package, that is not installed from registry (root or workspace)
+fn to_locked_manifest(package: &Manifest) -> LockedPackage {
+    return LockedPackage {
+        name: Some(package.name.clone()),
+        version: package.version.clone(),
+        dependencies: to_locked_dependencies(&package.dependencies),
+        dev_dependencies: to_locked_dependencies(&package.dev_dependencies),
+        optional_dependencies: to_locked_dependencies(&package.optional_dependencies),
+        overrides: package
+            .overrides
+            .as_ref()
+            .filter(|overrides| !overrides.is_empty())
+            // sorted, so lockfile doesn't change between installs
+            .map(|overrides| {
+                Value::from_iter(overrides.clone().into_iter().collect::<BTreeMap<_, _>>())
+            }),
+        resolutions: to_locked_dependencies(&package.resolutions),
+        ..Default::default()
+    };
+}
+
+pub fn to_locked_dependencies(deps: &Option<DependenciesMap>) -> Option<LockedDependencies> {
+    return deps
+        .as_ref()
+        .filter(|deps| !deps.is_empty())
+        .map(|deps| deps.clone().into_iter().collect());
+}
@@ -1,10 +1,19 @@
+use std::env;
+
 const ESC: &str = "\x1B";
 
 #[allow(dead_code)]
 pub fn log_fetching(package_name: &String) {
-  print!("{ESC}[1A{ESC}[2K\rfetching: {}\n", package_name);
+  eprint!("{ESC}[1A{ESC}[2K\rfetching: {}\n", package_name);
 }
 
 pub fn log_processed(package_name: &String) {
-  print!("{ESC}[1A{ESC}[2K\rprocessed: {}\n", package_name);
-}
\ No newline at end of file
+  eprint!("{ESC}[1A{ESC}[2K\rprocessed: {}\n", package_name);
+}
+
+/// printed only with `RAZEE_DEBUG=true`
+pub fn log_debug(message: &str) {
+  if env::var("RAZEE_DEBUG").is_ok_and(|debug| debug == "true" || debug == "1") {
+    eprintln!("debug: {message}");
+  }
+}
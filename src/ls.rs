@@ -0,0 +1,330 @@
+use serde_json::{json, Map, Value};
+use std::{collections::HashSet, path::Path};
+
+use crate::{
+    import,
+    lockfile::{LockedPackage, Lockfile, LOCKFILE},
+    parse_root_package, publish, NODE_MODULES,
+};
+
+/// installed package in the tree, dependencies are found like node does, from the closest
+/// node_modules up to the root one
+#[derive(Debug)]
+struct TreeNode {
+    name: String,
+    /// requested range, when package is missing
+    version: String,
+    resolved: Option<String>,
+    /// already printed with its dependencies
+    deduped: bool,
+    missing: bool,
+    dependencies: Vec<TreeNode>,
+}
+
+/// `razee ls [--depth <n>] [--pattern <glob>] [--json]`: prints installed dependency tree from
+/// lockfile (or node_modules, when there is no lockfile), only top level packages by default.
+/// With `--pattern` (e.g. `@types/*`) only paths to matching packages are printed, at any depth
+pub fn ls(args: &[String]) {
+    let usage = "usage: razee ls [--depth <n>] [--pattern <glob>] [--json]";
+    let json = args.iter().any(|arg| arg == "--json");
+
+    let pattern = args
+        .iter()
+        .position(|arg| arg == "--pattern")
+        .map(|index| args.get(index + 1).expect(usage).as_str());
+
+    let depth = match args.iter().position(|arg| arg == "--depth") {
+        Some(index) => Some(
+            args.get(index + 1)
+                .and_then(|depth| depth.parse().ok())
+                .expect(usage),
+        ),
+        None if pattern.is_some() => None,
+        None => Some(0),
+    };
+
+    let manifest = parse_root_package();
+    let lockfile =
+        Lockfile::read(Path::new(LOCKFILE)).unwrap_or_else(|| import::from_node_modules(&manifest));
+    let root = lockfile
+        .packages
+        .get("")
+        .expect(format!("{LOCKFILE} has no root package").as_str());
+
+    let mut shown = HashSet::new();
+    let mut dependencies = vec![];
+
+    for (name, range) in requested(root, true) {
+        dependencies.push(build(&lockfile, "", &name, &range, depth, &mut shown));
+    }
+
+    // workspaces are linked into root node_modules, even when root doesn't depend on them
+    for (path, workspace) in lockfile.packages.iter() {
+        let Some(name) = &workspace.name else {
+            continue;
+        };
+
+        if path.is_empty() || dependencies.iter().any(|node| &node.name == name) {
+            continue;
+        }
+
+        dependencies.push(build(&lockfile, "", name, "*", depth, &mut shown));
+    }
+
+    if let Some(pattern) = pattern {
+        dependencies = filter(dependencies, pattern);
+    }
+
+    let tree = TreeNode {
+        name: manifest.name,
+        version: manifest.version.unwrap_or_default(),
+        resolved: None,
+        deduped: false,
+        missing: false,
+        dependencies,
+    };
+
+    if json {
+        let mut document = to_json(&tree);
+
+        document["name"] = json!(tree.name);
+
+        println!("{}", serde_json::to_string_pretty(&document).unwrap());
+        return;
+    }
+
+    match tree.version.is_empty() {
+        true => println!("{}", tree.name),
+        false => println!("{}@{}", tree.name, tree.version),
+    }
+
+    print_dependencies(&tree.dependencies, "");
+
+    if tree.dependencies.is_empty() {
+        println!("└── (empty)");
+    }
+}
+
+/// dependencies, optional ones (and dev dependencies of root or workspaces) with their ranges
+fn requested(locked: &LockedPackage, dev: bool) -> Vec<(String, String)> {
+    let dev_dependencies = locked.dev_dependencies.as_ref().filter(|_| dev);
+    let mut deps: Vec<(String, String)> = [
+        locked.dependencies.as_ref(),
+        locked.optional_dependencies.as_ref(),
+        dev_dependencies,
+    ]
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|(name, range)| (name.clone(), range.clone()))
+    .collect();
+
+    // registry lists optional dependencies in both
+    deps.sort();
+    deps.dedup();
+
+    return deps;
+}
+
+fn build(
+    lockfile: &Lockfile,
+    from: &str,
+    name: &str,
+    range: &str,
+    depth: Option<usize>,
+    shown: &mut HashSet<String>,
+) -> TreeNode {
+    let Some((path, locked)) = find(lockfile, from, name) else {
+        return TreeNode {
+            name: name.to_string(),
+            version: range.to_string(),
+            resolved: None,
+            deduped: false,
+            missing: true,
+            dependencies: vec![],
+        };
+    };
+
+    let mut node = TreeNode {
+        name: name.to_string(),
+        version: locked.version.clone().unwrap_or_default(),
+        resolved: locked.resolved.clone(),
+        deduped: false,
+        missing: false,
+        dependencies: vec![],
+    };
+
+    if depth == Some(0) {
+        return node;
+    }
+
+    if !shown.insert(path.clone()) {
+        node.deduped = true;
+        return node;
+    }
+
+    // linked workspace resolves its dependencies from its own dir
+    let (base, locked) = match locked
+        .resolved
+        .as_ref()
+        .and_then(|resolved| resolved.strip_prefix("portal:"))
+    {
+        Some(dir) => (
+            dir.to_string(),
+            lockfile.packages.get(dir).unwrap_or(locked),
+        ),
+        None => (path, locked),
+    };
+
+    // dev dependencies of workspaces are installed too
+    let is_workspace = !base.starts_with(&format!("{NODE_MODULES}/"));
+
+    for (dependency, range) in requested(locked, is_workspace) {
+        node.dependencies.push(build(
+            lockfile,
+            &base,
+            &dependency,
+            &range,
+            depth.map(|depth| depth - 1),
+            shown,
+        ));
+    }
+
+    return node;
+}
+
+/// lockfile entry, that `name` resolves to from package at `from` path
+pub(crate) fn find<'a>(
+    lockfile: &'a Lockfile,
+    from: &str,
+    name: &str,
+) -> Option<(String, &'a LockedPackage)> {
+    let mut base = from;
+
+    loop {
+        let path = match base.is_empty() {
+            true => format!("{NODE_MODULES}/{name}"),
+            false => format!("{base}/{NODE_MODULES}/{name}"),
+        };
+
+        if let Some(locked) = lockfile.packages.get(&path) {
+            return Some((path, locked));
+        }
+
+        if base.is_empty() {
+            return None;
+        }
+
+        // `node_modules/a/node_modules/b` -> `node_modules/a` -> root
+        base = base
+            .rsplit_once(&format!("/{NODE_MODULES}/"))
+            .map_or("", |(parent, _)| parent);
+    }
+}
+
+/// keeps only matching packages and ones that lead to them
+fn filter(nodes: Vec<TreeNode>, pattern: &str) -> Vec<TreeNode> {
+    return nodes
+        .into_iter()
+        .filter_map(|mut node| {
+            node.dependencies = filter(node.dependencies, pattern);
+
+            let matches = publish::wildcard(pattern, &node.name);
+
+            (matches || !node.dependencies.is_empty()).then_some(node)
+        })
+        .collect();
+}
+
+fn print_dependencies(nodes: &[TreeNode], indent: &str) {
+    for (index, node) in nodes.iter().enumerate() {
+        let is_last = index == nodes.len() - 1;
+        let (branch, child_indent) = match is_last {
+            true => ("└── ", "    "),
+            false => ("├── ", "│   "),
+        };
+
+        let suffix = match node
+            .resolved
+            .as_ref()
+            .and_then(|resolved| resolved.strip_prefix("portal:"))
+        {
+            _ if node.missing => " (missing)".to_string(),
+            _ if node.deduped => " deduped".to_string(),
+            Some(dir) => format!(" -> ./{dir}"),
+            None => String::new(),
+        };
+
+        println!("{indent}{branch}{}@{}{suffix}", node.name, node.version);
+
+        print_dependencies(&node.dependencies, &format!("{indent}{child_indent}"));
+    }
+}
+
+/// similar to `npm ls --json`
+fn to_json(node: &TreeNode) -> Value {
+    let mut value = Map::new();
+
+    if node.missing {
+        value.insert("required".to_string(), json!(node.version));
+        value.insert("missing".to_string(), json!(true));
+    } else if !node.version.is_empty() {
+        // root package can have no version
+        value.insert("version".to_string(), json!(node.version));
+    }
+
+    if let Some(resolved) = &node.resolved {
+        value.insert("resolved".to_string(), json!(resolved));
+    }
+
+    if node.deduped {
+        value.insert("deduped".to_string(), json!(true));
+    }
+
+    if !node.dependencies.is_empty() {
+        let dependencies: Map<String, Value> = node
+            .dependencies
+            .iter()
+            .map(|dependency| (dependency.name.clone(), to_json(dependency)))
+            .collect();
+
+        value.insert("dependencies".to_string(), Value::Object(dependencies));
+    }
+
+    return Value::Object(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Manifest;
+
+    #[test]
+    fn finds_closest_installed_package() {
+        let manifest: Manifest = serde_json::from_value(json!({ "name": "root" })).unwrap();
+        let mut lockfile = Lockfile::new(&manifest);
+
+        for path in [
+            "node_modules/a",
+            "node_modules/b",
+            "node_modules/a/node_modules/b",
+            "node_modules/a/node_modules/c",
+        ] {
+            lockfile
+                .packages
+                .insert(path.to_string(), LockedPackage::default());
+        }
+
+        let found = |from: &str, name: &str| find(&lockfile, from, name).map(|(path, _)| path);
+
+        assert_eq!(
+            found("node_modules/a/node_modules/c", "b").as_deref(),
+            Some("node_modules/a/node_modules/b")
+        );
+        assert_eq!(
+            found("node_modules/b", "a").as_deref(),
+            Some("node_modules/a")
+        );
+        assert_eq!(found("node_modules/b", "c"), None);
+    }
+}
@@ -1,299 +1,743 @@
-use async_recursion::async_recursion;
-use flate2::read::GzDecoder;
-use futures::future::join_all;
-use node_semver::{Range, Version};
-use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    env, fs,
-    io::{BufReader, Cursor},
-    path::Path,
+    env,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
     sync::Arc,
 };
-use tar::Archive;
-use walkdir::WalkDir;
-use elsa::FrozenMap;
 
-use http_client::HttpClient;
+use clap::{error::ErrorKind, Args, CommandFactory, Parser, Subcommand};
+use razee::{
+    access, add, audit, cache, check, create, dedupe, deprecate, diff, dist_tag, dlx, fund,
+    github_actions,
+    graph::{self, GraphFormat},
+    http_client::HttpClient,
+    import, init, lockfile, ls, owner, parse_root_package, patch, plan, prune, publish, remove,
+    run, self_update, store, unpublish, update, watch, InstallOptions, Installer,
+};
 
-mod http_client;
-mod logger;
+/// installs dependencies of package.json, when no command is given
+#[derive(Parser)]
+#[command(name = "razee", version)]
+struct Cli {
+    /// registry to use, instead of RAZEE_REGISTRY (or npm registry)
+    #[arg(long, global = true, value_name = "url")]
+    registry: Option<String>,
 
-type DependenciesMap = HashMap<String, String>;
-type ProcessedDeps = Arc<FrozenMap<String, Box<Dep>>>;
+    /// runs as if razee was started in <path>
+    #[arg(long, global = true, value_name = "path")]
+    dir: Option<PathBuf>,
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct RegistryPackage {
-    name: String,
-    time: HashMap<String, String>,
-}
+    /// prints debug messages, same as RAZEE_DEBUG=true
+    #[arg(long, global = true)]
+    verbose: bool,
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Package {
-    name: String,
-    dependencies: Option<DependenciesMap>,
-    #[serde(rename = "devDependencies")]
-    dev_dependencies: Option<DependenciesMap>,
-}
+    #[command(flatten)]
+    install: InstallArgs,
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct DependencyDist {
-    integrity: String,
-    tarball: String,
-    #[serde(rename = "fileCount")]
-    file_count: Option<i16>,
-}
+    /// installs again, when package.json or lockfile changes
+    #[arg(long)]
+    watch: bool,
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Dependency {
-    name: String,
-    version: String,
-    dependencies: Option<DependenciesMap>,
-    #[serde(rename = "devDependencies")]
-    dev_dependencies: Option<DependenciesMap>,
-    dist: DependencyDist,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, Clone)]
-struct Dep {
-    name: String,
-    version: String,
-}
+#[derive(Args, Default, PartialEq)]
+struct InstallArgs {
+    /// fails when lockfile is out of date, instead of updating it
+    #[arg(long)]
+    frozen_lockfile: bool,
 
-const NODE_MODULES: &str = "node_modules";
+    /// prints what is going to change, and asks before installing
+    #[arg(long)]
+    preview: bool,
 
-fn parse_root_package() -> Package {
-    let mut package_path = env::current_dir().expect("cannot get current dir");
+    /// installs previewed changes without asking
+    #[arg(short, long, requires = "preview")]
+    yes: bool,
 
-    package_path.push("package.json");
+    /// checks registry signatures and provenance of packages
+    #[arg(long)]
+    verify_signatures: bool,
 
-    if !package_path.exists() {
-        panic!("no package json exists")
-    }
-
-    let package_json = fs::File::open(package_path).expect("cannot open package.json");
-    let reader = BufReader::new(package_json);
+    /// doesn't run lifecycle scripts of packages
+    #[arg(long)]
+    ignore_scripts: bool,
 
-    let package = serde_json::from_reader(reader).expect("cannot parse package.json");
-
-    return package;
-}
+    /// skips dev dependencies, same as --omit=dev
+    #[arg(long)]
+    production: bool,
 
-fn resolve_version(package: &RegistryPackage, requested_version: &Range) -> Version {
-    let dep_versions = package
-        .time
-        .keys()
-        .filter(|version| version.contains("."))
-        .map(|v| Version::parse(v).unwrap());
-
-    let satisfied_version = dep_versions
-        .clone()
-        .find(|version| requested_version.satisfies(version));
-
-    if let Some(version) = satisfied_version {
-        return version;
-    } else {
-        let versions: Vec<Version> = dep_versions.collect();
-
-        if versions.len() == 1 {
-            return versions
-                .get(0)
-                .expect("there is no versions available")
-                .clone();
-        } else {
-            return versions
-                .iter()
-                .max()
-                .unwrap_or({
-                    let msg = format!("no versions {:?}\n{:?}", package, versions);
-
-                    versions.get(0).expect(msg.as_str())
-                })
-                .clone();
-        }
-    }
-}
+    /// types of dependencies to skip, comma separated or repeated, like in npm
+    #[arg(long, value_name = "type", value_delimiter = ',', value_parser = ["dev"])]
+    omit: Vec<String>,
 
-async fn fetch_dep(dep: &Dep, client: Arc<HttpClient>) -> Dependency {
-    let package = client.fetch_package(&dep).await;
+    /// types of dependencies to install anyway, wins over --production and --omit
+    #[arg(long, value_name = "type", value_delimiter = ',', value_parser = ["dev"])]
+    include: Vec<String>,
 
-    let normalized_version;
+    /// fails on missing or mismatching peer dependencies
+    #[arg(long)]
+    strict_peer_dependencies: bool,
 
-    // TODO: export this into function
-    if dep.version.starts_with("npm") {
-        let package_or_version = dep.version.strip_prefix("npm:").unwrap();
+    /// installs missing peer dependencies
+    #[arg(long)]
+    auto_install_peers: bool,
 
-        if package_or_version.contains("@") {
-            normalized_version = package_or_version.split("@").last().unwrap();
-        } else {
-            normalized_version = package_or_version;
-        }
-    } else {
-        normalized_version = dep.version.as_str();
-    }
+    /// fails when package doesn't support current node
+    #[arg(long)]
+    engine_strict: bool,
 
-    let requested_version = Range::parse(normalized_version).expect(
-        format!(
-            "cannot parse requested version: {}:{}",
-            package.name, dep.version
-        )
-        .as_str(),
-    );
+    /// fails when packageManager of package.json is not razee
+    #[arg(long)]
+    strict: bool,
 
-    let resolved_version = resolve_version(&package, &requested_version);
+    /// max number of concurrent requests
+    #[arg(long, value_name = "count")]
+    network_concurrency: Option<usize>,
 
-    let dependency = client.fetch_dependency(&dep.name, &resolved_version).await;
+    /// max number of concurrent file system operations
+    #[arg(long, value_name = "count")]
+    fs_concurrency: Option<usize>,
 
-    return dependency.to_owned();
+    /// doesn't print funding summary
+    #[arg(long)]
+    no_fund: bool,
 }
 
-async fn download_tarball(
-    dep_name: &String,
-    dep_dist: &DependencyDist,
-    client: Arc<HttpClient>,
-) {
-    let dep_dir = format!("{NODE_MODULES}/{dep_name}");
+#[derive(Subcommand)]
+enum Command {
+    /// creates package.json in current directory
+    Init {
+        /// uses defaults, without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// installs dependencies of package.json, also runs when no command is given
+    Install {
+        #[command(flatten)]
+        install: InstallArgs,
+
+        /// installs again, when package.json or lockfile changes
+        #[arg(long)]
+        watch: bool,
+    },
+    /// installs exactly what lockfile has, from clean node_modules
+    Ci {
+        #[command(flatten)]
+        install: InstallArgs,
+    },
+    /// saves packages into package.json and installs them
+    Add {
+        /// <package>[@<range>]
+        #[arg(required = true)]
+        packages: Vec<String>,
+
+        /// saves into devDependencies
+        #[arg(short = 'D', long)]
+        save_dev: bool,
+
+        /// saves exact version, instead of ^ range
+        #[arg(short = 'E', long)]
+        save_exact: bool,
+
+        /// installs previewed changes without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// workspace to add packages to
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    /// removes packages from package.json and node_modules
+    Remove {
+        #[arg(required = true)]
+        packages: Vec<String>,
+
+        /// workspace to remove packages from
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    /// removes packages, that are not in lockfile, from node_modules
+    Prune {
+        /// removes dev dependencies too
+        #[arg(long)]
+        production: bool,
+
+        /// prints what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// collapses duplicated packages to as few versions as ranges allow
+    Dedupe {
+        /// prints what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// updates packages to the highest versions their ranges (or latest) allow
+    Update {
+        /// packages to update, all when none is given
+        packages: Vec<String>,
+
+        /// updates to latest, even when range doesn't allow it
+        #[arg(short = 'L', long)]
+        latest: bool,
+
+        /// workspace to update packages of
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    /// runs script from package.json, with its pre and post scripts, lists them without one
+    Run {
+        script: Option<String>,
+
+        /// doesn't fail, when there is no such script
+        #[arg(long, requires = "script")]
+        if_present: bool,
+
+        /// arguments after `--`, passed to script
+        #[arg(last = true, requires = "script")]
+        args: Vec<String>,
+    },
+    /// manages store of extracted packages and cache of registry metadata
+    Store {
+        #[command(subcommand)]
+        command: StoreCommand,
+    },
+    /// removes (or checks) cached registry metadata
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// reports known vulnerabilities of installed packages
+    Audit {
+        /// lowest severity that fails audit
+        #[arg(long, value_parser = audit::SEVERITIES)]
+        audit_level: Option<String>,
+    },
+    /// compares node_modules with lockfile, without changing anything
+    Check {
+        /// compares files of every package, not only of a few
+        #[arg(long)]
+        all: bool,
+    },
+    /// prints resolved dependency graph
+    Graph {
+        #[arg(long, default_value = "json", value_parser = ["dot", "json", "mermaid"])]
+        format: String,
+    },
+    /// prints tree of installed packages
+    Ls {
+        /// levels of dependencies to print, 0 when no --pattern is given
+        #[arg(long, value_name = "n")]
+        depth: Option<usize>,
+
+        /// prints only packages matching glob, with their dependents
+        #[arg(long, value_name = "glob")]
+        pattern: Option<String>,
+
+        #[arg(long)]
+        json: bool,
+    },
+    /// lists funding links of installed packages
+    Fund,
+    /// writes lockfile from packages that are already installed
+    Import {
+        #[arg(long, required = true)]
+        from_node_modules: bool,
+    },
+    /// runs create-<template> package
+    Create {
+        template: String,
+
+        /// arguments passed to template
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// runs bin of package from temp dir, without installing it into project
+    Dlx {
+        /// packages to install, instead of command's package
+        #[arg(short, long = "package", value_name = "package")]
+        packages: Vec<String>,
+
+        /// <command>[@<range>] [args...]
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// prints changes between two published versions
+    Diff {
+        /// <package>@<version>
+        from: String,
+
+        /// <package>@<version>
+        to: String,
+    },
+    /// extracts package into temp dir for editing
+    Patch { package: String },
+    /// saves edits of patched package into patch file
+    PatchCommit { dir: PathBuf },
+    /// packs and publishes package
+    Publish {
+        /// publishes every workspace
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// dist-tag to publish with
+        #[arg(long)]
+        tag: Option<String>,
+
+        #[arg(long, value_parser = ["public", "restricted"])]
+        access: Option<String>,
+
+        /// packs without publishing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// removes published version (or whole package) from registry
+    Unpublish {
+        /// <package>@<version>, or <package> with --force
+        spec: String,
+
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// marks versions as deprecated
+    Deprecate {
+        /// <package>[@<range>]
+        spec: String,
+
+        message: String,
+    },
+    /// manages dist-tags of package
+    DistTag {
+        #[command(subcommand)]
+        command: DistTagCommand,
+    },
+    /// manages owners of package
+    Owner {
+        #[command(subcommand)]
+        command: OwnerCommand,
+    },
+    /// manages visibility and team access of package
+    Access {
+        #[command(subcommand)]
+        command: AccessCommand,
+    },
+    /// replaces razee with its latest release
+    SelfUpdate,
+}
 
-    if Path::new(&dep_dir).exists() {
-        if let Some(file_count) = dep_dist.file_count {
-            let mut file_counter = 0;
+#[derive(Subcommand)]
+enum StoreCommand {
+    /// removes packages, that no project uses
+    Prune {
+        /// removes only packages unused for <days>
+        #[arg(long, value_name = "days")]
+        older_than: Option<u64>,
+    },
+    /// removes packages, that don't match their integrity
+    Verify,
+}
 
-            for entry in WalkDir::new(&dep_dir) {
-                let entry = entry.unwrap();
+#[derive(Subcommand)]
+enum CacheCommand {
+    Clean,
+    Verify,
+}
 
-                if entry.file_type().is_file() {
-                    file_counter += 1;
-                }
-            }
+#[derive(Subcommand)]
+enum DistTagCommand {
+    /// tags <package>@<version>, with latest when no tag is given
+    Add {
+        spec: String,
+        tag: Option<String>,
+    },
+    Rm {
+        package: String,
+        tag: String,
+    },
+    Ls {
+        package: Option<String>,
+    },
+}
 
-            if file_counter == file_count {
-                return;
-            }
-        }
-    }
+/// package defaults to the one in current directory
+#[derive(Subcommand)]
+enum OwnerCommand {
+    Add {
+        user: String,
+        package: Option<String>,
+    },
+    Rm {
+        user: String,
+        package: Option<String>,
+    },
+    Ls {
+        package: Option<String>,
+    },
+}
 
-    let tarball_bytes = client.fetch_tarball(dep_dist).await;
+/// package defaults to the one in current directory
+#[derive(Subcommand)]
+enum AccessCommand {
+    Get {
+        #[arg(value_parser = ["status"])]
+        what: String,
+
+        package: Option<String>,
+    },
+    Set {
+        #[arg(value_parser = ["status=public", "status=restricted"])]
+        status: String,
+
+        package: Option<String>,
+    },
+    Grant {
+        #[arg(value_parser = ["read-only", "read-write"])]
+        permissions: String,
+
+        /// <scope:team>
+        team: String,
+
+        package: Option<String>,
+    },
+    Revoke {
+        /// <scope:team>
+        team: String,
+
+        package: Option<String>,
+    },
+}
 
-    let tarball_cursor = Cursor::new(tarball_bytes);
-    let tarball = GzDecoder::new(tarball_cursor);
+/// names of flags that are set, for commands that read their own arguments
+fn flags(flags: &[(&str, bool)]) -> Vec<String> {
+    return flags
+        .iter()
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| name.to_string())
+        .collect();
+}
 
-    let mut archive = Archive::new(tarball);
+/// `<name> <value>`, when value is given
+fn option(name: &str, value: Option<impl ToString>) -> Vec<String> {
+    return match value {
+        Some(value) => vec![name.to_string(), value.to_string()],
+        None => vec![],
+    };
+}
 
-    for entry in archive.entries().unwrap() {
-        if let Ok(mut entry) = entry {
-            let mut path = entry
-                .path()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .replace("package", &dep_dir)
-                .to_owned();
+fn import() {
+    let package = parse_root_package();
+    let lockfile = import::from_node_modules(&package);
 
-            // Transforms @types/estree   estree/readme
-            //              dep_name        entry ? why not package ? idk
-            if !path.starts_with(&NODE_MODULES) {
-                path = format!("{dep_dir}/{path}");
+    for (path, locked) in lockfile.packages.iter() {
+        if !path.is_empty() && locked.integrity.is_none() {
+            println!("no integrity metadata for {path}");
+        }
+    }
 
-                let mut path_parts = path.split("/");
+    lockfile.write(Path::new(lockfile::LOCKFILE));
 
-                let mut new_path = vec![];
+    // root package is also in packages
+    println!(
+        "Imported {} packages into {}",
+        lockfile.packages.len() - 1,
+        lockfile::LOCKFILE
+    );
+}
 
-                new_path.push(path_parts.next().unwrap().to_string());
+/// `--include=dev` wins over `--production` (or `--omit=dev`), e.g. when Dockerfile sets
+/// `NODE_ENV=production`, but build still needs dev dependencies
+fn production_arg(args: &InstallArgs) -> Option<bool> {
+    if args
+        .include
+        .iter()
+        .any(|dependency_type| dependency_type == "dev")
+    {
+        return Some(false);
+    }
 
-                for part in path_parts {
-                    let prev_part = new_path.last().unwrap();
+    let production = args.production
+        || args
+            .omit
+            .iter()
+            .any(|dependency_type| dependency_type == "dev");
 
-                    if !part.eq(prev_part) {
-                        new_path.push(part.to_string());
-                    }
-                }
+    return production.then_some(true);
+}
 
-                path = new_path.join("/");
-            }
+async fn install(args: &InstallArgs) {
+    let mut client = HttpClient::new();
 
-            let mut folders: Vec<&str> = path.split("/").collect();
-            folders.pop();
+    if let Some(network_concurrency) = args.network_concurrency {
+        client = client.network_concurrency(network_concurrency);
+    }
 
-            if folders.len() > 1 {
-                fs::create_dir_all(folders.join("/")).unwrap();
-            }
+    let client = Arc::new(client);
 
-            if !Path::new(&path).exists() {
-                entry.unpack(&path).unwrap();
-            }
-        }
+    if args.preview && !plan::preview(parse_root_package(), client.clone(), args.yes).await {
+        return;
     }
-}
-
-#[async_recursion(?Send)]
-async fn process_dep(dep: &Dep, processed_deps: ProcessedDeps, client: Arc<HttpClient>) {
-    let package = fetch_dep(&dep, client.clone()).await;
-    let tarball_future = download_tarball(&package.name, &package.dist, client.clone());
 
-    logger::log_processed(&dep.name);
+    github_actions::group("Install dependencies");
+
+    let report = razee::install(InstallOptions {
+        interactive: io::stdin().is_terminal(),
+        client: Some(client),
+        frozen_lockfile: args.frozen_lockfile,
+        fs_concurrency: args.fs_concurrency,
+        verify_signatures: args.verify_signatures,
+        ignore_scripts: args.ignore_scripts,
+        production: production_arg(args),
+        strict_peer_dependencies: args.strict_peer_dependencies,
+        auto_install_peers: args.auto_install_peers,
+        engine_strict: args.engine_strict,
+        strict_package_manager: args.strict,
+        ..Default::default()
+    })
+    .await;
 
-    processed_deps.insert(dep.name.clone(), Box::new(dep.to_owned()));
+    github_actions::end_group();
 
-    let mut needs_processing = vec![];
+    println!(
+        "Fetched {} packages in {:.2}s",
+        report.graph.len(),
+        report.timings.total.as_secs_f64()
+    );
 
-    if let Some(deps) = package.dependencies {
-        for (k, v) in deps.iter() {
-            if processed_deps.get(k).is_none() {
-                needs_processing.push(Dep {
-                    name: k.to_owned(),
-                    version: v.to_owned(),
-                });
-            }
+    if !args.no_fund {
+        if let Some(summary) = fund::summary(&report.graph) {
+            println!("{summary}");
         }
     }
-
-    tarball_future.await;
-
-    join_all(
-        needs_processing
-            .iter()
-            .map(|dep| process_dep(dep, processed_deps.clone(), client.clone()))
-            .collect::<Vec<_>>(),
-    )
-    .await;
 }
 
 #[tokio::main]
 async fn main() {
-    let package = parse_root_package();
-
-    let mut needs_processing = vec![];
-    let processed_deps: ProcessedDeps = Arc::new(FrozenMap::new());
-
-    if let Some(normal_deps) = package.dependencies {
-        normal_deps.into_iter().for_each(|(name, version)| {
-            let dep = Dep { name, version };
-
-            needs_processing.push(dep);
-        });
+    github_actions::install_panic_hook();
+
+    let cli = Cli::parse();
+
+    // install options belong to install, `razee --frozen-lockfile add` would drop them
+    if cli.command.is_some() && (cli.watch || cli.install != InstallArgs::default()) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "install options can't be given before other command",
+            )
+            .exit();
     }
 
-    if let Some(dev_deps) = package.dev_dependencies {
-        dev_deps.into_iter().for_each(|(name, version)| {
-            let dep = Dep { name, version };
+    if let Some(registry) = &cli.registry {
+        env::set_var("RAZEE_REGISTRY", registry);
+    }
 
-            needs_processing.push(dep);
-        });
+    if let Some(dir) = &cli.dir {
+        env::set_current_dir(dir)
+            .expect(format!("cannot change dir to {}", dir.display()).as_str());
     }
 
-    println!();
+    if cli.verbose {
+        env::set_var("RAZEE_DEBUG", "true");
+    }
 
-    // let http_client = Arc::new(Mutex::new(HttpClient::new()));
-    let http_client = Arc::new(HttpClient::new());
+    let command = cli.command.unwrap_or(Command::Install {
+        install: cli.install,
+        watch: cli.watch,
+    });
+
+    match command {
+        Command::Install {
+            install: args,
+            watch: true,
+        } => watch::watch(|| install(&args)).await,
+        Command::Install { install: args, .. } => install(&args).await,
+        Command::Ci { install: args } => {
+            install(&InstallArgs {
+                frozen_lockfile: true,
+                ..args
+            })
+            .await
+        }
+        Command::Deprecate { spec, message } => deprecate::deprecate(&[spec, message]).await,
+        Command::DistTag { command } => {
+            let args = match command {
+                DistTagCommand::Add { spec, tag } => {
+                    [vec!["add".to_string(), spec], tag.into_iter().collect()]
+                }
+                DistTagCommand::Rm { package, tag } => {
+                    [vec!["rm".to_string(), package, tag], vec![]]
+                }
+                DistTagCommand::Ls { package } => {
+                    [vec!["ls".to_string()], package.into_iter().collect()]
+                }
+            };
 
-    join_all(
-        needs_processing
-            .iter()
-            .map(|dep| process_dep(dep, processed_deps.clone(), http_client.clone()))
-            .collect::<Vec<_>>(),
-    )
-    .await;
+            dist_tag::dist_tag(&args.concat()).await;
+        }
+        Command::Fund => fund::fund(),
+        Command::Ls {
+            depth,
+            pattern,
+            json,
+        } => ls::ls(
+            &[
+                option("--depth", depth),
+                option("--pattern", pattern),
+                flags(&[("--json", json)]),
+            ]
+            .concat(),
+        ),
+        Command::Graph { format } => {
+            let format = GraphFormat::parse(&format).expect("format is checked by clap");
+            let graph = Installer::new(parse_root_package()).resolve().await;
+
+            print!("{}", graph::export(&graph, format));
+        }
+        Command::Import { .. } => import(),
+        Command::Init { yes } => init::init(&flags(&[("--yes", yes)])),
+        Command::Add {
+            packages,
+            save_dev,
+            save_exact,
+            yes,
+            workspace,
+        } => {
+            let flags = flags(&[
+                ("--save-dev", save_dev),
+                ("--save-exact", save_exact),
+                ("--yes", yes),
+            ]);
+
+            add::add(&[packages, flags, option("--workspace", workspace)].concat()).await;
+        }
+        Command::Audit { audit_level } => audit::audit(&option("--audit-level", audit_level)).await,
+        Command::Cache { command } => match command {
+            CacheCommand::Clean => cache::cache(&["clean".to_string()]),
+            CacheCommand::Verify => cache::cache(&["verify".to_string()]),
+        },
+        Command::Access { command } => {
+            let args = match command {
+                AccessCommand::Get { what, package } => {
+                    vec![vec!["get".to_string(), what], package.into_iter().collect()]
+                }
+                AccessCommand::Set { status, package } => vec![
+                    vec!["set".to_string(), status],
+                    package.into_iter().collect(),
+                ],
+                AccessCommand::Grant {
+                    permissions,
+                    team,
+                    package,
+                } => vec![
+                    vec!["grant".to_string(), permissions, team],
+                    package.into_iter().collect(),
+                ],
+                AccessCommand::Revoke { team, package } => vec![
+                    vec!["revoke".to_string(), team],
+                    package.into_iter().collect(),
+                ],
+            };
+
+            access::access(&args.concat()).await;
+        }
+        Command::Check { all } => check::check(&flags(&[("--all", all)])).await,
+        Command::Create { template, args } => create::create(&template, &args).await,
+        Command::Dedupe { dry_run } => dedupe::dedupe(&flags(&[("--dry-run", dry_run)])).await,
+        Command::Dlx { packages, command } => {
+            let packages = packages
+                .into_iter()
+                .flat_map(|package| option("--package", Some(package)));
+
+            dlx::dlx(&packages.chain(command).collect::<Vec<_>>()).await;
+        }
+        Command::Diff { from, to } => diff::diff(&from, &to, &HttpClient::new()).await,
+        Command::Owner { command } => {
+            let args = match command {
+                OwnerCommand::Add { user, package } => {
+                    [vec!["add".to_string(), user], package.into_iter().collect()]
+                }
+                OwnerCommand::Rm { user, package } => {
+                    [vec!["rm".to_string(), user], package.into_iter().collect()]
+                }
+                OwnerCommand::Ls { package } => {
+                    [vec!["ls".to_string()], package.into_iter().collect()]
+                }
+            };
 
-    println!("Fetched {} packages", processed_deps.len());
-    // println!("{:?}", processed);
+            owner::owner(&args.concat()).await;
+        }
+        Command::Patch { package } => patch::start(&package, &HttpClient::new()).await,
+        Command::PatchCommit { dir } => patch::commit(&dir),
+        Command::Prune {
+            production,
+            dry_run,
+        } => prune::prune(&flags(&[
+            ("--production", production),
+            ("--dry-run", dry_run),
+        ])),
+        Command::Publish {
+            recursive,
+            tag,
+            access,
+            dry_run,
+        } => {
+            let args = [
+                flags(&[("--recursive", recursive), ("--dry-run", dry_run)]),
+                option("--tag", tag),
+                option("--access", access),
+            ];
+
+            publish::publish(&args.concat()).await;
+        }
+        Command::Remove {
+            packages,
+            workspace,
+        } => remove::remove(&[packages, option("--workspace", workspace)].concat()).await,
+        Command::Run {
+            script,
+            if_present,
+            args,
+        } => {
+            let script_args = match args.is_empty() {
+                true => vec![],
+                false => [vec!["--".to_string()], args].concat(),
+            };
+
+            let args = [
+                script.into_iter().collect(),
+                flags(&[("--if-present", if_present)]),
+                script_args,
+            ];
+
+            run::run(&args.concat());
+        }
+        Command::SelfUpdate => self_update::self_update().await,
+        Command::Store { command } => match command {
+            StoreCommand::Prune { older_than } => store::store(
+                &[
+                    vec!["prune".to_string()],
+                    option("--older-than", older_than),
+                ]
+                .concat(),
+            ),
+            StoreCommand::Verify => store::store(&["verify".to_string()]),
+        },
+        Command::Unpublish { spec, force } => {
+            unpublish::unpublish(&[vec![spec], flags(&[("--force", force)])].concat()).await
+        }
+        Command::Update {
+            packages,
+            latest,
+            workspace,
+        } => {
+            let args = [
+                packages,
+                flags(&[("--latest", latest)]),
+                option("--workspace", workspace),
+            ];
+
+            update::update(&args.concat()).await;
+        }
+    }
 }
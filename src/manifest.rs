@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::DependenciesMap;
+
+pub const PACKAGE_JSON: &str = "package.json";
+
+/// root package.json, only fields razee cares about
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    pub version: Option<String>,
+    pub dependencies: Option<DependenciesMap>,
+    #[serde(rename = "devDependencies")]
+    pub dev_dependencies: Option<DependenciesMap>,
+    /// installed like dependencies, but their failures are only reported, they win over
+    /// `dependencies` with the same name
+    #[serde(rename = "optionalDependencies")]
+    pub optional_dependencies: Option<DependenciesMap>,
+    /// "name@version" -> path to patch file
+    #[serde(rename = "patchedDependencies")]
+    pub patched_dependencies: Option<DependenciesMap>,
+    /// package name -> version that is used instead of whatever dependents request
+    pub overrides: Option<HashMap<String, Value>>,
+    /// yarn alternative to `overrides`: pattern like `parent/**/name` -> version
+    pub resolutions: Option<DependenciesMap>,
+    /// patterns of workspace package dirs, see [`workspaces::patterns`](crate::workspaces::patterns)
+    pub workspaces: Option<Value>,
+    /// packages, that can run install scripts, see
+    /// [`Installer::trusted_dependencies`](crate::Installer::trusted_dependencies)
+    #[serde(rename = "trustedDependencies")]
+    pub trusted_dependencies: Option<Vec<String>>,
+    /// node versions project supports, checked before install
+    pub engines: Option<Value>,
+    /// `<name>@<version>`, that project is installed with, e.g. `razee@0.4.0`
+    #[serde(rename = "packageManager")]
+    pub package_manager: Option<String>,
+}
+
+/// reads package.json from current directory
+pub fn parse_root_package() -> Manifest {
+    let mut package_path = env::current_dir().expect("cannot get current dir");
+
+    package_path.push("package.json");
+
+    if !package_path.exists() {
+        panic!("no package json exists")
+    }
+
+    let package_json = fs::File::open(package_path).expect("cannot open package.json");
+    let reader = BufReader::new(package_json);
+
+    let package = serde_json::from_reader(reader).expect("cannot parse package.json");
+
+    return package;
+}
+
+/// reads package.json as plain json, so it can be edited and written back
+/// without losing fields razee doesn't know about
+pub fn read_raw(path: &Path) -> Value {
+    let file = fs::File::open(path).expect(format!("cannot open {}", path.display()).as_str());
+
+    return serde_json::from_reader(BufReader::new(file))
+        .expect(format!("cannot parse {}", path.display()).as_str());
+}
+
+pub fn write_raw(path: &Path, manifest: &Value) {
+    let file = fs::File::create(path).expect(format!("cannot write {}", path.display()).as_str());
+    let mut writer = BufWriter::new(file);
+
+    serde_json::to_writer_pretty(&mut writer, manifest).expect("cannot serialize package.json");
+    writer.write_all(b"\n").expect("cannot write package.json");
+}
+
+/// sets `manifest[section][key] = value`, creating section if needed
+pub fn set_entry(manifest: &mut Value, section: &str, key: &str, value: Value) {
+    let root = manifest
+        .as_object_mut()
+        .expect("package.json is not an object");
+
+    let section = root
+        .entry(section)
+        .or_insert_with(|| Value::Object(Default::default()))
+        .as_object_mut()
+        .expect(format!("\"{section}\" in package.json is not an object").as_str());
+
+    section.insert(key.to_string(), value);
+}
+
+/// removes `manifest[section][key]`, and section too, when it is left empty,
+/// returns whether there was such entry
+pub fn remove_entry(manifest: &mut Value, section: &str, key: &str) -> bool {
+    let Some(root) = manifest.as_object_mut() else {
+        return false;
+    };
+
+    let Some(entries) = root.get_mut(section).and_then(Value::as_object_mut) else {
+        return false;
+    };
+
+    let removed = entries.remove(key).is_some();
+
+    if entries.is_empty() {
+        root.remove(section);
+    }
+
+    return removed;
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::fs_retry::retry;
+
+/// registry documents (packuments) saved between runs, every document is kept with its
+/// `ETag`, so it is only transferred again, when registry has changed it
+#[derive(Debug)]
+pub(crate) struct MetadataCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CachedResponse {
+    pub etag: String,
+    pub body: String,
+}
+
+impl MetadataCache {
+    pub(crate) fn new(dir: PathBuf) -> MetadataCache {
+        return MetadataCache { dir };
+    }
+
+    /// `RAZEE_CACHE_DIR`, defaults to `~/.razee/metadata`, `RAZEE_CACHE=false` disables
+    /// cache, every document is downloaded in full then
+    pub(crate) fn from_env() -> Option<MetadataCache> {
+        if env::var("RAZEE_CACHE").is_ok_and(|cache| cache == "false" || cache == "0") {
+            return None;
+        }
+
+        if let Some(dir) = env::var_os("RAZEE_CACHE_DIR") {
+            return Some(MetadataCache::new(PathBuf::from(dir)));
+        }
+
+        let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+
+        return Some(MetadataCache::new(
+            PathBuf::from(home).join(".razee").join("metadata"),
+        ));
+    }
+
+    /// `key` is url, with whatever else changes response (e.g. accepted type)
+    pub(crate) fn get(&self, key: &str) -> Option<CachedResponse> {
+        let cached = fs::read_to_string(self.path(key)).ok()?;
+
+        return serde_json::from_str(&cached).ok();
+    }
+
+    /// failures are only reported, document just won't be revalidated next time
+    pub(crate) fn put(&self, key: &str, etag: &str, body: &str) {
+        let cached = CachedResponse {
+            etag: etag.to_string(),
+            body: body.to_string(),
+        };
+
+        if let Err(error) = self.write(&self.path(key), &cached) {
+            eprintln!("warning: cannot cache {key}: {error}");
+        }
+    }
+
+    /// removes entries, that are not valid json (e.g. damaged by disk issues), returns
+    /// how many entries were checked and removed
+    pub(crate) fn verify(&self) -> (usize, usize) {
+        let mut checked = 0;
+        let mut removed = 0;
+
+        for entry in fs::read_dir(&self.dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+
+            checked += 1;
+
+            let is_valid = fs::read_to_string(&path).is_ok_and(|cached| {
+                serde_json::from_str::<CachedResponse>(&cached).is_ok_and(|cached| {
+                    serde_json::from_str::<serde_json::Value>(&cached.body).is_ok()
+                })
+            });
+
+            if is_valid {
+                continue;
+            }
+
+            match retry(&path, || fs::remove_file(&path)) {
+                Ok(()) => println!("{}: corrupted metadata, removed", path.display()),
+                Err(error) => eprintln!("warning: cannot remove corrupted metadata: {error}"),
+            }
+
+            removed += 1;
+        }
+
+        return (checked, removed);
+    }
+
+    /// removes every entry, returns how many were removed
+    pub(crate) fn clean(&self) -> usize {
+        let mut removed = 0;
+
+        for entry in fs::read_dir(&self.dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+
+            match retry(&path, || fs::remove_file(&path)) {
+                Ok(()) => removed += 1,
+                Err(error) => eprintln!("warning: cannot remove {}: {error}", path.display()),
+            }
+        }
+
+        return removed;
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        return &self.dir;
+    }
+
+    /// urls can't be file names, so entries are named by hash of the key
+    fn path(&self, key: &str) -> PathBuf {
+        let hash: String = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        return self.dir.join(format!("{hash}.json"));
+    }
+
+    /// entry is written aside and moved in place, so other processes never read half of it
+    fn write(&self, path: &Path, cached: &CachedResponse) -> io::Result<()> {
+        retry(&self.dir, || fs::create_dir_all(&self.dir))?;
+
+        let temp_path = path.with_extension(format!("{}.tmp", process::id()));
+        let content = serde_json::to_string(cached)?;
+
+        retry(&temp_path, || fs::write(&temp_path, &content))?;
+
+        return retry(path, || fs::rename(&temp_path, path));
+    }
+}
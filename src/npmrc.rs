@@ -0,0 +1,186 @@
+use std::{collections::HashMap, env, fs, path::PathBuf, sync::OnceLock};
+
+pub(crate) const NPMRC: &str = ".npmrc";
+
+/// merged `.npmrc` files, keys of project one win over user ones, which win over global
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Npmrc(HashMap<String, String>);
+
+impl Npmrc {
+    /// configs are read once per run, they are looked up on every request
+    pub(crate) fn load() -> &'static Npmrc {
+        static NPMRC: OnceLock<Npmrc> = OnceLock::new();
+
+        return NPMRC.get_or_init(Npmrc::read);
+    }
+
+    /// global (`$PREFIX/etc/npmrc`), user (`~/.npmrc`) and project (`./.npmrc`) configs,
+    /// `NPM_CONFIG_GLOBALCONFIG`, `NPM_CONFIG_PREFIX` and `NPM_CONFIG_USERCONFIG` point
+    /// to other files, like they do for npm
+    fn read() -> Npmrc {
+        let mut config = HashMap::new();
+
+        for path in config_paths() {
+            if let Ok(content) = fs::read_to_string(&path) {
+                config.extend(parse(&content));
+            }
+        }
+
+        return Npmrc(config);
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        return self.0.get(key).map(String::as_str);
+    }
+
+    /// `@scope:registry=<url>` keys, as `(@scope, url)`
+    pub(crate) fn scoped_registries(&self) -> Vec<(&str, &str)> {
+        return self
+            .0
+            .iter()
+            .filter_map(|(key, url)| Some((key.strip_suffix(":registry")?, url.as_str())))
+            .filter(|(scope, _)| scope.starts_with("@"))
+            .collect();
+    }
+
+    /// `//host/path/:_authToken=<token>`, that is the closest to `url`, npm scopes tokens
+    /// by registry url without scheme, so they are sent only there
+    pub(crate) fn auth_token_for(&self, url: &str) -> Option<&str> {
+        let (_, location) = url.split_once("//")?;
+        let location = format!("//{location}");
+
+        return self
+            .0
+            .iter()
+            .filter_map(|(key, token)| Some((key.strip_suffix(":_authToken")?, token)))
+            .filter(|(prefix, _)| prefix.ends_with("/") && location.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, token)| token.as_str())
+            .filter(|token| !token.trim().is_empty());
+    }
+}
+
+/// `key` of .npmrc files
+pub(crate) fn get(key: &str) -> Option<String> {
+    return Npmrc::load().get(key).map(String::from);
+}
+
+/// `env_name` (`true` or `1`), or `key` of .npmrc, when env var is not set
+pub(crate) fn flag(env_name: &str, key: &str) -> bool {
+    if let Ok(value) = env::var(env_name) {
+        return value == "true" || value == "1";
+    }
+
+    return get(key).is_some_and(|value| value == "true");
+}
+
+fn config_paths() -> Vec<PathBuf> {
+    let global = npm_config("GLOBALCONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            npm_config("PREFIX")
+                .map_or(PathBuf::from("/usr/local"), PathBuf::from)
+                .join("etc")
+                .join("npmrc")
+        });
+    let user = npm_config("USERCONFIG").map(PathBuf::from).or_else(|| {
+        let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+
+        return Some(PathBuf::from(home).join(NPMRC));
+    });
+
+    return [Some(global), user, Some(PathBuf::from(NPMRC))]
+        .into_iter()
+        .flatten()
+        .collect();
+}
+
+/// npm reads its env vars in any case, e.g. `npm_config_userconfig`
+fn npm_config(name: &str) -> Option<String> {
+    return env::var(format!("NPM_CONFIG_{name}"))
+        .or_else(|_| env::var(format!("npm_config_{}", name.to_lowercase())))
+        .ok()
+        .filter(|value| !value.is_empty());
+}
+
+/// `key=value` lines of ini file, comments (`#` or `;`) and sections are skipped
+fn parse(content: &str) -> Vec<(String, String)> {
+    return content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with(['#', ';', '[']))
+        .filter_map(|line| line.split_once("="))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix("\"")
+                .and_then(|value| value.strip_suffix("\""))
+                .unwrap_or(value);
+
+            (expand(key.trim()), expand(value))
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .collect();
+}
+
+/// replaces `${NAME}` with env var, so tokens are not written into committed .npmrc,
+/// missing vars are replaced with nothing
+fn expand(value: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+
+    while let Some((before, after)) = rest.split_once("${") {
+        let Some((name, after)) = after.split_once("}") else {
+            break;
+        };
+
+        expanded.push_str(before);
+        expanded.push_str(&env::var(name.trim_end_matches("?")).unwrap_or_default());
+        rest = after;
+    }
+
+    expanded.push_str(rest);
+
+    return expanded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_npmrc() {
+        env::set_var("RAZEE_TEST_NPMRC_TOKEN", "secret");
+
+        let npmrc = Npmrc(HashMap::from_iter(parse(
+            "# comment\n\
+            registry = https://registry.example/\n\
+            ; other comment\n\
+            @corp:registry=\"https://npm.corp.example\"\n\
+            //npm.corp.example/:_authToken=${RAZEE_TEST_NPMRC_TOKEN}\n\
+            //npm.corp.example/private/:_authToken=private\n\
+            [section]\n\
+            ignore-scripts=true",
+        )));
+
+        assert_eq!(npmrc.get("registry"), Some("https://registry.example/"));
+        assert_eq!(npmrc.get("//npm.corp.example/:_authToken"), Some("secret"));
+        assert_eq!(npmrc.get("ignore-scripts"), Some("true"));
+        assert_eq!(
+            npmrc.auth_token_for("https://npm.corp.example/@corp/lib"),
+            Some("secret")
+        );
+        assert_eq!(
+            npmrc.auth_token_for("https://npm.corp.example/private/lib"),
+            Some("private")
+        );
+        assert_eq!(
+            npmrc.auth_token_for("https://npm.corp.example.evil/lib"),
+            None
+        );
+        assert_eq!(
+            npmrc.scoped_registries(),
+            [("@corp", "https://npm.corp.example")]
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use node_semver::{Range, Version};
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::{manifest::PACKAGE_JSON, Dep, Manifest};
+
+/// rule of `overrides` in package.json: package it selects (`name` or `name@range`),
+/// version it is replaced with and rules, that apply only under that package
+#[derive(Debug)]
+struct Override {
+    name: String,
+    range: Option<Range>,
+    /// `"."` of object rules, they can also only select where nested rules apply
+    version: Option<String>,
+    children: Vec<Arc<Override>>,
+    /// applies to any package under the one, it is nested under, not only to its direct
+    /// dependencies, only yarn's `parent/child` isn't
+    deep: bool,
+}
+
+/// rules, that apply to dependencies of some package, most nested first, so
+/// `{ "a": { "b": "2" }, "b": "1" }` gives `b@2` to `a` and `b@1` to everything else
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Overrides(Vec<Arc<Override>>);
+
+impl Overrides {
+    /// npm `overrides` of root package, keys can also be yarn like `parent > child`
+    /// selectors, values can reference version of root dependency with `$name`, then
+    /// yarn `resolutions`, e.g. `**/foo`, `parent/foo` or `parent/**/foo`
+    pub(crate) fn from_manifest(manifest: &Manifest) -> Overrides {
+        let overrides = manifest
+            .overrides
+            .iter()
+            .flatten()
+            .map(|(key, value)| parse_rule(key, value, manifest));
+        let resolutions = manifest
+            .resolutions
+            .iter()
+            .flatten()
+            .map(|(pattern, version)| parse_resolution(pattern, version));
+
+        return Overrides(overrides.chain(resolutions).collect());
+    }
+
+    /// `dep` with version its override asks for, as it is without one
+    pub(crate) fn apply(&self, dep: &Dep) -> Dep {
+        return Dep {
+            name: dep.name.clone(),
+            version: self
+                .version_for(&dep.name, &dep.version)
+                .unwrap_or(&dep.version)
+                .to_string(),
+        };
+    }
+
+    /// version `name`, requested with `range`, is replaced with
+    pub(crate) fn version_for(&self, name: &str, range: &str) -> Option<&str> {
+        return self
+            .0
+            .iter()
+            .filter(|rule| rule.name == name)
+            .filter(|rule| match &rule.range {
+                None => true,
+                Some(selector) => {
+                    Range::parse(range).is_ok_and(|range| range.intersect(selector).is_some())
+                }
+            })
+            .find_map(|rule| rule.version.as_deref());
+    }
+
+    /// rules for dependencies of installed `name@version`
+    pub(crate) fn enter(&self, name: &str, version: &str) -> Overrides {
+        let version = Version::parse(version).ok();
+        let mut rules: Vec<Arc<Override>> = self
+            .0
+            .iter()
+            .filter(|rule| rule.name == name && !rule.children.is_empty())
+            .filter(|rule| match (&rule.range, &version) {
+                (Some(selector), Some(version)) => selector.satisfies(version),
+                _ => true,
+            })
+            .flat_map(|rule| rule.children.iter().cloned())
+            .collect();
+
+        rules.extend(self.0.iter().filter(|rule| rule.deep).cloned());
+
+        return Overrides(rules);
+    }
+}
+
+fn parse_rule(key: &str, value: &Value, manifest: &Manifest) -> Arc<Override> {
+    // `a > b > c` is the same as `{ "a": { "b": { "c": value } } }`, only spaced `>`
+    // separates packages, `b@>=2` is a range
+    if let Some((parent, child)) = key.split_once(" > ") {
+        let (name, range) = parse_selector(parent.trim());
+
+        return Arc::new(Override {
+            name,
+            range,
+            version: None,
+            children: vec![parse_rule(child.trim(), value, manifest)],
+            deep: true,
+        });
+    }
+
+    let (name, range) = parse_selector(key);
+    let (version, children) = match value {
+        Value::String(version) => (Some(resolve_reference(version, manifest)), vec![]),
+        Value::Object(rules) => (
+            rules
+                .get(".")
+                .and_then(Value::as_str)
+                .map(|version| resolve_reference(version, manifest)),
+            rules
+                .iter()
+                .filter(|(key, _)| *key != ".")
+                .map(|(key, value)| parse_rule(key, value, manifest))
+                .collect(),
+        ),
+        _ => panic!("override of {key} in {PACKAGE_JSON} must be a version or an object"),
+    };
+
+    return Arc::new(Override {
+        name,
+        range,
+        version,
+        children,
+        deep: true,
+    });
+}
+
+/// yarn pattern is a path of package names, where `**` stands for any packages in between,
+/// so rule of every name is nested under the rule of previous one
+fn parse_resolution(pattern: &str, version: &str) -> Arc<Override> {
+    let mut names = vec![];
+    let mut parts = pattern.split("/");
+
+    while let Some(part) = parts.next() {
+        match part.starts_with("@") {
+            true => names.push(format!("{part}/{}", parts.next().unwrap_or_default())),
+            false => names.push(part.to_string()),
+        }
+    }
+
+    let mut rule = None;
+
+    // built from the package, that is overridden, up to the first one in pattern
+    for (index, name) in names.iter().enumerate().rev() {
+        if name == "**" {
+            continue;
+        }
+
+        let (name, range) = parse_selector(name);
+
+        rule = Some(Arc::new(Override {
+            name,
+            range,
+            version: rule.is_none().then(|| version.to_string()),
+            children: rule.into_iter().collect(),
+            // plain `name` is anywhere, otherwise first package of pattern is dependency
+            // of root, and every next one of previous one
+            deep: names.len() == 1 || index > 0 && names[index - 1] == "**",
+        }));
+    }
+
+    return rule.expect(format!("resolution {pattern} doesn't select any package").as_str());
+}
+
+/// `name` or `name@range`, scoped names start with `@` too
+fn parse_selector(selector: &str) -> (String, Option<Range>) {
+    let Some((name, range)) = selector
+        .rsplit_once("@")
+        .filter(|(name, _)| !name.is_empty())
+    else {
+        return (selector.to_string(), None);
+    };
+
+    let range = Range::parse(range)
+        .expect(format!("cannot parse override {selector} in {PACKAGE_JSON}").as_str());
+
+    return (name.to_string(), Some(range));
+}
+
+/// `$name` is the version root package requests `name` with
+fn resolve_reference(version: &str, manifest: &Manifest) -> String {
+    let Some(name) = version.strip_prefix("$") else {
+        return version.to_string();
+    };
+
+    return [
+        &manifest.dependencies,
+        &manifest.dev_dependencies,
+        &manifest.optional_dependencies,
+    ]
+    .into_iter()
+    .find_map(|deps| deps.as_ref()?.get(name))
+    .expect(format!("override references ${name}, but {name} is not a dependency").as_str())
+    .clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn applies_nested_overrides() {
+        let manifest: Manifest = serde_json::from_value(json!({
+            "name": "app",
+            "dependencies": { "c": "^3.1.0" },
+            "overrides": {
+                "b": "1.0.0",
+                "a": { "b": "2.0.0" },
+                "d@1 > b": "3.0.0",
+                "c": "$c",
+            },
+        }))
+        .unwrap();
+        let overrides = Overrides::from_manifest(&manifest);
+
+        assert_eq!(overrides.version_for("b", "^0.1.0"), Some("1.0.0"));
+        assert_eq!(overrides.version_for("c", "*"), Some("^3.1.0"));
+        assert_eq!(
+            overrides.enter("a", "1.0.0").version_for("b", "*"),
+            Some("2.0.0")
+        );
+        assert_eq!(
+            overrides.enter("d", "1.2.0").version_for("b", "*"),
+            Some("3.0.0")
+        );
+        assert_eq!(
+            overrides.enter("d", "2.0.0").version_for("b", "*"),
+            Some("1.0.0")
+        );
+        assert_eq!(overrides.version_for("a", "*"), None);
+    }
+
+    #[test]
+    fn ranges_of_selectors_can_have_comparators() {
+        let manifest: Manifest = serde_json::from_value(json!({
+            "name": "app",
+            "overrides": {
+                "b@>=2.0.0": "2.1.0",
+                "d@>=1 > b": "3.0.0",
+            },
+        }))
+        .unwrap();
+        let overrides = Overrides::from_manifest(&manifest);
+
+        assert_eq!(overrides.version_for("b", "^2.0.0"), Some("2.1.0"));
+        assert_eq!(overrides.version_for("b", "^1.0.0"), None);
+        assert_eq!(
+            overrides.enter("d", "1.2.0").version_for("b", "^1.0.0"),
+            Some("3.0.0")
+        );
+        assert_eq!(
+            overrides.enter("d", "0.9.0").version_for("b", "^1.0.0"),
+            None
+        );
+    }
+
+    #[test]
+    fn applies_resolutions() {
+        let manifest: Manifest = serde_json::from_value(json!({
+            "name": "app",
+            "resolutions": {
+                "**/b": "1.0.0",
+                "a/c": "2.0.0",
+                "@scope/d/**/c": "3.0.0",
+            },
+        }))
+        .unwrap();
+        let overrides = Overrides::from_manifest(&manifest);
+
+        assert_eq!(overrides.version_for("b", "*"), Some("1.0.0"));
+        assert_eq!(overrides.version_for("c", "*"), None);
+
+        let under_a = overrides.enter("a", "1.0.0");
+
+        assert_eq!(under_a.version_for("c", "*"), Some("2.0.0"));
+        // only direct dependencies of a
+        assert_eq!(under_a.enter("e", "1.0.0").version_for("c", "*"), None);
+        assert_eq!(
+            under_a.enter("e", "1.0.0").version_for("b", "*"),
+            Some("1.0.0")
+        );
+
+        let under_d = overrides.enter("@scope/d", "1.0.0").enter("e", "1.0.0");
+
+        assert_eq!(under_d.version_for("c", "*"), Some("3.0.0"));
+    }
+}
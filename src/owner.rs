@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+use crate::{parse_root_package, registry::Registry, registry_api::RegistryApi};
+
+/// `razee owner add <user> [pkg]`, `rm <user> [pkg]` and `ls [pkg]`, package
+/// defaults to the one in current directory
+pub async fn owner(args: &[String]) {
+    let usage = "usage: razee owner add <user> [package] | rm <user> [package] | ls [package]";
+    let api = RegistryApi::new(&Registry::from_env());
+
+    let package_arg = |index: usize| match args.get(index) {
+        Some(name) => name.to_owned(),
+        None => parse_root_package().name,
+    };
+
+    match args.first().map(|command| command.as_str()) {
+        Some("ls") => {
+            let package_name = package_arg(1);
+            let packument = api.packument(&package_name).await;
+
+            for maintainer in maintainers(&packument) {
+                let name = maintainer
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("?");
+
+                match maintainer.get("email").and_then(Value::as_str) {
+                    Some(email) => println!("{name} <{email}>"),
+                    None => println!("{name}"),
+                }
+            }
+        }
+        Some("add") => {
+            let username = args.get(1).expect(usage);
+            let package_name = package_arg(2);
+
+            let mut packument = api.packument(&package_name).await;
+            let mut owners = maintainers(&packument);
+
+            if owners.iter().any(|owner| is_user(owner, username)) {
+                println!("{username} already maintains {package_name}");
+                return;
+            }
+
+            let user = api.user(username).await;
+
+            owners.push(serde_json::json!({
+                "name": username,
+                "email": user.get("email").cloned().unwrap_or(Value::Null),
+            }));
+
+            packument["maintainers"] = Value::Array(owners);
+            api.update_packument(&package_name, &packument).await;
+
+            println!("+ {username} ({package_name})");
+        }
+        Some("rm") => {
+            let username = args.get(1).expect(usage);
+            let package_name = package_arg(2);
+
+            let mut packument = api.packument(&package_name).await;
+            let mut owners = maintainers(&packument);
+            let owners_before = owners.len();
+
+            owners.retain(|owner| !is_user(owner, username));
+
+            if owners.len() == owners_before {
+                println!("{username} does not maintain {package_name}");
+                return;
+            }
+
+            if owners.is_empty() {
+                panic!("cannot remove {username}: package {package_name} must have at least one maintainer");
+            }
+
+            packument["maintainers"] = Value::Array(owners);
+            api.update_packument(&package_name, &packument).await;
+
+            println!("- {username} ({package_name})");
+        }
+        _ => panic!("{usage}"),
+    }
+}
+
+fn maintainers(packument: &Value) -> Vec<Value> {
+    return packument
+        .get("maintainers")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+}
+
+fn is_user(maintainer: &Value, username: &str) -> bool {
+    return maintainer.get("name").and_then(Value::as_str) == Some(username);
+}
@@ -0,0 +1,67 @@
+use node_semver::{Range, Version};
+
+use crate::{github_actions, manifest::PACKAGE_JSON};
+
+/// `packageManager` of package.json, like corepack reads it: `<name>@<version>[+<hash>]`,
+/// other managers write lockfiles of their own, so installing with razee is reported, and
+/// refused when `strict`, `razee@<version>` pins version of razee instead
+pub(crate) fn check(package_manager: Option<&str>, strict: bool) {
+    let Some(mismatch) = package_manager
+        .and_then(|package_manager| mismatch(package_manager, env!("CARGO_PKG_VERSION")))
+    else {
+        return;
+    };
+
+    match strict {
+        true => panic!("{mismatch}"),
+        false => github_actions::warning(&mismatch),
+    }
+}
+
+/// why razee at `current` version is not the manager `package_manager` asks for, `None`
+/// when it is
+fn mismatch(package_manager: &str, current: &str) -> Option<String> {
+    let spec = package_manager.split("+").next().unwrap_or_default().trim();
+    let (name, version) = match spec.rsplit_once("@") {
+        Some((name, version)) if !name.is_empty() => (name, Some(version)),
+        _ => (spec, None),
+    };
+
+    if name != "razee" {
+        return Some(format!(
+            "{PACKAGE_JSON} asks for {spec} as package manager, lockfile of razee can differ \
+            from the one it expects"
+        ));
+    }
+
+    let version = version?;
+    let current = Version::parse(current).expect("cannot parse version of razee");
+
+    return match Range::parse(version) {
+        Ok(range) if range.satisfies(&current) => None,
+        Ok(_) => Some(format!(
+            "{PACKAGE_JSON} asks for razee@{version} (current: {current})"
+        )),
+        Err(_) => Some(format!(
+            "cannot parse razee version {version} of packageManager in {PACKAGE_JSON}"
+        )),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_package_manager() {
+        assert_eq!(mismatch("razee@1.2.3", "1.2.3"), None);
+        assert_eq!(mismatch("razee", "1.2.3"), None);
+        assert_eq!(mismatch("razee@1.2.3+sha512.abc", "1.2.3"), None);
+        assert_eq!(
+            mismatch("razee@1.3.0", "1.2.3").as_deref(),
+            Some("package.json asks for razee@1.3.0 (current: 1.2.3)")
+        );
+        assert!(mismatch("pnpm@9.1.0+sha512.abc", "1.2.3")
+            .is_some_and(|mismatch| mismatch.contains("pnpm@9.1.0 as package manager")));
+    }
+}
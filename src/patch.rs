@@ -0,0 +1,196 @@
+use node_semver::Version;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    env, fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{fetcher::extract_tarball, http_client::HttpClient, manifest, NODE_MODULES};
+
+const PATCHES_DIR: &str = "patches";
+
+#[derive(Debug, Deserialize)]
+struct PatchedPackage {
+    name: String,
+    version: String,
+}
+
+/// extracts pristine copy of installed package into temp dir, where it can be edited
+pub async fn start(package_name: &str, client: &HttpClient) {
+    let installed = read_package(&Path::new(NODE_MODULES).join(package_name))
+        .expect(format!("{package_name} is not installed").as_str());
+
+    let version = Version::parse(&installed.version)
+        .expect(format!("invalid version of {package_name}: {}", installed.version).as_str());
+
+    let dependency = client
+        .fetch_dependency(&installed.name, &version)
+        .await
+        .to_owned();
+    let tarball = client.fetch_tarball(&dependency.dist).await;
+
+    let edit_dir = edit_dir(&installed);
+    let original_dir = original_dir(&installed);
+
+    for dir in [&edit_dir, &original_dir] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).expect("cannot clean up previous patch dir");
+        }
+
+        extract_tarball(tarball.as_ref(), dir.to_str().expect("temp dir is not valid utf-8"))
+            .expect("cannot extract package");
+    }
+
+    println!(
+        "You can now edit {}@{} in:",
+        installed.name, installed.version
+    );
+    println!("  {}", edit_dir.display());
+    println!("When done, run: razee patch-commit {}", edit_dir.display());
+}
+
+/// diffs edited package against pristine copy, saves patch and records it in package.json
+pub fn commit(edit_dir: &Path) {
+    let patched = read_package(edit_dir)
+        .expect(format!("{} is not a package directory", edit_dir.display()).as_str());
+    let original_dir = original_dir(&patched);
+
+    if !original_dir.exists() {
+        panic!(
+            "pristine copy of {}@{} is missing, run `razee patch {}` again",
+            patched.name, patched.version, patched.name
+        );
+    }
+
+    let patch = diff_dirs(&original_dir, edit_dir);
+
+    if patch.is_empty() {
+        println!("No changes in {}@{}", patched.name, patched.version);
+        return;
+    }
+
+    let patch_key = format!("{}@{}", patched.name, patched.version);
+    let patch_path = format!("{PATCHES_DIR}/{}.patch", escape_name(&patch_key));
+
+    fs::create_dir_all(PATCHES_DIR).expect("cannot create patches dir");
+    fs::write(&patch_path, patch).expect("cannot write patch file");
+
+    let package_json = Path::new(manifest::PACKAGE_JSON);
+    let mut root = manifest::read_raw(package_json);
+
+    manifest::set_entry(
+        &mut root,
+        "patchedDependencies",
+        &patch_key,
+        Value::String(patch_path.clone()),
+    );
+    manifest::write_raw(package_json, &root);
+
+    println!("Saved patch for {patch_key} into {patch_path}");
+}
+
+/// applies patch inside package directory, already applied patches are skipped
+pub fn apply(patch_path: &Path, package_dir: &str) {
+    let patch_path = fs::canonicalize(patch_path)
+        .expect(format!("patch file is missing: {}", patch_path.display()).as_str());
+
+    let already_applied = git_apply(&patch_path, package_dir, &["--reverse", "--check"]);
+
+    if already_applied {
+        return;
+    }
+
+    if !git_apply(&patch_path, package_dir, &[]) {
+        panic!(
+            "cannot apply {} to {package_dir}, package was probably updated",
+            patch_path.display()
+        );
+    }
+}
+
+fn git_apply(patch_path: &Path, package_dir: &str, extra_args: &[&str]) -> bool {
+    let package_dir = Path::new(package_dir);
+
+    // stops git from discovering project repository, so it behaves like plain `patch`
+    let ceiling = package_dir.parent().unwrap_or(package_dir);
+
+    return Command::new("git")
+        .arg("apply")
+        .args(extra_args)
+        .arg(patch_path)
+        .current_dir(package_dir)
+        .env(
+            "GIT_CEILING_DIRECTORIES",
+            fs::canonicalize(ceiling).unwrap_or_default(),
+        )
+        .output()
+        .expect("cannot run git, is it installed?")
+        .status
+        .success();
+}
+
+/// unified diff between two directories with paths relative to them
+pub fn diff_dirs(from: &Path, to: &Path) -> String {
+    let output = Command::new("git")
+        .args(["diff", "--no-index", "--no-color", "--no-ext-diff"])
+        .args(["--src-prefix=a/", "--dst-prefix=b/"])
+        .arg(fs::canonicalize(from).expect("cannot resolve original dir"))
+        .arg(fs::canonicalize(to).expect("cannot resolve edited dir"))
+        .output()
+        .expect("cannot run git, is it installed?");
+
+    // exits with 1 when there are differences
+    if output.status.code().is_some_and(|code| code > 1) {
+        panic!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let from_prefix = diff_prefix(from);
+    let to_prefix = diff_prefix(to);
+
+    // added and deleted files use the same path on both sides
+    return String::from_utf8_lossy(&output.stdout)
+        .replace(&format!("a/{from_prefix}"), "a/")
+        .replace(&format!("a/{to_prefix}"), "a/")
+        .replace(&format!("b/{from_prefix}"), "b/")
+        .replace(&format!("b/{to_prefix}"), "b/");
+}
+
+/// git prints absolute paths without leading slash after a/ and b/ prefixes
+fn diff_prefix(dir: &Path) -> String {
+    let dir = fs::canonicalize(dir).expect(format!("cannot resolve {}", dir.display()).as_str());
+
+    return format!("{}/", dir.display().to_string().trim_start_matches("/"));
+}
+
+fn read_package(dir: &Path) -> Option<PatchedPackage> {
+    let file = fs::File::open(dir.join(manifest::PACKAGE_JSON)).ok()?;
+
+    return serde_json::from_reader(BufReader::new(file)).ok();
+}
+
+fn patch_root() -> PathBuf {
+    return env::temp_dir().join("razee-patch");
+}
+
+fn edit_dir(package: &PatchedPackage) -> PathBuf {
+    let dir_name = escape_name(&format!("{}@{}", package.name, package.version));
+
+    return patch_root().join(dir_name);
+}
+
+fn original_dir(package: &PatchedPackage) -> PathBuf {
+    let dir_name = escape_name(&format!("{}@{}", package.name, package.version));
+
+    return patch_root().join(".original").join(dir_name);
+}
+
+/// scoped package names can't be used as plain file names
+pub fn escape_name(name: &str) -> String {
+    return name.replace("/", "__");
+}
@@ -0,0 +1,203 @@
+use elsa::FrozenMap;
+use node_semver::{Range, Version};
+use std::{env, fmt};
+
+use crate::{installer::InstallContext, npmrc::Npmrc, resolver::GraphEdge, Dependency};
+
+mod conflicts;
+
+pub(crate) use conflicts::resolve_conflicts;
+
+/// what to do with peer dependencies of installed packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PeerMode {
+    /// fail install when peer is missing or has incompatible version
+    Strict,
+    /// install missing peers (npm >= 7 behavior), warn about incompatible ones
+    AutoInstall,
+    /// warn about missing peers and ones with incompatible versions (npm 3-6 behavior)
+    #[default]
+    Warn,
+    /// don't look at peers at all
+    Ignore,
+}
+
+impl PeerMode {
+    /// `RAZEE_PEERS=strict|auto-install|warn|ignore`, or npm's `strict-peer-deps` and
+    /// `legacy-peer-deps` (that ignores peers) from .npmrc
+    pub fn from_env() -> PeerMode {
+        return match env::var("RAZEE_PEERS").as_deref() {
+            Ok("strict") => PeerMode::Strict,
+            Ok("auto-install") => PeerMode::AutoInstall,
+            Ok("warn") => PeerMode::Warn,
+            Ok("ignore") => PeerMode::Ignore,
+            Ok(other) => panic!(
+                "unknown RAZEE_PEERS value: {other}, expected strict|auto-install|warn|ignore"
+            ),
+            Err(_) => PeerMode::from_npmrc(Npmrc::load()),
+        };
+    }
+
+    fn from_npmrc(npmrc: &Npmrc) -> PeerMode {
+        if npmrc.get("strict-peer-deps") == Some("true") {
+            return PeerMode::Strict;
+        }
+
+        if npmrc.get("legacy-peer-deps") == Some("true") {
+            return PeerMode::Ignore;
+        }
+
+        return PeerMode::Warn;
+    }
+}
+
+type Packages = FrozenMap<String, Box<Dependency>>;
+
+/// `(dependent, peer name, peer range)` for every non optional peer of resolved packages
+fn required_peers(context: &InstallContext, packages: &Packages) -> Vec<(String, String, String)> {
+    let mut names: Vec<&str> = context.edges.iter().map(|edge| edge.to.as_str()).collect();
+
+    names.sort();
+    names.dedup();
+
+    let mut peers = vec![];
+
+    for name in names {
+        let Some(package) = packages.get(name) else {
+            continue;
+        };
+
+        let Some(peer_dependencies) = &package.peer_dependencies else {
+            continue;
+        };
+
+        for (peer_name, peer_range) in peer_dependencies {
+            let optional = package
+                .peer_dependencies_meta
+                .as_ref()
+                .and_then(|meta| meta.get(peer_name))
+                .is_some_and(|meta| meta.optional);
+
+            if !optional || packages.get(peer_name).is_some() {
+                peers.push((name.to_string(), peer_name.clone(), peer_range.clone()));
+            }
+        }
+    }
+
+    return peers;
+}
+
+/// edges to peers that are not in the graph yet, every missing peer is requested from its
+/// first dependent, with range that satisfies every dependent, when there is one
+pub(crate) fn missing_peers(context: &InstallContext, packages: &Packages) -> Vec<GraphEdge> {
+    let mut missing: Vec<GraphEdge> = vec![];
+
+    for (from, to, range) in required_peers(context, packages) {
+        if packages.get(&to).is_some() {
+            continue;
+        }
+
+        match missing.iter_mut().find(|edge| edge.to == to) {
+            // otherwise the first range wins, and the rest are reported as unmet
+            Some(edge) => {
+                if let Some(intersection) = intersect(&edge.range, &range) {
+                    edge.range = intersection;
+                }
+            }
+            None => missing.push(GraphEdge {
+                from,
+                to,
+                range,
+                deduped: false,
+            }),
+        }
+    }
+
+    return missing;
+}
+
+/// range, versions of which satisfy both ranges
+fn intersect(range: &str, other: &str) -> Option<String> {
+    let intersection = Range::parse(range)
+        .ok()?
+        .intersect(&Range::parse(other).ok()?)?;
+
+    return Some(intersection.to_string());
+}
+
+/// peer requirement that is not satisfied by resolved graph
+#[derive(Debug, Clone)]
+pub struct UnmetPeer {
+    pub dependent: String,
+    pub peer: String,
+    pub range: String,
+    /// `None` when peer is not installed at all
+    pub installed: Option<String>,
+}
+
+impl fmt::Display for UnmetPeer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (dependent, peer, range) = (&self.dependent, &self.peer, &self.range);
+
+        match &self.installed {
+            Some(version) => write!(
+                f,
+                "{dependent} requires peer {peer}@{range}, but {version} is installed"
+            ),
+            None => write!(
+                f,
+                "{dependent} requires peer {peer}@{range}, but it is not installed"
+            ),
+        }
+    }
+}
+
+/// missing peers and peers with incompatible versions
+pub(crate) fn unmet_peers(context: &InstallContext, packages: &Packages) -> Vec<UnmetPeer> {
+    let mut unmet = vec![];
+
+    for (dependent, peer, range) in required_peers(context, packages) {
+        let Some(installed) = packages.get(&peer) else {
+            unmet.push(UnmetPeer {
+                dependent,
+                peer,
+                range,
+                installed: None,
+            });
+            continue;
+        };
+
+        let (Ok(parsed_range), Ok(version)) =
+            (Range::parse(&range), Version::parse(&installed.version))
+        else {
+            continue;
+        };
+
+        if !parsed_range.satisfies(&version) {
+            unmet.push(UnmetPeer {
+                dependent,
+                peer,
+                range,
+                installed: Some(installed.version.clone()),
+            });
+        }
+    }
+
+    return unmet;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects_peer_ranges() {
+        let intersection = intersect("^18.0.0", ">=18.2.0").unwrap();
+        let range = Range::parse(&intersection).unwrap();
+
+        assert!(range.satisfies(&Version::parse("18.3.1").unwrap()));
+        assert!(!range.satisfies(&Version::parse("18.1.0").unwrap()));
+        assert!(!range.satisfies(&Version::parse("19.0.0").unwrap()));
+        assert_eq!(intersect("^17.0.0", "^18.0.0"), None);
+    }
+}
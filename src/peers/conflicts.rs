@@ -0,0 +1,215 @@
+use node_semver::{Range, Version};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use super::{required_peers, Packages, UnmetPeer};
+use crate::{
+    http_client::HttpClient,
+    installer::InstallContext,
+    manifest::{self, PACKAGE_JSON},
+    resolver, Dep,
+};
+
+/// `dependent` needs `peer` in `range`, either as peer or as regular dependency
+struct Requirement {
+    dependent: String,
+    range: String,
+    is_peer: bool,
+}
+
+/// asks which version should be used for peers with conflicting requirements and saves
+/// answers into `overrides` of package.json, returns whether anything was saved
+pub(crate) async fn resolve_conflicts(
+    unmet: &[UnmetPeer],
+    context: &InstallContext,
+    packages: &Packages,
+    client: &HttpClient,
+) -> bool {
+    let mut conflicting: Vec<&str> = unmet
+        .iter()
+        .filter(|unmet| unmet.installed.is_some())
+        .map(|unmet| unmet.peer.as_str())
+        .collect();
+
+    conflicting.sort();
+    conflicting.dedup();
+
+    let mut decisions = BTreeMap::new();
+
+    for peer in conflicting {
+        let Some(installed) = packages.get(peer) else {
+            continue;
+        };
+
+        let requirements = requirements(peer, context, packages);
+        let candidates = candidates(peer, &installed.version, &requirements, client).await;
+
+        println!();
+        println!(
+            "peer conflict for {peer} ({} is installed):",
+            installed.version
+        );
+
+        for requirement in requirements.iter() {
+            let kind = if requirement.is_peer {
+                "requires peer"
+            } else {
+                "depends on"
+            };
+
+            println!(
+                "  {} {kind} {peer}@{}",
+                requirement.dependent, requirement.range
+            );
+        }
+
+        println!("choose version of {peer}:");
+
+        for (index, candidate) in candidates.iter().enumerate() {
+            let (satisfied, broken) = consequences(candidate, &requirements);
+            let current = if candidate.to_string() == installed.version {
+                " (installed)"
+            } else {
+                ""
+            };
+
+            print!("  {}) {candidate}{current}", index + 1);
+
+            if !satisfied.is_empty() {
+                print!(", satisfies {}", satisfied.join(", "));
+            }
+
+            if !broken.is_empty() {
+                print!(", breaks {}", broken.join(", "));
+            }
+
+            println!();
+        }
+
+        println!("  0) keep as is");
+
+        if let Some(choice) = ask_choice(candidates.len()) {
+            decisions.insert(peer.to_string(), candidates[choice].to_string());
+        }
+    }
+
+    if decisions.is_empty() {
+        return false;
+    }
+
+    let manifest_path = Path::new(PACKAGE_JSON);
+    let mut manifest = manifest::read_raw(manifest_path);
+
+    for (peer, version) in decisions {
+        println!("overrides: {peer}@{version}");
+        manifest::set_entry(&mut manifest, "overrides", &peer, Value::from(version));
+    }
+
+    manifest::write_raw(manifest_path, &manifest);
+
+    return true;
+}
+
+fn requirements(peer: &str, context: &InstallContext, packages: &Packages) -> Vec<Requirement> {
+    let mut requirements: Vec<Requirement> = context
+        .edges
+        .iter()
+        .filter(|edge| edge.to == peer)
+        .map(|edge| Requirement {
+            dependent: edge.from.clone(),
+            range: edge.range.clone(),
+            is_peer: false,
+        })
+        .collect();
+
+    for (dependent, peer_name, range) in required_peers(context, packages) {
+        let already_listed = requirements
+            .iter()
+            .any(|requirement| requirement.dependent == dependent);
+
+        if peer_name == peer && !already_listed {
+            requirements.push(Requirement {
+                dependent,
+                range,
+                is_peer: true,
+            });
+        }
+    }
+
+    return requirements;
+}
+
+/// highest version for every requirement, plus installed one
+async fn candidates(
+    peer: &str,
+    installed: &str,
+    requirements: &[Requirement],
+    client: &HttpClient,
+) -> Vec<Version> {
+    let dep = Dep {
+        name: peer.to_string(),
+        version: "*".to_string(),
+    };
+    let package = client.fetch_package(&dep).await;
+
+    let versions = resolver::published_versions(package);
+
+    let mut candidates: Vec<Version> = requirements
+        .iter()
+        .filter_map(|requirement| Range::parse(&requirement.range).ok())
+        .filter_map(|range| {
+            versions
+                .iter()
+                .filter(|v| range.satisfies(v))
+                .max()
+                .cloned()
+        })
+        .chain(Version::parse(installed).ok())
+        .collect();
+
+    candidates.sort();
+    candidates.dedup();
+    candidates.reverse();
+
+    return candidates;
+}
+
+/// dependents that are satisfied and broken by choosing `candidate`
+fn consequences<'a>(
+    candidate: &Version,
+    requirements: &'a [Requirement],
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    let (satisfied, broken): (Vec<&Requirement>, Vec<&Requirement>) =
+        requirements.iter().partition(|requirement| {
+            Range::parse(&requirement.range).is_ok_and(|range| range.satisfies(candidate))
+        });
+
+    return (
+        satisfied.iter().map(|r| r.dependent.as_str()).collect(),
+        broken.iter().map(|r| r.dependent.as_str()).collect(),
+    );
+}
+
+/// index of chosen candidate, `None` to keep installed version
+fn ask_choice(candidates: usize) -> Option<usize> {
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("cannot write to stdout");
+
+        let mut answer = String::new();
+
+        if io::stdin().lock().read_line(&mut answer).unwrap_or(0) == 0 {
+            return None;
+        }
+
+        match answer.trim().parse::<usize>() {
+            Ok(0) => return None,
+            Ok(choice) if choice <= candidates => return Some(choice - 1),
+            _ => println!("enter number from 0 to {candidates}"),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::{fs, io, path::Path, sync::OnceLock};
+
+/// minimum modes applied to extracted entries, same as npm (pacote) uses
+const FILE_MODE: u32 = 0o666;
+const DIR_MODE: u32 = 0o777;
+const EXEC_MODE: u32 = 0o777;
+
+static UMASK: OnceLock<u32> = OnceLock::new();
+
+/// returns process umask, reads it only once. Should be called before any
+/// files are created, because reading umask requires briefly resetting it
+pub fn umask() -> u32 {
+    return *UMASK.get_or_init(read_umask);
+}
+
+#[cfg(unix)]
+fn read_umask() -> u32 {
+    // SAFETY: umask is always successful, previous value is restored right away
+    unsafe {
+        let mask = libc::umask(0);
+        libc::umask(mask);
+
+        return mask as u32;
+    }
+}
+
+#[cfg(not(unix))]
+fn read_umask() -> u32 {
+    return 0o022;
+}
+
+/// normalizes mode of extracted tarball entry: tarballs often come with weird
+/// modes (0o600, lost exec bits, etc.), so they are widened to npm's minimum
+/// modes and then masked with process umask
+pub fn normalize_extracted(path: &Path, header_mode: u32, is_dir: bool) -> io::Result<()> {
+    let min_mode = if is_dir { DIR_MODE } else { FILE_MODE };
+
+    return set_mode(path, (header_mode | min_mode) & 0o777 & !umask());
+}
+
+/// makes bin target executable for everyone allowed by umask
+pub fn make_executable(path: &Path) -> io::Result<()> {
+    return set_mode(path, EXEC_MODE & !umask());
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    return fs::set_permissions(path, fs::Permissions::from_mode(mode));
+}
+
+#[cfg(not(unix))]
+fn set_mode(path: &Path, _mode: u32) -> io::Result<()> {
+    // only checking that path exists, there is no exec bit on windows
+    return fs::metadata(path).map(|_| ());
+}
@@ -0,0 +1,86 @@
+use std::{fmt, sync::Arc};
+
+use crate::{http_client::HttpClient, prompt, report, Installer, Manifest};
+
+/// what install is going to download, resolved before anything is written
+#[derive(Debug, Default)]
+pub struct InstallPlan {
+    /// `name@version` of packages that are not installed yet
+    pub added: Vec<String>,
+    /// sum of `dist.unpackedSize` of added packages
+    pub size: u64,
+    /// added packages without size in their metadata
+    pub unknown_size: usize,
+}
+
+/// resolves manifest without installing, pass the same client to install afterwards,
+/// so metadata is not fetched twice
+pub async fn plan(manifest: Manifest, client: Arc<HttpClient>) -> InstallPlan {
+    let installed = report::installed_packages();
+    let graph = Installer::new(manifest).client(client).resolve().await;
+    let (added, _) = report::diff_installed(&installed, &graph);
+
+    let mut plan = InstallPlan::default();
+
+    for package in graph.packages.values() {
+        if installed.get(&package.name) == Some(&package.version) {
+            continue;
+        }
+
+        match package.dist.unpacked_size {
+            Some(size) => plan.size += size,
+            None => plan.unknown_size += 1,
+        }
+    }
+
+    plan.added = added;
+
+    return plan;
+}
+
+/// prints plan and asks whether to go on with install, `yes` skips the question
+pub async fn preview(manifest: Manifest, client: Arc<HttpClient>, yes: bool) -> bool {
+    let plan = plan(manifest, client).await;
+
+    println!("{plan}");
+
+    if yes || plan.added.is_empty() {
+        return true;
+    }
+
+    return prompt::confirm("Continue with install?");
+}
+
+impl fmt::Display for InstallPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} new packages, {}",
+            self.added.len(),
+            format_size(self.size)
+        )?;
+
+        if self.unknown_size > 0 {
+            write!(f, " (+ {} packages of unknown size)", self.unknown_size)?;
+        }
+
+        return Ok(());
+    }
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "kB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    return match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", UNITS[unit]),
+    };
+}
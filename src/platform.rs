@@ -0,0 +1,143 @@
+use serde::{Deserialize, Deserializer};
+use std::{env, fs};
+
+use crate::Dependency;
+
+/// platform packages are installed for, named like node names them (`process.platform`,
+/// `process.arch`), so they can be compared with `os`, `cpu` and `libc` of packages
+#[derive(Debug, Clone)]
+pub(crate) struct Platform {
+    pub os: String,
+    pub cpu: String,
+    /// only linux has it, `glibc` or `musl`
+    pub libc: Option<String>,
+}
+
+impl Platform {
+    /// current platform, `RAZEE_OS`, `RAZEE_CPU` and `RAZEE_LIBC` override it, e.g. to
+    /// install packages for docker image on other platform
+    pub(crate) fn from_env() -> Platform {
+        let os = env::var("RAZEE_OS").unwrap_or_else(|_| node_os().to_string());
+        let cpu = env::var("RAZEE_CPU").unwrap_or_else(|_| node_cpu().to_string());
+        let libc = match env::var("RAZEE_LIBC") {
+            Ok(libc) => Some(libc),
+            Err(_) if os == "linux" => Some(detect_libc().to_string()),
+            Err(_) => None,
+        };
+
+        return Platform { os, cpu, libc };
+    }
+
+    /// why `package` can't be installed here, `None` when it can
+    pub(crate) fn mismatch(&self, package: &Dependency) -> Option<String> {
+        let fields = [
+            ("os", &package.os, Some(&self.os)),
+            ("cpu", &package.cpu, Some(&self.cpu)),
+            ("libc", &package.libc, self.libc.as_ref()),
+        ];
+
+        for (field, allowed, current) in fields {
+            let (Some(allowed), Some(current)) = (allowed, current) else {
+                continue;
+            };
+
+            if !allows(allowed, current) {
+                return Some(format!(
+                    "{field} {} (current: {current})",
+                    allowed.join(", ")
+                ));
+            }
+        }
+
+        return None;
+    }
+}
+
+/// `os`, `cpu` and `libc` fields, old packages can have a single value instead of a list
+pub(crate) fn deserialize_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        Single(String),
+        Multiple(Vec<String>),
+    }
+
+    return Ok(match Option::<List>::deserialize(deserializer)? {
+        Some(List::Single(value)) => Some(vec![value]),
+        Some(List::Multiple(values)) => Some(values),
+        None => None,
+    });
+}
+
+/// npm rules: `!value` excludes value, any other value must be in the list, unless there
+/// are only exclusions
+fn allows(allowed: &[String], current: &str) -> bool {
+    let mut has_inclusions = false;
+    let mut included = false;
+
+    for value in allowed {
+        match value.strip_prefix("!") {
+            Some(excluded) if excluded == current => return false,
+            Some(_) => {}
+            None => {
+                has_inclusions = true;
+                included |= value == current || value == "any";
+            }
+        }
+    }
+
+    return !has_inclusions || included;
+}
+
+fn node_os() -> &'static str {
+    return match env::consts::OS {
+        "macos" => "darwin",
+        "windows" => "win32",
+        "solaris" | "illumos" => "sunos",
+        os => os,
+    };
+}
+
+fn node_cpu() -> &'static str {
+    return match env::consts::ARCH {
+        "x86_64" => "x64",
+        "x86" => "ia32",
+        "aarch64" => "arm64",
+        "powerpc64" => "ppc64",
+        "loongarch64" => "loong64",
+        arch => arch,
+    };
+}
+
+/// musl distros (e.g. alpine) have its dynamic loader in /lib
+fn detect_libc() -> &'static str {
+    let has_musl = fs::read_dir("/lib")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|entry| entry.file_name().to_string_lossy().starts_with("ld-musl-"));
+
+    return if has_musl { "musl" } else { "glibc" };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(values: &[&str]) -> Vec<String> {
+        return values.iter().map(|value| value.to_string()).collect();
+    }
+
+    #[test]
+    fn matches_platform_lists() {
+        assert!(allows(&list(&["darwin", "linux"]), "linux"));
+        assert!(!allows(&list(&["darwin"]), "linux"));
+        assert!(allows(&list(&["!win32"]), "linux"));
+        assert!(!allows(&list(&["!win32"]), "win32"));
+        assert!(!allows(&list(&["linux", "!linux"]), "linux"));
+        assert!(allows(&list(&[]), "linux"));
+    }
+}
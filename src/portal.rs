@@ -0,0 +1,227 @@
+use serde_json::{json, Value};
+use std::{
+    env, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    fs_retry::retry, local_dir, local_tarball, manifest::PACKAGE_JSON, Dependency, NODE_MODULES,
+};
+
+const PORTAL: &str = "portal:";
+const LINK: &str = "link:";
+
+/// `portal:<dir>` (from yarn berry) links local package dir into node_modules, like
+/// `link:<dir>`, but dependencies of the package are installed too
+pub(crate) fn is_portal(spec: &str) -> bool {
+    return spec.starts_with(PORTAL) || spec.starts_with(LINK);
+}
+
+/// reads package.json of portal dir, `dist.tarball` keeps the spec, so linker knows
+/// package is not downloaded, `link:` dir can have no package.json
+pub(crate) fn read_dependency(name: &str, spec: &str) -> Dependency {
+    let dir = portal_dir(spec);
+    let manifest_path = dir.join(PACKAGE_JSON);
+    let is_link = spec.starts_with(LINK);
+
+    let content = match fs::read_to_string(&manifest_path) {
+        Ok(content) => content,
+        Err(_) if is_link && dir.is_dir() => "{}".to_string(),
+        Err(_) => panic!("{spec} has no {}", manifest_path.display()),
+    };
+    let mut manifest: Value = serde_json::from_str(&content)
+        .expect(format!("cannot parse {}", manifest_path.display()).as_str());
+
+    // linked dir takes care of its dependencies itself
+    if let Some(fields) = manifest.as_object_mut().filter(|_| is_link) {
+        for field in ["dependencies", "optionalDependencies", "peerDependencies"] {
+            fields.remove(field);
+        }
+    }
+
+    // package is linked under the name it was requested with
+    manifest["name"] = Value::from(name);
+
+    if manifest.get("version").and_then(Value::as_str).is_none() {
+        manifest["version"] = Value::from("0.0.0");
+    }
+
+    manifest["dist"] = json!({
+        "tarball": spec,
+        "integrity": "",
+    });
+
+    return serde_json::from_value(manifest)
+        .expect(format!("invalid {}", manifest_path.display()).as_str());
+}
+
+/// symlinks portal dir as `node_modules/<name>`, replacing whatever was there
+pub(crate) fn link(package: &Dependency) -> io::Result<()> {
+    let link_path = PathBuf::from(NODE_MODULES).join(&package.name);
+
+    return link_dir(&portal_dir(&package.dist.tarball), &link_path);
+}
+
+/// dir of portal package, relative to project root, unless portal is absolute
+pub(crate) fn portal_dir(spec: &str) -> PathBuf {
+    let dir = spec
+        .strip_prefix(PORTAL)
+        .or(spec.strip_prefix(LINK))
+        .unwrap_or(spec);
+
+    return Path::new(dir.strip_prefix("./").unwrap_or(dir)).to_path_buf();
+}
+
+/// dir of linked (or copied `file:`) package, relative to project root, its own local
+/// dependencies are relative to it
+pub(crate) fn package_dir(package: &Dependency) -> Option<PathBuf> {
+    let spec = &package.dist.tarball;
+
+    if local_dir::is_local_dir(spec) {
+        return Some(portal_dir(spec.strip_prefix("file:").unwrap_or(spec)));
+    }
+
+    return is_portal(spec).then(|| portal_dir(spec));
+}
+
+/// `link:`, `portal:` and `file:` specs of package.json in `base` dir (relative to
+/// project root) are relative to that dir, they are rebased onto project root, like root
+/// ones are
+pub(crate) fn rebase(spec: &str, base: &Path) -> String {
+    let prefix = match [PORTAL, LINK]
+        .into_iter()
+        .find(|prefix| spec.starts_with(prefix))
+    {
+        Some(prefix) => prefix,
+        None if local_dir::is_local_dir(spec) || local_tarball::is_local_tarball(spec) => "file:",
+        None => return spec.to_string(),
+    };
+    let dir = portal_dir(spec.strip_prefix("file:").unwrap_or(spec));
+
+    if dir.is_absolute() || base.as_os_str().is_empty() {
+        return spec.to_string();
+    }
+
+    return format!("{prefix}{}", normalize(&base.join(dir)).display());
+}
+
+/// `a/./b/../c` as `a/c`, without touching fs, as dir can be missing yet, leading `..`
+/// are kept
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    if normalized.as_os_str().is_empty() {
+        return PathBuf::from(".");
+    }
+
+    return normalized;
+}
+
+/// symlinks `dir` as `link_path` (both relative to project root), replacing whatever
+/// was there
+pub(crate) fn link_dir(dir: &Path, link_path: &Path) -> io::Result<()> {
+    // relative link keeps working when project is moved
+    let target = if dir.is_absolute() {
+        dir.to_path_buf()
+    } else if link_path.is_absolute() {
+        env::current_dir()?.join(dir)
+    } else {
+        let depth = link_path.components().count() - 1;
+
+        (0..depth)
+            .map(|_| Component::ParentDir.as_os_str())
+            .collect::<PathBuf>()
+            .join(dir)
+    };
+
+    if fs::read_link(link_path).is_ok_and(|existing| existing == target) {
+        return Ok(());
+    }
+
+    if let Some(parent) = link_path.parent() {
+        retry(parent, || fs::create_dir_all(parent))?;
+    }
+
+    match link_path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => retry(link_path, || fs::remove_dir_all(link_path))?,
+        Ok(_) => retry(link_path, || fs::remove_file(link_path))?,
+        Err(_) => {}
+    }
+
+    return retry(link_path, || symlink_dir(&target, link_path));
+}
+
+fn symlink_dir(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link);
+
+    // without developer mode symlinks need admin rights, junctions don't
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_dir(target, link).or_else(|error| {
+        const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+
+        if error.raw_os_error() != Some(ERROR_PRIVILEGE_NOT_HELD) {
+            return Err(error);
+        }
+
+        return junction(target, link);
+    });
+}
+
+/// junctions can only point to absolute paths
+#[cfg(windows)]
+fn junction(target: &Path, link: &Path) -> io::Result<()> {
+    let base = env::current_dir()?.join(link.parent().unwrap_or(Path::new("")));
+    let output = std::process::Command::new("cmd")
+        .arg("/C")
+        .arg("mklink")
+        .arg("/J")
+        .arg(link)
+        .arg(base.join(target))
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebases_nested_specs_onto_root() {
+        let base = Path::new("packages/app");
+
+        assert_eq!(rebase("link:../shared", base), "link:packages/shared");
+        assert_eq!(rebase("portal:./lib", base), "portal:packages/app/lib");
+        assert_eq!(rebase("portal:../../..", base), "portal:..");
+        assert_eq!(rebase("link:/opt/shared", base), "link:/opt/shared");
+        assert_eq!(rebase("link:../shared", Path::new("")), "link:../shared");
+        assert_eq!(rebase("file:../shared", base), "file:packages/shared");
+        assert_eq!(
+            rebase("./vendor/lib.tgz", base),
+            "file:packages/app/vendor/lib.tgz"
+        );
+        assert_eq!(rebase("^1.0.0", base), "^1.0.0");
+    }
+}
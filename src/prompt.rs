@@ -0,0 +1,46 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// asks y/N question, non interactive shells always agree, so CI is not blocked
+pub(crate) fn confirm(question: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return true;
+    }
+
+    print!("{question} [y/N] ");
+    io::stdout().flush().expect("cannot write to stdout");
+
+    let mut answer = String::new();
+
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .expect("cannot read answer");
+
+    return matches!(answer.trim(), "y" | "Y" | "yes");
+}
+
+/// asks for a value, empty answer (or non interactive shell) keeps `default`
+pub(crate) fn ask(question: &str, default: &str) -> String {
+    if !io::stdin().is_terminal() {
+        return default.to_string();
+    }
+
+    match default.is_empty() {
+        true => print!("{question}: "),
+        false => print!("{question} ({default}): "),
+    }
+
+    io::stdout().flush().expect("cannot write to stdout");
+
+    let mut answer = String::new();
+
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .expect("cannot read answer");
+
+    return match answer.trim() {
+        "" => default.to_string(),
+        answer => answer.to_string(),
+    };
+}
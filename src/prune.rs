@@ -0,0 +1,156 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::{
+    lock::DirLock,
+    lockfile::{Lockfile, LOCKFILE},
+    ls, remove, NODE_MODULES,
+};
+
+/// suffix of dirs, that packages are extracted into before they are moved in place
+const TEMP_SUFFIX: &str = ".razee-tmp";
+
+/// `razee prune [--production] [--dry-run]`: removes packages, that are not in lockfile, from
+/// node_modules (also nested ones and leftovers of interrupted installs). With `--production`
+/// packages only dev dependencies need are removed too, lockfile is left as it is
+pub fn prune(args: &[String]) {
+    let production = args.iter().any(|arg| arg == "--production");
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+
+    let lockfile = Lockfile::read(Path::new(LOCKFILE))
+        .expect(format!("{LOCKFILE} is missing, run `razee` first").as_str());
+
+    let _node_modules_lock = DirLock::acquire(Path::new(NODE_MODULES));
+
+    let kept: HashSet<String> = match production {
+        true => production_packages(&lockfile),
+        false => lockfile.packages.keys().cloned().collect(),
+    };
+
+    let mut installed = vec![];
+
+    // workspaces can have their own node_modules
+    for path in lockfile.packages.keys() {
+        if !path.starts_with(&format!("{NODE_MODULES}/")) {
+            let dir = match path.is_empty() {
+                true => NODE_MODULES.to_string(),
+                false => format!("{path}/{NODE_MODULES}"),
+            };
+
+            collect_installed(Path::new(&dir), &dir, &mut installed);
+        }
+    }
+
+    let mut pruned = 0;
+
+    for path in installed {
+        if kept.contains(&path) {
+            continue;
+        }
+
+        println!("- {path}");
+        pruned += 1;
+
+        if dry_run {
+            continue;
+        }
+
+        // nested packages of already removed one are gone with it
+        if let Err(error) = remove::remove_package(Path::new(&path)) {
+            eprintln!("warning: cannot remove {path}: {error}");
+        }
+    }
+
+    if !dry_run {
+        remove::remove_dangling_bins(&Path::new(NODE_MODULES).join(".bin"));
+    }
+
+    let verb = if dry_run { "would prune" } else { "pruned" };
+
+    match pruned {
+        0 => println!("nothing to prune"),
+        1 => println!("{verb} 1 package"),
+        _ => println!("{verb} {pruned} packages"),
+    }
+}
+
+/// lockfile keys of installed packages (and temp dirs) in `dir`, linked packages are not
+/// followed into
+fn collect_installed(dir: &Path, key: &str, installed: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        let entry_key = format!("{key}/{file_name}");
+
+        if file_name.starts_with(".") {
+            if file_name.ends_with(TEMP_SUFFIX) {
+                installed.push(entry_key);
+            }
+
+            continue;
+        }
+
+        if file_name.starts_with("@") {
+            collect_installed(&path, &entry_key, installed);
+            continue;
+        }
+
+        installed.push(entry_key.clone());
+
+        let is_dir = fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir());
+
+        if is_dir {
+            collect_installed(
+                &path.join(NODE_MODULES),
+                &format!("{entry_key}/{NODE_MODULES}"),
+                installed,
+            );
+        }
+    }
+}
+
+/// root, workspaces and packages their (not dev) dependencies need
+pub(crate) fn production_packages(lockfile: &Lockfile) -> HashSet<String> {
+    let mut kept = HashSet::new();
+    let mut queue: Vec<String> = lockfile
+        .packages
+        .keys()
+        .filter(|path| !path.starts_with(&format!("{NODE_MODULES}/")))
+        .cloned()
+        .collect();
+
+    while let Some(path) = queue.pop() {
+        if !kept.insert(path.clone()) {
+            continue;
+        }
+
+        let Some(locked) = lockfile.packages.get(&path) else {
+            continue;
+        };
+
+        // workspaces are linked into root node_modules
+        if let Some(name) = locked.name.as_ref().filter(|_| !path.is_empty()) {
+            queue.push(format!("{NODE_MODULES}/{name}"));
+        }
+
+        let requested = [
+            &locked.dependencies,
+            &locked.optional_dependencies,
+            &locked.peer_dependencies,
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(|deps| deps.keys());
+
+        for name in requested {
+            if let Some((dependency_path, _)) = ls::find(lockfile, &path, name) {
+                queue.push(dependency_path);
+            }
+        }
+    }
+
+    return kept;
+}
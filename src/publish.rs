@@ -0,0 +1,384 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    github_actions, local_tarball,
+    manifest::{self, PACKAGE_JSON},
+    registry::Registry,
+    registry_api::RegistryApi,
+    workspaces, NODE_MODULES,
+};
+
+/// never packed, same as npm
+const ALWAYS_IGNORED: [&str; 6] = [
+    ".git",
+    NODE_MODULES,
+    ".DS_Store",
+    "npm-debug.log",
+    "package-lock.json",
+    ".npmrc",
+];
+
+/// packed even when `files` doesn't mention them, matched by name without extension
+const ALWAYS_INCLUDED: [&str; 4] = ["PACKAGE", "README", "LICENSE", "LICENCE"];
+
+#[derive(Debug, Default)]
+pub struct PublishOptions {
+    /// dist tag, `latest` by default
+    pub tag: Option<String>,
+    /// `public` or `restricted`, registry decides for scoped packages by default
+    pub access: Option<String>,
+    /// only lists files that would be published
+    pub dry_run: bool,
+}
+
+/// `razee publish [-r] [--tag <tag>] [--access public|restricted] [--dry-run]`
+pub async fn publish(args: &[String]) {
+    let flag_value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+            .cloned()
+    };
+
+    let options = PublishOptions {
+        tag: flag_value("--tag"),
+        access: flag_value("--access"),
+        dry_run: args.iter().any(|arg| arg == "--dry-run"),
+    };
+
+    if args.iter().any(|arg| arg == "-r" || arg == "--recursive") {
+        publish_recursive(Path::new("."), &options).await;
+    } else {
+        publish_dir(Path::new("."), &options).await;
+    }
+}
+
+/// packs package in `dir` and publishes it, `workspace:` specifiers of workspace package
+/// are replaced with versions of its siblings, the same way `-r` does it
+pub async fn publish_dir(dir: &Path, options: &PublishOptions) {
+    let dir = fs::canonicalize(dir).expect(format!("cannot find {}", dir.display()).as_str());
+    let mut manifest = manifest::read_raw(&dir.join(PACKAGE_JSON));
+
+    let versions = match workspaces::find_root(&dir) {
+        Some(root) => workspace_versions(&workspace_packages(&root)),
+        None => HashMap::new(),
+    };
+
+    workspaces::replace_workspace_specs(&mut manifest, &versions);
+
+    publish_manifest(&dir, manifest, options).await;
+}
+
+/// dirs of workspace packages of `root` with their package.json
+fn workspace_packages(root: &Path) -> Vec<(PathBuf, Value)> {
+    let root_manifest = manifest::read_raw(&root.join(PACKAGE_JSON));
+
+    return workspaces::find(root, &root_manifest)
+        .into_iter()
+        .map(|dir| {
+            let manifest = manifest::read_raw(&dir.join(PACKAGE_JSON));
+
+            (dir, manifest)
+        })
+        .collect();
+}
+
+/// name -> version of workspace packages
+fn workspace_versions(packages: &[(PathBuf, Value)]) -> HashMap<String, String> {
+    return packages
+        .iter()
+        .filter_map(|(_, manifest)| {
+            let name = manifest.get("name")?.as_str()?;
+            let version = manifest.get("version")?.as_str()?;
+
+            Some((name.to_string(), version.to_string()))
+        })
+        .collect();
+}
+
+/// publishes workspace packages, which versions are not in registry yet, dependencies
+/// first, `workspace:` specifiers are replaced with versions of workspace packages
+pub async fn publish_recursive(root: &Path, options: &PublishOptions) {
+    let api = RegistryApi::new(&Registry::from_env());
+    let packages = workspace_packages(root);
+
+    if packages.is_empty() {
+        panic!("no workspace packages found, `workspaces` in package.json is empty");
+    }
+
+    let versions = workspace_versions(&packages);
+
+    let mut published = vec![];
+    let mut skipped = vec![];
+
+    for index in workspaces::topological_order(&packages) {
+        let (dir, manifest) = &packages[index];
+        let name = manifest.get("name").and_then(Value::as_str).unwrap_or("?");
+        let version = manifest
+            .get("version")
+            .and_then(Value::as_str)
+            .unwrap_or("?");
+
+        if manifest.get("private").and_then(Value::as_bool) == Some(true) {
+            skipped.push(format!("{name} (private)"));
+            continue;
+        }
+
+        if api
+            .published_versions(name)
+            .await
+            .iter()
+            .any(|v| v == version)
+        {
+            skipped.push(format!("{name}@{version} (already published)"));
+            continue;
+        }
+
+        let mut manifest = manifest.clone();
+
+        workspaces::replace_workspace_specs(&mut manifest, &versions);
+
+        github_actions::group(&format!("Publish {name}@{version}"));
+        publish_manifest(dir, manifest, options).await;
+        github_actions::end_group();
+
+        published.push(format!("{name}@{version}"));
+    }
+
+    let action = if options.dry_run {
+        "would publish"
+    } else {
+        "published"
+    };
+
+    println!();
+    println!("{action} {} packages:", published.len());
+
+    for package in published {
+        println!("  {package}");
+    }
+
+    if !skipped.is_empty() {
+        println!("skipped {} packages:", skipped.len());
+
+        for package in skipped {
+            println!("  {package}");
+        }
+    }
+}
+
+/// packs `dir` with given manifest as package.json, and publishes it
+pub async fn publish_manifest(dir: &Path, manifest: Value, options: &PublishOptions) {
+    let field = |name: &str| manifest.get(name).and_then(Value::as_str).map(String::from);
+    let name = field("name").expect("cannot publish package without name");
+    let version = field("version").expect("cannot publish package without version");
+
+    if manifest.get("private").and_then(Value::as_bool) == Some(true) {
+        panic!("cannot publish {name}: package is marked \"private\": true in package.json");
+    }
+
+    // roots usually only tie workspaces together, publishing them is a mistake
+    if manifest.get("workspaces").is_some() {
+        panic!("cannot publish {name}: it is workspace root, publish workspace packages instead");
+    }
+
+    let files = package_files(dir, &manifest);
+
+    println!("{name}@{version}");
+
+    for file in files.iter() {
+        println!("  {}", file.display());
+    }
+
+    if options.dry_run {
+        return;
+    }
+
+    let tarball = pack(dir, &files, &manifest);
+    let api = RegistryApi::new(&Registry::from_env());
+    let tag = options.tag.as_deref().unwrap_or("latest");
+
+    let mut version_document = manifest.clone();
+
+    version_document["_id"] = Value::from(format!("{name}@{version}"));
+    version_document["dist"] = json!({
+        "integrity": local_tarball::integrity(&tarball),
+        "tarball": api.tarball_url(&name, &version),
+    });
+
+    let document = json!({
+        "_id": name,
+        "name": name,
+        "description": manifest.get("description").cloned().unwrap_or(Value::Null),
+        "dist-tags": { tag: version },
+        "versions": { version.as_str(): version_document },
+        "access": options.access,
+        "_attachments": {
+            format!("{name}-{version}.tgz"): {
+                "content_type": "application/octet-stream",
+                "data": STANDARD.encode(&tarball),
+                "length": tarball.len(),
+            },
+        },
+    });
+
+    api.publish(&name, &version, &document).await;
+
+    println!("+ {name}@{version} ({tag})");
+}
+
+/// packs `dir` like it would be published, e.g. package from git repo, which is installed
+/// from tarball like any other
+pub(crate) fn pack_dir(dir: &Path, manifest: &Value) -> Vec<u8> {
+    return pack(dir, &package_files(dir, manifest), manifest);
+}
+
+/// files to pack, relative to `dir`: listed in `files` of package.json, or everything
+/// that is not ignored by `.npmignore` (or `.gitignore`)
+fn package_files(dir: &Path, manifest: &Value) -> Vec<PathBuf> {
+    let included: Option<Vec<&str>> = manifest
+        .get("files")
+        .and_then(Value::as_array)
+        .map(|files| files.iter().filter_map(Value::as_str).collect());
+
+    let ignore_file = [".npmignore", ".gitignore"]
+        .iter()
+        .find_map(|file| fs::read_to_string(dir.join(file)).ok())
+        .unwrap_or_default();
+
+    let ignored: Vec<&str> = ignore_file
+        .lines()
+        .map(|line| line.trim().trim_matches('/'))
+        .filter(|line| !line.is_empty() && !line.starts_with("#"))
+        .collect();
+
+    let mut files = vec![];
+
+    let walker = WalkDir::new(dir).into_iter().filter_entry(|entry| {
+        let file_name = entry.file_name().to_string_lossy();
+
+        return entry.depth() == 0 || !ALWAYS_IGNORED.contains(&file_name.as_ref());
+    });
+
+    for entry in walker.flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry
+            .path()
+            .strip_prefix(dir)
+            .expect("walked file is inside package dir")
+            .to_path_buf();
+
+        let relative = path.to_string_lossy().replace("\\", "/");
+        let file_name = entry.file_name().to_string_lossy();
+
+        let file_stem = file_name
+            .split(".")
+            .next()
+            .unwrap_or_default()
+            .to_uppercase();
+        let always_included = entry.depth() == 1 && ALWAYS_INCLUDED.contains(&file_stem.as_str());
+
+        let is_included = match &included {
+            Some(included) => included
+                .iter()
+                .any(|pattern| matches_path(pattern, &relative)),
+            None => !ignored
+                .iter()
+                .any(|pattern| matches_path(pattern, &relative)),
+        };
+
+        if always_included || is_included {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    return files;
+}
+
+/// whether `pattern` (`lib`, `dist/*.js`, `**/*.d.ts`) matches file or one of its parent dirs
+fn matches_path(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches("/");
+
+    let segments: Vec<&str> = path.split("/").collect();
+
+    // pattern without slashes can match any file or dir name, like in .gitignore
+    if !pattern.contains("/") && segments.iter().any(|segment| wildcard(pattern, segment)) {
+        return true;
+    }
+
+    return (1..=segments.len()).any(|len| wildcard(pattern, &segments[..len].join("/")));
+}
+
+/// `*` matches anything except `/`, `**` matches anything
+pub(crate) fn wildcard(pattern: &str, text: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**") {
+        let rest = rest.trim_start_matches("/");
+
+        return (0..=text.len())
+            .filter(|index| text.is_char_boundary(*index))
+            .any(|index| wildcard(rest, &text[index..]));
+    }
+
+    if let Some(rest) = pattern.strip_prefix("*") {
+        return (0..=text.len())
+            .filter(|index| text.is_char_boundary(*index))
+            .take_while(|index| !text[..*index].contains("/"))
+            .any(|index| wildcard(rest, &text[index..]));
+    }
+
+    return match (pattern.chars().next(), text.chars().next()) {
+        (None, None) => true,
+        (Some(p), Some(t)) if p == t => wildcard(&pattern[p.len_utf8()..], &text[t.len_utf8()..]),
+        _ => false,
+    };
+}
+
+/// gzipped tarball with files under `package/`, like `npm pack` makes, package.json
+/// is written from `manifest`, because it can differ from one on disk
+fn pack(dir: &Path, files: &[PathBuf], manifest: &Value) -> Vec<u8> {
+    let encoder = GzEncoder::new(vec![], Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+
+    for file in files {
+        if file == Path::new(PACKAGE_JSON) {
+            let content = serde_json::to_vec_pretty(manifest).expect("cannot serialize manifest");
+            let mut header = tar::Header::new_gnu();
+
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+
+            archive
+                .append_data(
+                    &mut header,
+                    Path::new("package").join(file),
+                    content.as_slice(),
+                )
+                .expect("cannot pack package.json");
+
+            continue;
+        }
+
+        archive
+            .append_path_with_name(dir.join(file), Path::new("package").join(file))
+            .expect(format!("cannot pack {}", file.display()).as_str());
+    }
+
+    return archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .expect("cannot pack package");
+}
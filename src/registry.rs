@@ -0,0 +1,229 @@
+use bytes::Bytes;
+use reqwest::Url;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    npmrc::{self, Npmrc},
+    Dependency, RegistryPackage,
+};
+
+pub const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+/// where packuments and tarballs come from
+#[derive(Debug, Clone)]
+pub enum Registry {
+    /// npm compatible registry by url
+    Remote(String),
+    /// directory that mirrors registry's url layout (fixtures for tests, or vendored
+    /// mirror for air-gapped installs):
+    /// - `<dir>/<name>/index.json` - packument (with `versions`)
+    /// - `<dir>/<name>/<version>.json` - version document (optional, taken from packument
+    ///   otherwise)
+    /// - `<dir>/<name>/-/<file>.tgz` - tarballs
+    /// - `<dir>/<endpoint>.json` - responses of other endpoints,
+    ///   e.g. `-/npm/v1/security/advisories/bulk`
+    Fixture(PathBuf),
+}
+
+impl Registry {
+    /// `RAZEE_FIXTURE_REGISTRY=<dir>` selects fixture registry (useful for hermetic
+    /// tests), `RAZEE_REGISTRY=<url>` (or `registry` of .npmrc) overrides remote registry
+    pub fn from_env() -> Registry {
+        if let Ok(dir) = env::var("RAZEE_FIXTURE_REGISTRY") {
+            return Registry::Fixture(PathBuf::from(dir));
+        }
+
+        if let Some(url) = env::var("RAZEE_REGISTRY")
+            .ok()
+            .or_else(|| npmrc::get("registry"))
+        {
+            return Registry::parse(&url);
+        }
+
+        return Registry::Remote(DEFAULT_REGISTRY.to_string());
+    }
+
+    /// `file:///path/to/mirror` is read from disk (for offline installs), with the same
+    /// layout as fixture registry, anything else is remote registry
+    pub fn parse(url: &str) -> Registry {
+        if url.starts_with("file://") {
+            let path = Url::parse(url)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .expect(format!("invalid registry mirror url {url}").as_str());
+
+            if !path.is_dir() {
+                panic!("registry mirror {} does not exist", path.display());
+            }
+
+            return Registry::Fixture(path);
+        }
+
+        return Registry::Remote(url.trim_end_matches("/").to_string());
+    }
+
+    /// auth token, when `url` is on registry's host (tarballs can be hosted elsewhere,
+    /// and they must not receive the token)
+    pub fn auth_token_for(&self, url: &str) -> Option<String> {
+        let Registry::Remote(registry_url) = self else {
+            return None;
+        };
+
+        let origin = |url: &str| {
+            let url = Url::parse(url).ok()?;
+
+            Some((
+                url.scheme().to_string(),
+                url.host_str()?.to_string(),
+                url.port_or_known_default(),
+            ))
+        };
+
+        if origin(registry_url).is_none() || origin(registry_url) != origin(url) {
+            return None;
+        }
+
+        return auth_token();
+    }
+
+    /// panics on plaintext registry, unless `RAZEE_ALLOW_HTTP=true`
+    pub fn ensure_secure(&self) {
+        if let Registry::Remote(url) = self {
+            ensure_https(url, "registry");
+        }
+    }
+}
+
+/// `RAZEE_SCOPED_REGISTRIES=@mycorp=https://npm.corp.example,@other=file:///mirror` (and
+/// `@mycorp:registry=<url>` of .npmrc), packages of these scopes come from their registry
+/// instead of the default one
+pub fn scoped_from_env() -> HashMap<String, Registry> {
+    let mut registries: HashMap<String, Registry> = Npmrc::load()
+        .scoped_registries()
+        .into_iter()
+        .map(|(scope, url)| (scope.to_string(), Registry::parse(url)))
+        .collect();
+
+    let Ok(scoped) = env::var("RAZEE_SCOPED_REGISTRIES") else {
+        return registries;
+    };
+
+    let from_env = scoped
+        .split(",")
+        .map(str::trim)
+        .filter(|scoped| !scoped.is_empty())
+        .map(|scoped| {
+            let (scope, url) = scoped
+                .split_once("=")
+                .filter(|(scope, _)| scope.starts_with("@"))
+                .expect(format!("scoped registry must be @scope=<url>, got {scoped}").as_str());
+
+            (scope.trim().to_string(), Registry::parse(url.trim()))
+        });
+
+    registries.extend(from_env);
+
+    return registries;
+}
+
+/// `@scope` of scoped package name, or of tarball path in registry, like
+/// `@scope/name/-/name-1.0.0.tgz`
+pub(crate) fn scope_of(name: &str) -> Option<&str> {
+    return name
+        .split("/")
+        .next()
+        .filter(|scope| scope.len() > 1 && scope.starts_with("@"));
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        return Registry::from_env();
+    }
+}
+
+/// `RAZEE_AUTH_TOKEN`, or conventional CI variables: `NODE_AUTH_TOKEN` (set up by
+/// actions/setup-node) and `NPM_TOKEN`
+pub fn auth_token() -> Option<String> {
+    return ["RAZEE_AUTH_TOKEN", "NODE_AUTH_TOKEN", "NPM_TOKEN"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|token| !token.trim().is_empty());
+}
+
+/// plaintext downloads can be tampered with on the way, so they are refused by default
+pub fn allow_http() -> bool {
+    return env::var("RAZEE_ALLOW_HTTP").is_ok_and(|allow| allow == "true" || allow == "1");
+}
+
+/// panics when `url` is not https and plaintext is not explicitly allowed
+pub fn ensure_https(url: &str, what: &str) {
+    if url.starts_with("http://") && !allow_http() {
+        panic!(
+            "refusing to use plaintext {what} url {url}, use https or set RAZEE_ALLOW_HTTP=true"
+        );
+    }
+}
+
+pub(crate) fn read_fixture_packument(dir: &Path, name: &str) -> Value {
+    return read_json(&dir.join(name).join("index.json"));
+}
+
+pub(crate) fn read_fixture_package(dir: &Path, name: &str) -> RegistryPackage {
+    let packument = read_fixture_packument(dir, name);
+
+    return serde_json::from_value(packument)
+        .expect(format!("invalid packument of {name} in local registry").as_str());
+}
+
+pub(crate) fn read_fixture_dependency(dir: &Path, name: &str, version: &str) -> Dependency {
+    let version_path = dir.join(name).join(format!("{version}.json"));
+
+    let document = if version_path.exists() {
+        read_json(&version_path)
+    } else {
+        read_json(&dir.join(name).join("index.json"))
+            .get_mut("versions")
+            .and_then(|versions| versions.get_mut(version))
+            .map(Value::take)
+            .expect(format!("local registry has no {name}@{version}").as_str())
+    };
+
+    return serde_json::from_value(document).expect(
+        format!("invalid version document of {name}@{version} in local registry").as_str(),
+    );
+}
+
+/// tarball urls in fixtures can be relative to fixture dir, or real registry urls
+/// (so packuments can be copied from npm as is), then only `<name>/-/<file>` part is used
+pub(crate) fn read_fixture_tarball(dir: &Path, tarball_url: &str) -> Bytes {
+    let tarball_path = dir.join(fixture_tarball_path(tarball_url));
+    let tarball = fs::read(&tarball_path)
+        .expect(format!("local registry has no tarball {}", tarball_path.display()).as_str());
+
+    return Bytes::from(tarball);
+}
+
+/// `<name>/-/<file>` part of tarball url
+pub(crate) fn fixture_tarball_path(tarball_url: &str) -> &str {
+    return match tarball_url.split_once("://") {
+        Some((_, url)) => url.split_once("/").map(|(_host, path)| path).unwrap_or(url),
+        None => tarball_url,
+    };
+}
+
+pub(crate) fn read_fixture_response(dir: &Path, endpoint: &str) -> Value {
+    return read_json(&dir.join(format!("{endpoint}.json")));
+}
+
+fn read_json(path: &Path) -> Value {
+    let content = fs::read_to_string(path)
+        .expect(format!("local registry has no {}", path.display()).as_str());
+
+    return serde_json::from_str(&content)
+        .expect(format!("cannot parse {} of local registry", path.display()).as_str());
+}
@@ -0,0 +1,264 @@
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::{
+    npmrc::Npmrc,
+    registry::{self, Registry},
+};
+
+const USER_AGENT: &str = "Razee (Node Package Manger in Rust)";
+
+/// write side of registry api (dist tags, owners, deprecations, ...), unlike
+/// `HttpClient` which only reads packuments and tarballs
+pub struct RegistryApi {
+    client: reqwest::Client,
+    registry_url: String,
+    token: Option<String>,
+}
+
+impl RegistryApi {
+    pub fn new(registry: &Registry) -> RegistryApi {
+        registry.ensure_secure();
+
+        let registry_url = match registry {
+            Registry::Remote(url) => url.clone(),
+            Registry::Fixture(dir) => {
+                panic!("local registry ({}) is read only", dir.display())
+            }
+        };
+
+        // token of .npmrc for registry wins over the one from env, like for installs
+        let token = Npmrc::load()
+            .auth_token_for(&format!("{registry_url}/"))
+            .map(String::from)
+            .or_else(registry::auth_token);
+
+        return RegistryApi {
+            client: reqwest::Client::new(),
+            registry_url,
+            token,
+        };
+    }
+
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut request = self
+            .client
+            .request(method, format!("{}/{path}", self.registry_url))
+            .header("User-Agent", USER_AGENT);
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        return request;
+    }
+
+    /// sends request and turns registry errors into readable panics
+    pub async fn send(&self, request: RequestBuilder, action: &str) -> Response {
+        let (status, reason) = match self.try_send(request).await {
+            Ok(response) => return response,
+            Err(error) => error,
+        };
+
+        match status {
+            StatusCode::UNAUTHORIZED => panic!(
+                "cannot {action}: not logged in (E401), set RAZEE_AUTH_TOKEN or .npmrc _authToken"
+            ),
+            StatusCode::FORBIDDEN => {
+                panic!("cannot {action}: permission denied (E403): {reason}")
+            }
+            StatusCode::NOT_FOUND => panic!("cannot {action}: not found (E404): {reason}"),
+            _ => panic!("cannot {action}: registry responded with {status}: {reason}"),
+        }
+    }
+
+    /// sends request, unsuccessful responses are returned as status and registry's reason
+    pub async fn try_send(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Response, (StatusCode, String)> {
+        let response = request.send().await.expect("probably no internet");
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let reason = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|body| body.get("error").and_then(Value::as_str).map(String::from))
+            .unwrap_or(body);
+
+        return Err((status, reason));
+    }
+
+    pub async fn dist_tags(&self, package_name: &str) -> BTreeMap<String, String> {
+        let path = format!("-/package/{}/dist-tags", encode_name(package_name));
+        let request = self.request(Method::GET, &path);
+
+        return self
+            .send(request, &format!("list dist tags of {package_name}"))
+            .await
+            .json()
+            .await
+            .expect("cannot parse dist tags");
+    }
+
+    pub async fn add_dist_tag(&self, package_name: &str, version: &str, tag: &str) {
+        let path = format!("-/package/{}/dist-tags/{tag}", encode_name(package_name));
+        let request = self.request(Method::PUT, &path).json(&version);
+
+        self.send(
+            request,
+            &format!("add {tag} tag to {package_name}@{version}"),
+        )
+        .await;
+    }
+
+    pub async fn remove_dist_tag(&self, package_name: &str, tag: &str) {
+        let path = format!("-/package/{}/dist-tags/{tag}", encode_name(package_name));
+        let request = self.request(Method::DELETE, &path);
+
+        self.send(request, &format!("remove {tag} tag from {package_name}"))
+            .await;
+    }
+
+    /// full packument with `_rev`, that can be modified and put back with `update_packument`
+    pub async fn packument(&self, package_name: &str) -> Value {
+        let path = format!("{}?write=true", encode_name(package_name));
+        let request = self.request(Method::GET, &path);
+
+        return self
+            .send(request, &format!("fetch {package_name}"))
+            .await
+            .json()
+            .await
+            .expect("cannot parse packument");
+    }
+
+    /// versions of package in registry, empty when package was never published
+    pub async fn published_versions(&self, package_name: &str) -> Vec<String> {
+        let request = self.request(Method::GET, &encode_name(package_name));
+
+        let packument: Value = match self.try_send(request).await {
+            Ok(response) => response.json().await.expect("cannot parse packument"),
+            Err((StatusCode::NOT_FOUND, _)) => return vec![],
+            Err((status, reason)) => {
+                panic!("cannot fetch {package_name}: registry responded with {status}: {reason}")
+            }
+        };
+
+        return packument
+            .get("versions")
+            .and_then(Value::as_object)
+            .map(|versions| versions.keys().cloned().collect())
+            .unwrap_or_default();
+    }
+
+    pub async fn update_packument(&self, package_name: &str, packument: &Value) {
+        let path = encode_name(package_name);
+        let request = self.request(Method::PUT, &path).json(packument);
+
+        self.send(request, &format!("update {package_name}")).await;
+    }
+
+    /// puts new version (with tarball in `_attachments`) into registry
+    pub async fn publish(&self, package_name: &str, version: &str, document: &Value) {
+        let path = encode_name(package_name);
+        let request = self.request(Method::PUT, &path).json(document);
+
+        self.send(request, &format!("publish {package_name}@{version}"))
+            .await;
+    }
+
+    /// where registry will serve published tarball from
+    pub fn tarball_url(&self, package_name: &str, version: &str) -> String {
+        return format!(
+            "{}/{package_name}/-/{package_name}-{version}.tgz",
+            self.registry_url
+        );
+    }
+
+    /// `public` or `restricted`
+    pub async fn visibility(&self, package_name: &str) -> String {
+        let path = format!("-/package/{}/visibility", encode_name(package_name));
+        let request = self.request(Method::GET, &path);
+
+        let visibility: Value = self
+            .send(request, &format!("get access of {package_name}"))
+            .await
+            .json()
+            .await
+            .expect("cannot parse package visibility");
+
+        let public = visibility
+            .get("public")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        return if public { "public" } else { "restricted" }.to_string();
+    }
+
+    pub async fn set_access(&self, package_name: &str, access: &str) {
+        let path = format!("-/package/{}/access", encode_name(package_name));
+        let request = self
+            .request(Method::POST, &path)
+            .json(&serde_json::json!({ "access": access }));
+
+        self.send(
+            request,
+            &format!("set access of {package_name} to {access}"),
+        )
+        .await;
+    }
+
+    /// `team` is `scope:team`, `permissions` is `read-only` or `read-write`
+    pub async fn grant_access(&self, package_name: &str, team: &str, permissions: &str) {
+        let path = team_package_path(team);
+        let request = self
+            .request(Method::PUT, &path)
+            .json(&serde_json::json!({ "package": package_name, "permissions": permissions }));
+
+        self.send(request, &format!("grant {team} access to {package_name}"))
+            .await;
+    }
+
+    pub async fn revoke_access(&self, package_name: &str, team: &str) {
+        let path = team_package_path(team);
+        let request = self
+            .request(Method::DELETE, &path)
+            .json(&serde_json::json!({ "package": package_name }));
+
+        self.send(request, &format!("revoke {team} access to {package_name}"))
+            .await;
+    }
+
+    /// `{ name, email }` of registry user, needed to add them as maintainer
+    pub async fn user(&self, username: &str) -> Value {
+        let path = format!("-/user/org.couchdb.user:{username}");
+        let request = self.request(Method::GET, &path);
+
+        return self
+            .send(request, &format!("find user {username}"))
+            .await
+            .json()
+            .await
+            .expect("cannot parse user");
+    }
+}
+
+fn team_package_path(team: &str) -> String {
+    let (scope, team_name) = team
+        .trim_start_matches("@")
+        .split_once(":")
+        .expect(format!("team must be <scope>:<team>, got {team}").as_str());
+
+    return format!("-/team/{scope}/{team_name}/package");
+}
+
+/// scoped names have to be sent as `@scope%2fname`
+pub fn encode_name(package_name: &str) -> String {
+    return package_name.replace("/", "%2f");
+}
@@ -0,0 +1,180 @@
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use crate::{
+    bin_linker,
+    fs_retry::retry,
+    install,
+    lockfile::{Lockfile, LOCKFILE},
+    manifest::{self, PACKAGE_JSON},
+    workspaces, InstallOptions, ResolutionGraph, NODE_MODULES,
+};
+
+/// sections of package.json, that removed package is dropped from
+const SECTIONS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+/// `razee remove <pkg>... [-w <workspace>]`: drops packages from package.json and installs
+/// the rest, packages that are no longer in the graph (removed ones and dependencies only
+/// they had) are deleted from node_modules and lockfile
+pub async fn remove(args: &[String]) {
+    let usage = "usage: razee remove <package>... [--workspace|-w <workspace>]";
+
+    let workspace_flag = args
+        .iter()
+        .position(|arg| arg == "--workspace" || arg == "-w");
+    let workspace = workspace_flag.map(|index| args.get(index + 1).expect(usage));
+
+    let names: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(index, arg)| {
+            !arg.starts_with("-") && workspace_flag.map(|flag| flag + 1) != Some(*index)
+        })
+        .map(|(_, arg)| arg)
+        .collect();
+
+    if names.is_empty() {
+        panic!("{usage}");
+    }
+
+    let current_dir = env::current_dir().expect("cannot get current dir");
+    let root = workspaces::find_root(&current_dir).unwrap_or(current_dir.clone());
+
+    let target_dir = match workspace {
+        Some(workspace) => workspaces::find_workspace(&root, workspace)
+            .expect(format!("no workspace {workspace} in {}", root.display()).as_str()),
+        None => current_dir,
+    };
+
+    let manifest_path = target_dir.join(PACKAGE_JSON);
+    let mut manifest = manifest::read_raw(&manifest_path);
+    let mut removed = vec![];
+
+    for name in names {
+        let mut is_removed = false;
+
+        for section in SECTIONS {
+            is_removed |= manifest::remove_entry(&mut manifest, section, name);
+        }
+
+        match is_removed {
+            true => removed.push(name.as_str()),
+            false => eprintln!(
+                "warning: {name} is not a dependency of {}",
+                manifest_path.display()
+            ),
+        }
+    }
+
+    if removed.is_empty() {
+        return;
+    }
+
+    manifest::write_raw(&manifest_path, &manifest);
+
+    env::set_current_dir(&root).expect("cannot enter workspace root");
+
+    let installed_before: Vec<String> = Lockfile::read(Path::new(LOCKFILE))
+        .map(|lockfile| lockfile.packages.into_keys().collect())
+        .unwrap_or_default();
+
+    let report = install(InstallOptions {
+        interactive: io::stdin().is_terminal(),
+        ..Default::default()
+    })
+    .await;
+
+    for name in removed.iter() {
+        match report.graph.get(name) {
+            Some(_) => println!(
+                "{name} is still installed, as dependency of {}",
+                report.graph.dependents(name).join(", ")
+            ),
+            None => println!("- {name}"),
+        }
+    }
+
+    remove_leftovers(installed_before, &report.graph);
+}
+
+/// lockfile is already written from new graph, only node_modules has leftovers, packages
+/// at `installed_before` paths (lockfile keys) that are not in `graph` are removed
+pub(crate) fn remove_leftovers(installed_before: Vec<String>, graph: &ResolutionGraph) {
+    for path in installed_before {
+        let Some(install_path) = path.strip_prefix(&format!("{NODE_MODULES}/")) else {
+            continue;
+        };
+
+        if graph.packages.contains_key(install_path) {
+            continue;
+        }
+
+        if let Err(error) = remove_package(Path::new(&path)) {
+            eprintln!("warning: cannot remove {path}: {error}");
+        }
+    }
+
+    remove_dangling_bins(&Path::new(NODE_MODULES).join(".bin"));
+}
+
+/// removes package dir (or link to it), and dirs around it, when they are left empty
+pub(crate) fn remove_package(package_dir: &Path) -> io::Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(package_dir) else {
+        return Ok(());
+    };
+
+    match metadata.is_dir() {
+        true => retry(package_dir, || fs::remove_dir_all(package_dir))?,
+        false => retry(package_dir, || fs::remove_file(package_dir))?,
+    }
+
+    // scope dir and nested node_modules, e.g. `node_modules/a/node_modules/@scope`
+    for dir in package_dir.ancestors().skip(1).take(2) {
+        let is_removable = dir
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("@") || name == NODE_MODULES);
+
+        if !is_removable || dir == Path::new(NODE_MODULES) {
+            break;
+        }
+
+        // fails while there are other packages in it
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+
+    return Ok(());
+}
+
+/// bins of removed packages point to nothing now
+pub(crate) fn remove_dangling_bins(bin_dir: &Path) {
+    for entry in fs::read_dir(bin_dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+
+        if fs::metadata(&path).is_err() && fs::symlink_metadata(&path).is_ok() {
+            let _ = retry(&path, || fs::remove_file(&path));
+        }
+
+        let is_dangling_shim = path.extension().is_none()
+            && bin_linker::shim_target(&path).is_some_and(|target| !target.exists());
+
+        if is_dangling_shim {
+            for shim in [
+                path.clone(),
+                path.with_extension("cmd"),
+                path.with_extension("ps1"),
+            ] {
+                let _ = retry(&shim, || fs::remove_file(&shim));
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io::BufReader, path::Path, sync::Arc, time::Duration};
+
+use crate::{
+    http_client::HttpClient, peers::UnmetPeer, registry::Registry, Manifest, ResolutionGraph,
+    NODE_MODULES,
+};
+
+/// options for [`install`](crate::install), everything is optional and
+/// defaults to what razee binary would do in current directory
+#[derive(Debug, Default)]
+pub struct InstallOptions {
+    /// defaults to package.json from current directory
+    pub manifest: Option<Manifest>,
+    /// defaults to registry from environment
+    pub registry: Option<Registry>,
+    /// whether install can ask questions on stdin
+    pub interactive: bool,
+    /// reuses client, e.g. the one that already resolved install plan
+    pub client: Option<Arc<HttpClient>>,
+    /// see [`Installer::frozen_lockfile`](crate::Installer::frozen_lockfile)
+    pub frozen_lockfile: bool,
+    /// see [`Installer::fs_concurrency`](crate::Installer::fs_concurrency)
+    pub fs_concurrency: Option<usize>,
+    /// see [`Installer::verify_signatures`](crate::Installer::verify_signatures), `false`
+    /// keeps default from environment
+    pub verify_signatures: bool,
+    /// see [`Installer::ignore_scripts`](crate::Installer::ignore_scripts), `false` keeps
+    /// default from environment
+    pub ignore_scripts: bool,
+    /// see [`Installer::production`](crate::Installer::production), `None` keeps default
+    /// from environment
+    pub production: Option<bool>,
+    /// fails install on unmet peers, `false` keeps default from environment, see
+    /// [`Installer::peers`](crate::Installer::peers)
+    pub strict_peer_dependencies: bool,
+    /// installs missing peers, `false` keeps default from environment, see
+    /// [`Installer::peers`](crate::Installer::peers)
+    pub auto_install_peers: bool,
+    /// see [`Installer::engine_strict`](crate::Installer::engine_strict), `false` keeps
+    /// default from environment
+    pub engine_strict: bool,
+    /// see [`Installer::strict_package_manager`](crate::Installer::strict_package_manager),
+    /// `false` keeps default from environment
+    pub strict_package_manager: bool,
+    /// see [`Installer::update`](crate::Installer::update)
+    pub update: Option<Vec<String>>,
+}
+
+/// structured result of install, so tools don't need to parse razee output
+#[derive(Debug)]
+pub struct InstallReport {
+    pub graph: ResolutionGraph,
+    /// `name@version` of packages that were not installed before (or had other version)
+    pub added: Vec<String>,
+    /// `name@version` of previously installed packages that are no longer in graph,
+    /// they are left in node_modules
+    pub removed: Vec<String>,
+    /// missing peers and ones with incompatible versions, they are in `warnings` too
+    pub unmet_peers: Vec<UnmetPeer>,
+    pub warnings: Vec<String>,
+    pub timings: InstallTimings,
+}
+
+/// resolution and fetching are summed across all packages, they run concurrently,
+/// so they can be longer than total
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InstallTimings {
+    pub total: Duration,
+    pub resolution: Duration,
+    pub fetching: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstalledPackage {
+    name: String,
+    version: String,
+}
+
+/// name -> version of packages installed at the top of node_modules
+pub(crate) fn installed_packages() -> HashMap<String, String> {
+    let mut installed = HashMap::new();
+
+    collect_installed(Path::new(NODE_MODULES), &mut installed);
+
+    return installed;
+}
+
+fn collect_installed(dir: &Path, installed: &mut HashMap<String, String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        if file_name.starts_with(".") {
+            continue;
+        }
+
+        if file_name.starts_with("@") {
+            collect_installed(&entry.path(), installed);
+            continue;
+        }
+
+        let Ok(manifest) = fs::File::open(entry.path().join("package.json")) else {
+            continue;
+        };
+
+        if let Ok(package) =
+            serde_json::from_reader::<_, InstalledPackage>(BufReader::new(manifest))
+        {
+            installed.insert(package.name, package.version);
+        }
+    }
+}
+
+/// compares installed packages before install with resolved graph
+pub(crate) fn diff_installed(
+    before: &HashMap<String, String>,
+    graph: &ResolutionGraph,
+) -> (Vec<String>, Vec<String>) {
+    let mut added: Vec<String> = graph
+        .hoisted()
+        .filter(|package| before.get(&package.name) != Some(&package.version))
+        .map(|package| format!("{}@{}", package.name, package.version))
+        .collect();
+
+    let mut removed: Vec<String> = before
+        .iter()
+        .filter(|(name, version)| {
+            graph
+                .get(name)
+                .is_none_or(|package| &package.version != *version)
+        })
+        .map(|(name, version)| format!("{name}@{version}"))
+        .collect();
+
+    added.sort();
+    removed.sort();
+
+    return (added, removed);
+}
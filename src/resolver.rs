@@ -0,0 +1,774 @@
+use node_semver::{Range, Version};
+use serde::Serialize;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    git,
+    http_client::HttpClient,
+    import::{self, SeededVersion},
+    installer::InstallContext,
+    local_dir, local_tarball, logger, portal, workspaces, Dep, Dependency, DependencyDist,
+    RegistryPackage, NODE_MODULES,
+};
+
+/// resolved packages keyed by install path relative to node_modules: name for packages
+/// at the top, `dependent/node_modules/name` for versions nested under their dependents
+#[derive(Debug, Default)]
+pub struct ResolutionGraph {
+    /// name of root package
+    pub root: String,
+    pub packages: HashMap<String, Dependency>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// `from` package depends on `to` with `range`
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub range: String,
+    /// `to` was already resolved through another dependent, so it is shared
+    pub deduped: bool,
+}
+
+impl ResolutionGraph {
+    pub fn len(&self) -> usize {
+        return self.packages.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.packages.is_empty();
+    }
+
+    /// package at the top of node_modules, the one root package gets
+    pub fn get(&self, name: &str) -> Option<&Dependency> {
+        return self.packages.get(name);
+    }
+
+    /// packages at the top of node_modules
+    pub fn hoisted(&self) -> impl Iterator<Item = &Dependency> {
+        return self
+            .packages
+            .iter()
+            .filter(|(install_path, _)| !install_path.contains(&format!("/{NODE_MODULES}/")))
+            .map(|(_, package)| package);
+    }
+
+    /// names of packages (root and workspace packages too) that depend on `name`
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        let mut dependents: Vec<&str> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.to == name)
+            .map(|edge| edge.from.as_str())
+            .collect();
+
+        dependents.sort();
+        dependents.dedup();
+
+        return dependents;
+    }
+}
+
+/// same package can be requested by several groups (e.g. `dependencies` and
+/// `devDependencies`, or several workspace packages), it is resolved once with range
+/// that satisfies every request, returns merged specs and conflicts, when there is no
+/// such range
+pub(crate) fn merge_root_specs(specs: Vec<(String, Dep)>) -> (Vec<Dep>, Vec<String>) {
+    let mut merged: Vec<(String, Dep)> = vec![];
+    let mut conflicts = vec![];
+
+    for (group, dep) in specs {
+        let Some((merged_group, merged_dep)) = merged
+            .iter_mut()
+            .find(|(_, merged)| merged.name == dep.name)
+        else {
+            merged.push((group, dep));
+            continue;
+        };
+
+        if merged_dep.version == dep.version {
+            continue;
+        }
+
+        let merged_range = Range::parse(&merged_dep.version);
+        let range = Range::parse(&dep.version);
+
+        let intersection = match (&merged_range, &range) {
+            (Ok(merged_range), Ok(range)) if merged_range.allows_all(range) => {
+                Some(dep.version.clone())
+            }
+            (Ok(merged_range), Ok(range)) if range.allows_all(merged_range) => {
+                Some(merged_dep.version.clone())
+            }
+            (Ok(merged_range), Ok(range)) => merged_range
+                .intersect(range)
+                .map(|intersection| intersection.to_string()),
+            // tags, urls and other specs can only be equal
+            _ => None,
+        };
+
+        match intersection {
+            Some(version) => merged_dep.version = version,
+            None => conflicts.push(format!(
+                "{} is requested as {} in {merged_group} and as {} in {group}, \
+                which cannot be satisfied together, using {}",
+                dep.name, merged_dep.version, dep.version, merged_dep.version
+            )),
+        }
+    }
+
+    return (merged.into_iter().map(|(_, dep)| dep).collect(), conflicts);
+}
+
+/// install paths, where node looks for `name` required from package at `parent_path`,
+/// nearest first, same as `require` walks up node_modules dirs
+pub(crate) fn lookup_paths(parent_path: Option<&str>, name: &str) -> Vec<String> {
+    let mut paths = vec![];
+    let mut current = parent_path;
+
+    while let Some(path) = current {
+        paths.push(format!("{path}/{NODE_MODULES}/{name}"));
+        current = path
+            .rsplit_once(&format!("/{NODE_MODULES}/"))
+            .map(|(parent, _)| parent);
+    }
+
+    paths.push(name.to_string());
+
+    return paths;
+}
+
+/// `""`, `*`, `x` and `latest` all mean "whatever is tagged as latest", same as in npm
+pub(crate) fn is_latest_spec(spec: &str) -> bool {
+    return matches!(spec.trim(), "" | "*" | "x" | "X" | "latest");
+}
+
+/// versions, that can be installed, `time` of full packument also has unpublished ones
+pub(crate) fn published_versions(package: &RegistryPackage) -> Vec<Version> {
+    return package
+        .versions
+        .keys()
+        .filter_map(|key| match Version::parse(key) {
+            Ok(version) => Some(version),
+            Err(_) => {
+                logger::log_debug(&format!(
+                    "skipping invalid version {key} of {}",
+                    package.name
+                ));
+                None
+            }
+        })
+        .collect();
+}
+
+/// version tagged as `latest`, when it satisfies requested range, like npm picks it,
+/// otherwise highest satisfying version. Prereleases satisfy only ranges with prerelease
+/// of the same `major.minor.patch`, so `^1.0.0` never gets `1.1.0-beta.0`, while
+/// `>=1.1.0-beta.0` gets `1.1.0-beta.1`, but not `1.2.0-beta.0`
+pub(crate) fn resolve_version(package: &RegistryPackage, requested_version: &Range) -> Version {
+    let versions = published_versions(package);
+
+    let tagged_version = package
+        .dist_tags
+        .get("latest")
+        .and_then(|latest| Version::parse(latest).ok())
+        .filter(|latest| versions.contains(latest) && requested_version.satisfies(latest));
+
+    if let Some(version) = tagged_version {
+        return version;
+    }
+
+    let satisfied_version = versions
+        .iter()
+        .filter(|version| requested_version.satisfies(version))
+        .max();
+
+    // highest version out of range (and prerelease least of all) is not what was asked for
+    return satisfied_version
+        .expect(
+            format!(
+                "no version of {} satisfies {requested_version}",
+                package.name
+            )
+            .as_str(),
+        )
+        .clone();
+}
+
+/// version tagged as `latest`, unless it is prerelease, then highest stable version,
+/// prereleases are picked only when nothing else was published (tagged one first),
+/// see [`is_prerelease_only`]
+pub(crate) fn resolve_latest(package: &RegistryPackage) -> Version {
+    let versions = published_versions(package);
+
+    let tagged_version = package
+        .dist_tags
+        .get("latest")
+        .and_then(|latest| Version::parse(latest).ok())
+        .filter(|latest| versions.contains(latest));
+
+    let stable_version = tagged_version
+        .iter()
+        .filter(|latest| !latest.is_prerelease())
+        .chain(
+            versions
+                .iter()
+                .filter(|version| !version.is_prerelease())
+                .max(),
+        )
+        .next();
+
+    return stable_version
+        .or(tagged_version.as_ref())
+        .or(versions.iter().max())
+        .expect(format!("no valid versions of {} found", package.name).as_str())
+        .clone();
+}
+
+/// `latest` of package with only prereleases is prerelease, which is worth a warning
+pub(crate) fn is_prerelease_only(package: &RegistryPackage) -> bool {
+    return published_versions(package)
+        .iter()
+        .all(|version| version.is_prerelease());
+}
+
+/// version tagged as `tag` in `dist-tags`
+pub(crate) fn resolve_tag(package: &RegistryPackage, tag: &str) -> Version {
+    let tagged = package.dist_tags.get(tag.trim()).expect(
+        format!(
+            "{} is requested as {tag}, which is neither a version range, nor its dist-tag",
+            package.name
+        )
+        .as_str(),
+    );
+
+    return Version::parse(tagged).expect(
+        format!(
+            "{} has invalid version {tagged} tagged as {tag}",
+            package.name
+        )
+        .as_str(),
+    );
+}
+
+pub(crate) async fn fetch_dep(
+    dep: &Dep,
+    context: &InstallContext,
+    client: Arc<HttpClient>,
+) -> Dependency {
+    context.hooks.before_resolve(dep);
+
+    if local_tarball::is_local_tarball(&dep.version) {
+        let dependency = Dependency {
+            // aliased `"foo": "file:./bar.tgz"` is installed as foo too
+            name: dep.name.clone(),
+            ..local_tarball::read_dependency(&dep.version)
+        };
+
+        verify_locked_tarball(dep, &dependency, context);
+        context.hooks.after_resolve(dep, &dependency);
+
+        return dependency;
+    }
+
+    if local_tarball::is_tarball_url(&dep.version) {
+        let tarball = client
+            .fetch_tarball(&DependencyDist {
+                integrity: String::new(),
+                shasum: None,
+                tarball: dep.version.clone(),
+                file_count: None,
+                unpacked_size: None,
+                signatures: vec![],
+                attestations: None,
+            })
+            .await;
+        let dependency = Dependency {
+            // package is installed under the name it was requested with
+            name: dep.name.clone(),
+            ..local_tarball::to_dependency(tarball, &dep.version)
+        };
+
+        verify_locked_tarball(dep, &dependency, context);
+        context.hooks.after_resolve(dep, &dependency);
+
+        return dependency;
+    }
+
+    if local_dir::is_local_dir(&dep.version) {
+        let dependency = local_dir::read_dependency(&dep.name, &dep.version);
+
+        context.hooks.after_resolve(dep, &dependency);
+
+        return dependency;
+    }
+
+    if git::is_git(&dep.version) {
+        let dependency = git::read_dependency(&dep.name, &dep.version);
+
+        context.hooks.after_resolve(dep, &dependency);
+
+        return dependency;
+    }
+
+    if portal::is_portal(&dep.version) {
+        let dependency = portal::read_dependency(&dep.name, &dep.version);
+
+        context.hooks.after_resolve(dep, &dependency);
+
+        return dependency;
+    }
+
+    if dep.version.starts_with("workspace:") {
+        let path = context.workspaces.get(&dep.name).expect(
+            format!(
+                "{} is requested as {}, but there is no such workspace package",
+                dep.name, dep.version
+            )
+            .as_str(),
+        );
+
+        // workspace packages are linked the same way as portals
+        let dependency = portal::read_dependency(&dep.name, &format!("portal:{path}"));
+
+        if !workspaces::allows(&dep.version, &dependency.version) {
+            panic!(
+                "{} is requested as {}, but workspace package has version {}",
+                dep.name, dep.version, dependency.version
+            );
+        }
+
+        context.hooks.after_resolve(dep, &dependency);
+
+        return dependency;
+    }
+
+    // `npm:bar@^2` installs bar under the name it is requested with
+    let (registry_name, normalized_version) = match parse_alias(&dep.version) {
+        Some((name, range)) => (name, range),
+        None => (dep.name.as_str(), dep.version.as_str()),
+    };
+    let registry_dep = Dep {
+        name: registry_name.to_string(),
+        version: normalized_version.to_string(),
+    };
+
+    let wants_latest = is_latest_spec(normalized_version);
+
+    // anything, that is not a range, is dist-tag, e.g. `next` or `beta`
+    let requested_version = match wants_latest {
+        true => Some(Range::any()),
+        false => Range::parse(normalized_version).ok(),
+    };
+
+    let seeded_version = requested_version
+        .as_ref()
+        .and_then(|range| import::seeded_version(&context.seed, registry_name, range));
+
+    let (resolved_version, seeded) = match seeded_version {
+        Some(seeded) => (seeded.version.clone(), Some(seeded)),
+        None => {
+            let package = client.fetch_package(&registry_dep).await;
+
+            match &requested_version {
+                _ if wants_latest && is_prerelease_only(package) => {
+                    let version = resolve_latest(package);
+
+                    context.warn(format!(
+                        "{registry_name} has only prereleases, installing {version} as its latest"
+                    ));
+
+                    (version, None)
+                }
+                _ if wants_latest => (resolve_latest(package), None),
+                Some(requested_version) => (resolve_version(package, requested_version), None),
+                None => (resolve_tag(package, normalized_version), None),
+            }
+        }
+    };
+
+    let mut dependency = client
+        .fetch_dependency(&registry_dep.name, &resolved_version)
+        .await
+        .to_owned();
+
+    // keeps tarball urls of migrated lockfile (e.g. from private mirror), content is
+    // still checked against registry integrity
+    if let Some(seeded) = seeded {
+        verify_seeded_integrity(&dependency, &seeded, context);
+
+        if let Some(tarball) = seeded.resolved {
+            dependency.dist.tarball = tarball;
+        }
+    }
+
+    if registry_name != dep.name {
+        dependency.alias_of = Some(dependency.name);
+        dependency.name = dep.name.clone();
+    }
+
+    context.hooks.after_resolve(dep, &dependency);
+
+    return dependency;
+}
+
+/// name and range of `npm:name@range` alias, any version of package without range
+pub(crate) fn parse_alias(spec: &str) -> Option<(&str, &str)> {
+    let package = spec.strip_prefix("npm:")?;
+
+    return match package.rsplit_once("@") {
+        Some((name, range)) if !name.is_empty() => Some((name, range)),
+        _ => Some((package, "*")),
+    };
+}
+
+/// package could be republished since other package manager locked it, only integrities
+/// of the same algorithm can be compared (yarn has sha1 for older packages)
+fn verify_seeded_integrity(
+    dependency: &Dependency,
+    seeded: &SeededVersion,
+    context: &InstallContext,
+) {
+    let Some(integrity) = &seeded.integrity else {
+        return;
+    };
+
+    let algorithm = |integrity: &str| integrity.split("-").next().map(str::to_string);
+
+    if algorithm(integrity) == algorithm(&dependency.dist.integrity)
+        && integrity != &dependency.dist.integrity
+    {
+        context.warn(format!(
+            "{}@{} has different integrity in registry than in imported lockfile",
+            dependency.name, dependency.version
+        ));
+    }
+}
+
+/// local tarball can change on disk (and one from url on server), make sure it is still
+/// the one that was locked
+fn verify_locked_tarball(dep: &Dep, dependency: &Dependency, context: &InstallContext) {
+    let locked = context.lockfile.as_ref().and_then(|lockfile| {
+        lockfile
+            .packages
+            .get(&format!("{NODE_MODULES}/{}", dep.name))
+    });
+
+    let Some(locked) = locked else {
+        return;
+    };
+
+    if locked.resolved.as_ref() != Some(&dependency.dist.tarball) {
+        return;
+    }
+
+    if let Some(integrity) = &locked.integrity {
+        if integrity != &dependency.dist.integrity {
+            panic!(
+                "{} does not match integrity in lockfile, run `razee add {}` to lock new content",
+                dependency.dist.tarball, dependency.dist.tarball
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use elsa::FrozenVec;
+    use serde_json::{json, Value};
+    use std::{cell::Cell, env, fs, process};
+    use tokio::sync::Semaphore;
+
+    use super::*;
+    use crate::{
+        engines::EngineCheck, hooks::Hooks, lifecycle::ScriptPolicy, overrides::Overrides,
+        platform::Platform, registry::Registry,
+    };
+
+    fn package(versions: &[&str], latest: Option<&str>) -> RegistryPackage {
+        let mut time: HashMap<String, String> = versions
+            .iter()
+            .map(|version| (version.to_string(), "2023-01-01T00:00:00.000Z".to_string()))
+            .collect();
+
+        time.insert(
+            "created".to_string(),
+            "2022-01-01T00:00:00.000Z".to_string(),
+        );
+        time.insert(
+            "modified".to_string(),
+            "2023-01-01T00:00:00.000Z".to_string(),
+        );
+        // unpublished versions stay in `time`
+        time.insert("9.9.9".to_string(), "2023-01-02T00:00:00.000Z".to_string());
+
+        return RegistryPackage {
+            time,
+            dist_tags: latest
+                .map(|latest| HashMap::from([("latest".to_string(), latest.to_string())]))
+                .unwrap_or_default(),
+            ..abbreviated_package(versions)
+        };
+    }
+
+    /// abbreviated packument has no `time`, only `versions`
+    fn abbreviated_package(versions: &[&str]) -> RegistryPackage {
+        return RegistryPackage {
+            name: "pkg".to_string(),
+            time: HashMap::new(),
+            dist_tags: HashMap::new(),
+            versions: versions
+                .iter()
+                .map(|version| {
+                    (
+                        version.to_string(),
+                        serde_json::json!({ "version": version }),
+                    )
+                })
+                .collect(),
+        };
+    }
+
+    fn version(version: &str) -> Version {
+        return Version::parse(version).unwrap();
+    }
+
+    const LATEST_SPECS: [&str; 5] = ["*", "", "x", "latest", " * "];
+
+    #[test]
+    fn latest_specs() {
+        for spec in LATEST_SPECS {
+            assert!(is_latest_spec(spec), "{spec:?} should mean latest");
+        }
+
+        for spec in ["^1", "1.x", "next", ">=0.0.0", "npm:other@*"] {
+            assert!(!is_latest_spec(spec), "{spec:?} should not mean latest");
+        }
+    }
+
+    #[test]
+    fn parses_aliases() {
+        assert_eq!(parse_alias("npm:bar@^2"), Some(("bar", "^2")));
+        assert_eq!(
+            parse_alias("npm:@scope/bar@next"),
+            Some(("@scope/bar", "next"))
+        );
+        assert_eq!(parse_alias("npm:@scope/bar"), Some(("@scope/bar", "*")));
+        assert_eq!(parse_alias("^2"), None);
+    }
+
+    #[test]
+    fn latest_uses_dist_tag() {
+        let package = package(&["1.0.0", "1.1.0", "2.0.0"], Some("1.1.0"));
+
+        assert_eq!(resolve_latest(&package), version("1.1.0"));
+    }
+
+    #[test]
+    fn latest_skips_newer_prerelease() {
+        let package = package(&["1.0.0", "1.1.0", "2.0.0-beta.1", "2.0.0-rc.0"], None);
+
+        assert_eq!(resolve_latest(&package), version("1.1.0"));
+    }
+
+    #[test]
+    fn latest_skips_prerelease_tag() {
+        let package = package(&["1.0.0", "2.0.0-beta.1"], Some("2.0.0-beta.1"));
+
+        assert_eq!(resolve_latest(&package), version("1.0.0"));
+    }
+
+    #[test]
+    fn latest_follows_tag_of_prerelease_only_package() {
+        let package = package(&["0.1.0-alpha.1", "0.1.0-alpha.2"], Some("0.1.0-alpha.1"));
+
+        assert_eq!(resolve_latest(&package), version("0.1.0-alpha.1"));
+        assert!(is_prerelease_only(&package));
+    }
+
+    #[test]
+    fn latest_ignores_tag_of_unknown_version() {
+        let package = package(&["1.0.0", "1.2.0", "2.0.0-beta.1"], Some("3.0.0"));
+
+        assert_eq!(resolve_latest(&package), version("1.2.0"));
+    }
+
+    #[test]
+    fn latest_of_prerelease_only_package() {
+        let package = package(&["0.1.0-alpha.1", "0.1.0-alpha.2"], None);
+
+        assert_eq!(resolve_latest(&package), version("0.1.0-alpha.2"));
+    }
+
+    #[test]
+    fn prereleases_satisfy_only_ranges_with_them() {
+        let package = package(
+            &["1.0.0", "1.1.0-beta.0", "1.1.0-beta.1", "1.2.0-beta.0"],
+            Some("1.0.0"),
+        );
+        let resolve = |range: &str| resolve_version(&package, &Range::parse(range).unwrap());
+
+        assert_eq!(resolve("^1.0.0"), version("1.0.0"));
+        assert_eq!(resolve(">=1.1.0-beta.0"), version("1.1.0-beta.1"));
+        assert_eq!(resolve("1.2.0-beta.0"), version("1.2.0-beta.0"));
+    }
+
+    #[test]
+    #[should_panic(expected = "no version of pkg satisfies")]
+    fn range_without_satisfying_version_fails() {
+        let package = package(&["1.0.0", "2.0.0-beta.0"], None);
+
+        resolve_version(&package, &Range::parse("^2.0.0").unwrap());
+    }
+
+    #[test]
+    fn range_prefers_latest_tag() {
+        let package = package(&["1.0.0", "1.1.0", "1.2.0", "2.0.0"], Some("1.1.0"));
+
+        assert_eq!(
+            resolve_version(&package, &Range::parse("^1").unwrap()),
+            version("1.1.0")
+        );
+        assert_eq!(
+            resolve_version(&package, &Range::parse(">=1.2.0").unwrap()),
+            version("2.0.0")
+        );
+    }
+
+    #[test]
+    fn tag_resolves_tagged_version() {
+        let mut package = package(&["1.0.0", "2.0.0-beta.1"], Some("1.0.0"));
+
+        package
+            .dist_tags
+            .insert("next".to_string(), "2.0.0-beta.1".to_string());
+
+        assert_eq!(resolve_tag(&package, "next"), version("2.0.0-beta.1"));
+    }
+
+    #[test]
+    fn range_resolves_highest_satisfying_version() {
+        let package = package(&["1.0.0", "1.3.0", "1.2.0", "2.0.0", "1.4.0-beta.0"], None);
+        let range = Range::parse("^1").unwrap();
+
+        assert_eq!(resolve_version(&package, &range), version("1.3.0"));
+    }
+
+    fn context(seed: import::ResolutionSeed) -> InstallContext {
+        return InstallContext {
+            seed,
+            lockfile: None,
+            patched_dependencies: HashMap::new(),
+            overrides: Overrides::default(),
+            workspaces: HashMap::new(),
+            store: None,
+            node_linker: Default::default(),
+            signatures: Default::default(),
+            scripts: ScriptPolicy {
+                ignore_scripts: true,
+                trusted_dependencies: None,
+            },
+            platform: Platform::from_env(),
+            engines: EngineCheck {
+                node_version: None,
+                strict: false,
+            },
+            fs_limit: Semaphore::new(1),
+            hooks: Hooks::default(),
+            link: false,
+            peers: Default::default(),
+            warnings: FrozenVec::new(),
+            skipped: FrozenVec::new(),
+            other_platform: FrozenVec::new(),
+            linked: FrozenVec::new(),
+            edges: FrozenVec::new(),
+            resolution_time: Cell::default(),
+            fetching_time: Cell::default(),
+        };
+    }
+
+    #[tokio::test]
+    async fn seeded_version_wins_over_highest_one() {
+        let dir = env::temp_dir().join(format!("razee-seeded-{}", process::id()));
+        let versions: serde_json::Map<String, Value> = ["1.0.0", "1.1.0", "1.2.0", "2.0.0"]
+            .iter()
+            .map(|version| {
+                let tarball = format!("pkg/-/pkg-{version}.tgz");
+                let document = json!({
+                    "name": "pkg",
+                    "version": version,
+                    "dist": { "integrity": "sha512-AAAA", "tarball": tarball },
+                });
+
+                return (version.to_string(), document);
+            })
+            .collect();
+        let packument = json!({
+            "name": "pkg",
+            "dist-tags": { "latest": "2.0.0" },
+            "versions": versions,
+        });
+
+        fs::create_dir_all(dir.join("pkg")).unwrap();
+        fs::write(dir.join("pkg").join("index.json"), packument.to_string()).unwrap();
+
+        let seeded = |version: &str| SeededVersion {
+            version: Version::parse(version).unwrap(),
+            resolved: None,
+            integrity: None,
+        };
+        let seed = import::ResolutionSeed::from([(
+            "pkg".to_string(),
+            vec![seeded("1.1.0"), seeded("1.0.0"), seeded("3.0.0")],
+        )]);
+        let client = Arc::new(HttpClient::with_registry(Registry::Fixture(dir.clone())));
+        let dep = |range: &str| Dep {
+            name: "pkg".to_string(),
+            version: range.to_string(),
+        };
+
+        let unseeded = fetch_dep(&dep("^1"), &context(Default::default()), client.clone()).await;
+        let seeded = fetch_dep(&dep("^1"), &context(seed.clone()), client.clone()).await;
+        // seeded versions outside of range are not used
+        let other_major = fetch_dep(&dep("^2"), &context(seed), client).await;
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(unseeded.version, "1.2.0");
+        assert_eq!(seeded.version, "1.1.0");
+        assert_eq!(other_major.version, "2.0.0");
+    }
+
+    #[test]
+    fn dependents_are_deduplicated() {
+        let edge = |from: &str, to: &str| GraphEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            range: "*".to_string(),
+            deduped: false,
+        };
+        let graph = ResolutionGraph {
+            root: "root".to_string(),
+            packages: HashMap::new(),
+            edges: vec![
+                edge("root", "a"),
+                edge("b", "a"),
+                edge("root", "b"),
+                edge("b", "a"),
+            ],
+        };
+
+        assert_eq!(graph.dependents("a"), vec!["b", "root"]);
+        assert!(graph.dependents("root").is_empty());
+    }
+
+    #[test]
+    fn abbreviated_packument_resolves_from_versions() {
+        let package = abbreviated_package(&["1.0.0", "1.3.0", "2.0.0"]);
+        let range = Range::parse("^1").unwrap();
+
+        assert_eq!(resolve_version(&package, &range), version("1.3.0"));
+        assert_eq!(resolve_latest(&package), version("2.0.0"));
+    }
+}
@@ -0,0 +1,85 @@
+use serde_json::Value;
+use std::{env, process};
+
+use crate::{
+    manifest::{self, PACKAGE_JSON},
+    scripts,
+};
+
+/// `razee run <script> [--if-present] [-- args...]`, runs `pre<script>` and
+/// `post<script>` around it when they are defined, like npm does. Scripts run in the closest
+/// dir with package.json, without script name available scripts are listed
+pub fn run(args: &[String]) {
+    let (own_args, script_args) = match args.iter().position(|arg| arg == "--") {
+        Some(index) => (&args[..index], &args[index + 1..]),
+        None => (args, &[][..]),
+    };
+
+    let if_present = own_args.iter().any(|arg| arg == "--if-present");
+    let name = own_args.iter().find(|arg| !arg.starts_with("-"));
+
+    let current_dir = env::current_dir().expect("cannot get current dir");
+    let dir = current_dir
+        .ancestors()
+        .find(|dir| dir.join(PACKAGE_JSON).is_file())
+        .expect(format!("no {PACKAGE_JSON} in {}", current_dir.display()).as_str());
+    let manifest = manifest::read_raw(&dir.join(PACKAGE_JSON));
+
+    let Some(name) = name else {
+        list_scripts(&manifest);
+        return;
+    };
+
+    let script = |name: &str| {
+        manifest
+            .get("scripts")
+            .and_then(|scripts| scripts.get(name))
+            .and_then(Value::as_str)
+            .map(String::from)
+    };
+
+    let Some(main_script) = script(name) else {
+        if if_present {
+            return;
+        }
+
+        panic!("missing script: {name}");
+    };
+
+    let pre_name = format!("pre{name}");
+    let post_name = format!("post{name}");
+
+    // pre and post scripts don't get extra arguments
+    let steps = [
+        script(&pre_name).map(|script| (pre_name.as_str(), script, &[][..])),
+        Some((name.as_str(), main_script, script_args)),
+        script(&post_name).map(|script| (post_name.as_str(), script, &[][..])),
+    ];
+
+    for (step_name, step_script, step_args) in steps.into_iter().flatten() {
+        let status = scripts::run_script(dir, step_name, &step_script, step_args);
+
+        if !status.success() {
+            process::exit(scripts::exit_code(status));
+        }
+    }
+}
+
+fn list_scripts(manifest: &Value) {
+    let scripts = manifest
+        .get("scripts")
+        .and_then(Value::as_object)
+        .filter(|scripts| !scripts.is_empty());
+
+    let Some(scripts) = scripts else {
+        println!("no scripts in {PACKAGE_JSON}");
+        return;
+    };
+
+    println!("scripts:");
+
+    for (name, script) in scripts {
+        println!("  {name}");
+        println!("    {}", script.as_str().unwrap_or_default());
+    }
+}
@@ -0,0 +1,120 @@
+use serde_json::Value;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+
+use crate::{manifest::PACKAGE_JSON, NODE_MODULES};
+
+/// runs package script in `dir` through system shell, with `node_modules/.bin` of `dir` and
+/// its parents (e.g. workspace root, where bins are hoisted to) in PATH and npm's
+/// `npm_lifecycle_event` and `npm_package_*` set, extra `args` are appended to it
+pub fn run_script(dir: &Path, name: &str, script: &str, args: &[String]) -> ExitStatus {
+    let mut command_line = script.to_string();
+
+    for arg in args {
+        command_line.push(' ');
+        command_line.push_str(&quote(arg));
+    }
+
+    let path = env::var_os("PATH").unwrap_or_default();
+    let path = env::join_paths(bin_dirs(dir).into_iter().chain(env::split_paths(&path)))
+        .expect("cannot build PATH for script");
+
+    println!("> {name}");
+    println!("> {command_line}");
+
+    let mut command = shell(&command_line);
+
+    command
+        .current_dir(dir)
+        .env("PATH", path)
+        .env("npm_lifecycle_event", name)
+        .env("npm_lifecycle_script", script);
+
+    // scripts of dependencies run in their dirs, but can still find the project
+    if env::var_os("INIT_CWD").is_none() {
+        if let Ok(current_dir) = env::current_dir() {
+            command.env("INIT_CWD", current_dir);
+        }
+    }
+
+    let manifest = fs::read_to_string(dir.join(PACKAGE_JSON))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+
+    for field in ["name", "version"] {
+        let value = manifest
+            .as_ref()
+            .and_then(|manifest| manifest.get(field))
+            .and_then(Value::as_str);
+
+        if let Some(value) = value {
+            command.env(format!("npm_package_{field}"), value);
+        }
+    }
+
+    return command
+        .status()
+        .expect(format!("cannot run script {name}").as_str());
+}
+
+/// existing `node_modules/.bin` dirs from `dir` up to the filesystem root, closest first
+fn bin_dirs(dir: &Path) -> Vec<PathBuf> {
+    let dir = dir.canonicalize().unwrap_or(dir.to_path_buf());
+
+    return dir
+        .ancestors()
+        .map(|ancestor| ancestor.join(NODE_MODULES).join(".bin"))
+        .filter(|bin_dir| bin_dir.is_dir())
+        .collect();
+}
+
+/// exit code for script status, killed scripts exit with `128 + signal`, like in shells
+pub fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+
+    return status.code().unwrap_or(1);
+}
+
+fn shell(command_line: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+
+        command.args(["/d", "/s", "/c", command_line]);
+
+        return command;
+    }
+
+    let mut command = Command::new("sh");
+
+    command.args(["-c", command_line]);
+
+    return command;
+}
+
+/// quotes argument for shell, unless it is safe as is
+fn quote(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+%".contains(c));
+
+    if is_safe {
+        return arg.to_string();
+    }
+
+    if cfg!(windows) {
+        return format!("\"{}\"", arg.replace("\"", "\\\""));
+    }
+
+    return format!("'{}'", arg.replace("'", "'\\''"));
+}
@@ -0,0 +1,201 @@
+use node_semver::Version;
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Verifier};
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::permissions;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/logotip4ik/razee/releases/latest";
+const USER_AGENT: &str = "Razee (Node Package Manger in Rust)";
+
+/// ECDSA P-256 key, that signs release binaries, it is pinned in the binary, so release
+/// assets can't bring their own
+const RELEASE_PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEcMjhkeKGMZS6K1m+g+LIvrrz8aBc
+Sn2xpGdiLXhVBDS6zS8xyieZH7mguZwJMjPiLD6EIM94338oVIP3NqH7Og==
+-----END PUBLIC KEY-----";
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// replaces running razee binary with the latest released one. Every release
+/// asset `razee-<arch>-<os>` has `razee-<arch>-<os>.sig` next to it, a detached signature
+/// made with the release key, binary is not replaced unless it matches `RELEASE_PUBLIC_KEY`
+pub async fn self_update() {
+    let client = reqwest::Client::new();
+
+    let release: Release = client
+        .get(RELEASES_URL)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .expect("probably no internet")
+        .error_for_status()
+        .expect("cannot get latest razee release")
+        .json()
+        .await
+        .expect("cannot parse latest razee release");
+
+    let current_version = Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
+    let latest_version = Version::parse(release.tag_name.trim_start_matches("v"))
+        .expect(format!("invalid release version: {}", release.tag_name).as_str());
+
+    if latest_version <= current_version {
+        println!("razee {current_version} is already the latest version");
+        return;
+    }
+
+    let binary_name = platform_binary_name();
+    let signature_name = format!("{binary_name}.sig");
+
+    let binary_url = asset_url(&release, &binary_name);
+    let signature_url = asset_url(&release, &signature_name);
+
+    println!("Downloading razee {latest_version} ({binary_name})");
+
+    let binary = download(&client, &binary_url).await;
+    let signature = download(&client, &signature_url).await;
+
+    if !verify_release(&binary, &signature, RELEASE_PUBLIC_KEY) {
+        panic!("signature of {binary_name} doesn't match razee release key, not updating");
+    }
+
+    let current_exe = env::current_exe().expect("cannot find current razee executable");
+
+    replace_executable(&current_exe, &binary);
+
+    println!("Updated razee {current_version} -> {latest_version}");
+}
+
+/// checks DER encoded ECDSA (sha256) `signature` of `binary`, like
+/// `openssl dgst -sha256 -sign release.pem` makes it, broken signatures are just invalid
+fn verify_release(binary: &[u8], signature: &[u8], public_key: &str) -> bool {
+    let public_key =
+        PKey::public_key_from_pem(public_key.as_bytes()).expect("release key is not valid");
+
+    let verified = Verifier::new(MessageDigest::sha256(), &public_key)
+        .and_then(|mut verifier| verifier.verify_oneshot(signature, binary));
+
+    return verified.unwrap_or(false);
+}
+
+fn platform_binary_name() -> String {
+    let extension = if cfg!(windows) { ".exe" } else { "" };
+
+    return format!("razee-{}-{}{extension}", env::consts::ARCH, env::consts::OS);
+}
+
+fn asset_url(release: &Release, name: &str) -> String {
+    return release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .map(|asset| asset.browser_download_url.clone())
+        .expect(format!("release {} has no {name}", release.tag_name).as_str());
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Vec<u8> {
+    return client
+        .get(url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .expect("probably no internet")
+        .error_for_status()
+        .expect(format!("cannot download {url}").as_str())
+        .bytes()
+        .await
+        .expect(format!("cannot download {url}").as_str())
+        .to_vec();
+}
+
+/// writes new binary next to current one and renames it over, so there is
+/// never a moment without working razee executable
+fn replace_executable(current_exe: &Path, binary: &[u8]) {
+    let exe_dir = current_exe.parent().expect("executable has no parent dir");
+    let new_exe = exe_dir.join(format!(".razee-update-{}", std::process::id()));
+
+    fs::write(&new_exe, binary).expect("cannot write new razee executable");
+    permissions::make_executable(&new_exe).expect("cannot make new razee executable");
+
+    // windows can't overwrite running executable, but can rename it
+    if cfg!(windows) {
+        let old_exe: PathBuf = current_exe.with_extension("old.exe");
+
+        let _ = fs::remove_file(&old_exe);
+        fs::rename(current_exe, &old_exe).expect("cannot move current razee executable");
+    }
+
+    if let Err(error) = fs::rename(&new_exe, current_exe) {
+        let _ = fs::remove_file(&new_exe);
+
+        panic!("cannot replace razee executable: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::{
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+        pkey::Private,
+        sign::Signer,
+    };
+
+    use super::*;
+
+    fn generate_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+
+        return PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    }
+
+    fn public_pem(key: &PKey<Private>) -> String {
+        return String::from_utf8(key.public_key_to_pem().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn release_must_be_signed_with_pinned_key() {
+        let key = generate_key();
+        let binary = b"\x7fELF razee".to_vec();
+        let signature = Signer::new(MessageDigest::sha256(), &key)
+            .unwrap()
+            .sign_oneshot_to_vec(&binary)
+            .unwrap();
+
+        assert!(verify_release(&binary, &signature, &public_pem(&key)));
+
+        assert!(!verify_release(
+            b"\x7fELF evil",
+            &signature,
+            &public_pem(&key)
+        ));
+        assert!(!verify_release(
+            &binary,
+            &signature,
+            &public_pem(&generate_key())
+        ));
+        assert!(!verify_release(
+            &binary,
+            b"not a signature",
+            &public_pem(&key)
+        ));
+    }
+
+    #[test]
+    fn pinned_release_key_is_valid() {
+        assert!(PKey::public_key_from_pem(RELEASE_PUBLIC_KEY.as_bytes()).is_ok());
+    }
+}
@@ -0,0 +1,535 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::DateTime;
+use node_semver::Version;
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Verifier};
+use serde::Deserialize;
+use std::{collections::HashMap, env};
+
+use crate::{git, http_client::HttpClient, Dependency, DependencyDist};
+
+mod sigstore;
+
+/// predicate of attestations, that tell where and how package was built
+const PROVENANCE_PREDICATE: &str = "https://slsa.dev/provenance/";
+
+/// what is verified about packages, besides integrity of their tarballs
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SignaturePolicy {
+    /// registry signature of every package, and attestations of packages that have them
+    pub verify_signatures: bool,
+    /// scopes (e.g. `@company`), packages of which must have valid provenance
+    pub require_provenance: Vec<String>,
+}
+
+impl SignaturePolicy {
+    /// `RAZEE_VERIFY_SIGNATURES=true`, `RAZEE_REQUIRE_PROVENANCE=@company,@other`
+    pub(crate) fn from_env() -> SignaturePolicy {
+        return SignaturePolicy {
+            verify_signatures: env::var("RAZEE_VERIFY_SIGNATURES")
+                .is_ok_and(|verify| verify == "true" || verify == "1"),
+            require_provenance: env::var("RAZEE_REQUIRE_PROVENANCE")
+                .map(|scopes| {
+                    scopes
+                        .split(",")
+                        .map(|scope| scope.trim().to_string())
+                        .filter(|scope| !scope.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+    }
+
+    fn requires_provenance(&self, package_name: &str) -> bool {
+        return self.require_provenance.iter().any(|scope| {
+            package_name
+                .strip_prefix(scope.as_str())
+                .is_some_and(|name| name.starts_with("/"))
+        });
+    }
+}
+
+/// public key from `/-/npm/v1/keys`
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RegistryKey {
+    pub keyid: String,
+    /// base64 of DER encoded public key (SPKI)
+    pub key: String,
+    /// packages published after key expired can't be signed with it
+    pub expires: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Attestations {
+    attestations: Vec<Attestation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Attestation {
+    #[serde(rename = "predicateType")]
+    predicate_type: String,
+    bundle: Bundle,
+}
+
+/// sigstore bundle, only fields needed to check signature of envelope
+#[derive(Debug, Deserialize)]
+struct Bundle {
+    #[serde(rename = "verificationMaterial")]
+    verification_material: VerificationMaterial,
+    #[serde(rename = "dsseEnvelope")]
+    dsse_envelope: Envelope,
+}
+
+/// publish attestations are signed with registry key, provenance with short lived
+/// certificate of the build
+#[derive(Debug, Deserialize)]
+struct VerificationMaterial {
+    #[serde(rename = "publicKey")]
+    public_key: Option<KeyHint>,
+    #[serde(rename = "x509CertificateChain")]
+    certificate_chain: Option<CertificateChain>,
+    certificate: Option<Certificate>,
+    #[serde(rename = "tlogEntries", default)]
+    tlog_entries: Vec<sigstore::TlogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyHint {
+    hint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CertificateChain {
+    certificates: Vec<Certificate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Certificate {
+    /// base64 of DER encoded certificate
+    #[serde(rename = "rawBytes")]
+    raw_bytes: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    /// base64 of in-toto statement
+    payload: String,
+    #[serde(rename = "payloadType")]
+    payload_type: String,
+    signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnvelopeSignature {
+    sig: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Statement {
+    subject: Vec<Subject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Subject {
+    name: String,
+    digest: HashMap<String, String>,
+}
+
+/// verifies registry signature and attestations of package, as policy requires. Both
+/// cover integrity of tarball, which is checked once tarball is downloaded
+pub(crate) async fn verify(
+    package: &Dependency,
+    policy: &SignaturePolicy,
+    client: &HttpClient,
+) -> Result<(), String> {
+    let requires_provenance = policy.requires_provenance(package.registry_name());
+
+    // local tarballs and git repos don't come from registry, there is nobody to sign them
+    if !(policy.verify_signatures || requires_provenance)
+        || package.dist.tarball.starts_with("file:")
+        || git::is_git(&package.dist.tarball)
+    {
+        return Ok(());
+    }
+
+    let dist = registry_dist(package, client).await?;
+    let keys = client.fetch_registry_keys().await;
+
+    if policy.verify_signatures {
+        let key = signing_key(package, &dist, keys)?;
+
+        check_expiry(package, key, client).await?;
+    }
+
+    if dist.attestations.is_none() {
+        if requires_provenance {
+            return Err("package has no provenance, but its scope requires it".to_string());
+        }
+
+        return Ok(());
+    }
+
+    let path = format!(
+        "-/npm/v1/attestations/{}@{}",
+        package.registry_name(),
+        package.version
+    );
+    let attestations: Attestations = client
+        .fetch_registry_json(&path)
+        .await
+        .and_then(|attestations| serde_json::from_value(attestations).ok())
+        .ok_or("cannot fetch attestations from registry")?;
+
+    for attestation in attestations.attestations.iter() {
+        check_attestation(
+            package,
+            &dist,
+            attestation,
+            keys,
+            sigstore::TrustedRoot::sigstore(),
+        )?;
+    }
+
+    let has_provenance = attestations
+        .attestations
+        .iter()
+        .any(|attestation| attestation.predicate_type.starts_with(PROVENANCE_PREDICATE));
+
+    if requires_provenance && !has_provenance {
+        return Err("package has no provenance attestation, but its scope requires it".to_string());
+    }
+
+    return Ok(());
+}
+
+/// packages installed from lockfile have no signatures, so they are taken from
+/// registry, and registry must agree on integrity with lockfile
+async fn registry_dist(
+    package: &Dependency,
+    client: &HttpClient,
+) -> Result<DependencyDist, String> {
+    if !package.dist.signatures.is_empty() || package.dist.attestations.is_some() {
+        return Ok(package.dist.clone());
+    }
+
+    let version = Version::parse(&package.version).map_err(|error| error.to_string())?;
+    let dist = &client
+        .fetch_dependency(&package.registry_name().to_string(), &version)
+        .await
+        .dist;
+
+    if dist.integrity != package.dist.integrity {
+        return Err(format!(
+            "integrity {} differs from registry one {}",
+            package.dist.integrity, dist.integrity
+        ));
+    }
+
+    return Ok(dist.clone());
+}
+
+/// registry key, that has signed `name@version:integrity` of the package
+fn signing_key<'a>(
+    package: &Dependency,
+    dist: &DependencyDist,
+    keys: &'a [RegistryKey],
+) -> Result<&'a RegistryKey, String> {
+    if keys.is_empty() {
+        return Err("registry has no signing keys".to_string());
+    }
+
+    if dist.signatures.is_empty() {
+        return Err("package has no registry signature".to_string());
+    }
+
+    let message = format!(
+        "{}@{}:{}",
+        package.registry_name(),
+        package.version,
+        dist.integrity
+    );
+
+    for signature in dist.signatures.iter() {
+        let Some(key) = keys.iter().find(|key| key.keyid == signature.keyid) else {
+            continue;
+        };
+
+        if verify_signature(&key.key, &signature.sig, message.as_bytes()) {
+            return Ok(key);
+        }
+    }
+
+    return Err("registry signature is invalid, or made with unknown key".to_string());
+}
+
+/// abbreviated packuments have no publish times, so full one is only fetched for
+/// packages signed with keys that have expired
+async fn check_expiry(
+    package: &Dependency,
+    key: &RegistryKey,
+    client: &HttpClient,
+) -> Result<(), String> {
+    let Some(expires) = key.expires.as_deref() else {
+        return Ok(());
+    };
+
+    let expires = DateTime::parse_from_rfc3339(expires).map_err(|error| error.to_string())?;
+    let packument = client.fetch_packument(package.registry_name()).await;
+    let published = packument
+        .get("time")
+        .and_then(|time| time.get(&package.version))
+        .and_then(|time| time.as_str())
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .ok_or("registry has no publish time of the package")?;
+
+    if published >= expires {
+        return Err(format!(
+            "package was published after key {} had expired",
+            key.keyid
+        ));
+    }
+
+    return Ok(());
+}
+
+/// checks that envelope is signed and that its statement is about this very tarball.
+/// Publish attestations are signed with registry keys, provenance with sigstore
+/// certificate, that is checked against `root`
+fn check_attestation(
+    package: &Dependency,
+    dist: &DependencyDist,
+    attestation: &Attestation,
+    keys: &[RegistryKey],
+    root: &sigstore::TrustedRoot,
+) -> Result<(), String> {
+    let bundle = &attestation.bundle;
+    let material = &bundle.verification_material;
+    let envelope = &bundle.dsse_envelope;
+    let entries = &material.tlog_entries;
+    let public_key = match (
+        &material.public_key,
+        &material.certificate,
+        &material.certificate_chain,
+    ) {
+        (Some(hint), _, _) => keys
+            .iter()
+            .find(|key| key.keyid == hint.hint)
+            .map(|key| key.key.clone())
+            .ok_or(format!(
+                "attestation is signed with unknown key {}",
+                hint.hint
+            ))?,
+        (None, Some(certificate), _) => {
+            sigstore::certificate_key(certificate, entries, envelope, root)?
+        }
+        (None, None, Some(chain)) => sigstore::certificate_key(
+            chain
+                .certificates
+                .first()
+                .ok_or("attestation has no certificate")?,
+            entries,
+            envelope,
+            root,
+        )?,
+        (None, None, None) => return Err("attestation has no key or certificate".to_string()),
+    };
+
+    let payload = STANDARD
+        .decode(&envelope.payload)
+        .map_err(|error| error.to_string())?;
+    let message = pre_authentication_encoding(&envelope.payload_type, &payload);
+
+    let is_signed = envelope
+        .signatures
+        .iter()
+        .any(|signature| verify_signature(&public_key, &signature.sig, &message));
+
+    if !is_signed {
+        return Err(format!(
+            "signature of {} attestation is invalid",
+            attestation.predicate_type
+        ));
+    }
+
+    let statement: Statement =
+        serde_json::from_slice(&payload).map_err(|error| error.to_string())?;
+    let purl = format!(
+        "pkg:npm/{}@{}",
+        package.registry_name().replace("@", "%40"),
+        package.version
+    );
+    let digest = sha512_hex(&dist.integrity).ok_or("package has no sha512 integrity")?;
+
+    let is_about_tarball = statement
+        .subject
+        .iter()
+        .any(|subject| subject.name == purl && subject.digest.get("sha512") == Some(&digest));
+
+    if !is_about_tarball {
+        return Err(format!(
+            "{} attestation is not about {purl} with its integrity",
+            attestation.predicate_type
+        ));
+    }
+
+    return Ok(());
+}
+
+/// what DSSE signature is made over, so payload can't be taken as another type
+fn pre_authentication_encoding(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    )
+    .into_bytes();
+
+    message.extend_from_slice(payload);
+
+    return message;
+}
+
+/// hex of sha512 digest from subresource integrity, as in-toto subjects have it
+fn sha512_hex(integrity: &str) -> Option<String> {
+    let digest = integrity
+        .split_whitespace()
+        .find_map(|hash| hash.strip_prefix("sha512-"))?;
+    let digest = STANDARD.decode(digest.split('?').next()?).ok()?;
+
+    return Some(digest.iter().map(|byte| format!("{byte:02x}")).collect());
+}
+
+/// ECDSA (sha256) signature check, broken keys and signatures are just invalid
+fn verify_signature(public_key: &str, signature: &str, message: &[u8]) -> bool {
+    let (Ok(public_key), Ok(signature)) = (STANDARD.decode(public_key), STANDARD.decode(signature))
+    else {
+        return false;
+    };
+
+    let verified = PKey::public_key_from_der(&public_key).and_then(|public_key| {
+        return Verifier::new(MessageDigest::sha256(), &public_key)?
+            .verify_oneshot(&signature, message);
+    });
+
+    return verified.unwrap_or(false);
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::{
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+        pkey::Private,
+        sign::Signer,
+    };
+
+    use super::*;
+    use crate::DistSignature;
+
+    fn generate_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+
+        return PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    }
+
+    fn sign(key: &PKey<Private>, message: &[u8]) -> String {
+        let signature = Signer::new(MessageDigest::sha256(), key)
+            .unwrap()
+            .sign_oneshot_to_vec(message)
+            .unwrap();
+
+        return STANDARD.encode(signature);
+    }
+
+    fn registry_key(keyid: &str, key: &PKey<Private>) -> RegistryKey {
+        return RegistryKey {
+            keyid: keyid.to_string(),
+            key: STANDARD.encode(key.public_key_to_der().unwrap()),
+            expires: None,
+        };
+    }
+
+    fn package(integrity: &str) -> Dependency {
+        return serde_json::from_value(serde_json::json!({
+            "name": "@scope/pad",
+            "version": "1.0.0",
+            "dist": { "integrity": integrity, "tarball": "https://registry/pad.tgz" }
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn registry_signature_covers_integrity() {
+        let key = generate_key();
+        let keys = [
+            registry_key("SHA256:other", &generate_key()),
+            registry_key("SHA256:key", &key),
+        ];
+        let mut signed = package("sha512-AAAA");
+
+        signed.dist.signatures = vec![DistSignature {
+            keyid: "SHA256:key".to_string(),
+            sig: sign(&key, b"@scope/pad@1.0.0:sha512-AAAA"),
+        }];
+
+        assert_eq!(
+            signing_key(&signed, &signed.dist, &keys).unwrap().keyid,
+            "SHA256:key"
+        );
+
+        let mut tampered = signed.clone();
+
+        tampered.dist.integrity = "sha512-BBBB".to_string();
+
+        assert!(signing_key(&tampered, &tampered.dist, &keys).is_err());
+        assert!(signing_key(&package("sha512-AAAA"), &package("sha512-AAAA").dist, &keys).is_err());
+    }
+
+    #[test]
+    fn attestation_must_be_about_package_tarball() {
+        let key = generate_key();
+        let keys = [registry_key("SHA256:key", &key)];
+        let integrity = format!("sha512-{}", STANDARD.encode([0xab; 64]));
+        let statement = serde_json::json!({
+            "subject": [{ "name": "pkg:npm/%40scope/pad@1.0.0", "digest": { "sha512": "ab".repeat(64) } }]
+        });
+        let payload = statement.to_string().into_bytes();
+        let payload_type = "application/vnd.in-toto+json";
+        let attestation: Attestation = serde_json::from_value(serde_json::json!({
+            "predicateType": "https://github.com/npm/attestation/tree/main/specs/publish/v0.1",
+            "bundle": {
+                "verificationMaterial": { "publicKey": { "hint": "SHA256:key" } },
+                "dsseEnvelope": {
+                    "payload": STANDARD.encode(&payload),
+                    "payloadType": payload_type,
+                    "signatures": [{ "sig": sign(&key, &pre_authentication_encoding(payload_type, &payload)) }]
+                }
+            }
+        }))
+        .unwrap();
+
+        let signed = package(&integrity);
+
+        assert_eq!(
+            check_attestation(
+                &signed,
+                &signed.dist,
+                &attestation,
+                &keys,
+                sigstore::TrustedRoot::sigstore()
+            ),
+            Ok(())
+        );
+
+        let other = package(&format!("sha512-{}", STANDARD.encode([0xcd; 64])));
+
+        assert!(check_attestation(
+            &other,
+            &other.dist,
+            &attestation,
+            &keys,
+            sigstore::TrustedRoot::sigstore()
+        )
+        .unwrap_err()
+        .contains("is not about"));
+    }
+}
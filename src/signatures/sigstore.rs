@@ -0,0 +1,695 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::DateTime;
+use openssl::{
+    sha::{sha256, Sha256},
+    stack::Stack,
+    x509::{store::X509StoreBuilder, verify::X509VerifyParam, X509StoreContext, X509},
+};
+use serde::{de, Deserialize, Deserializer};
+use std::sync::OnceLock;
+
+use super::{verify_signature, Certificate, CertificateChain, Envelope};
+
+/// `trusted_root.json` of public good Sigstore instance (Fulcio certificate authorities
+/// and Rekor transparency log keys), as its TUF repository serves it
+const TRUSTED_ROOT: &str = include_str!("trusted_root.json");
+
+#[derive(Debug, Deserialize)]
+pub(super) struct TrustedRoot {
+    tlogs: Vec<TransparencyLog>,
+    #[serde(rename = "certificateAuthorities")]
+    certificate_authorities: Vec<CertificateAuthority>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransparencyLog {
+    #[serde(rename = "publicKey")]
+    public_key: TrustedKey,
+    #[serde(rename = "logId")]
+    log_id: LogId,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrustedKey {
+    /// base64 of DER encoded public key (SPKI)
+    #[serde(rename = "rawBytes")]
+    raw_bytes: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogId {
+    /// base64 of sha256 of log's public key
+    #[serde(rename = "keyId")]
+    key_id: String,
+}
+
+/// certificates go from intermediate to self signed root
+#[derive(Debug, Deserialize)]
+struct CertificateAuthority {
+    #[serde(rename = "certChain")]
+    cert_chain: CertificateChain,
+    #[serde(rename = "validFor")]
+    valid_for: ValidFor,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidFor {
+    start: String,
+    end: Option<String>,
+}
+
+/// entry of transparency log, that envelope was uploaded to when it was signed. Numbers
+/// are strings in protobuf JSON, bytes are base64
+#[derive(Debug, Deserialize)]
+pub(super) struct TlogEntry {
+    #[serde(rename = "logIndex", deserialize_with = "int64")]
+    log_index: u64,
+    #[serde(rename = "logId")]
+    log_id: LogId,
+    #[serde(rename = "integratedTime", deserialize_with = "int64")]
+    integrated_time: u64,
+    #[serde(rename = "inclusionPromise")]
+    inclusion_promise: Option<InclusionPromise>,
+    #[serde(rename = "inclusionProof")]
+    inclusion_proof: Option<InclusionProof>,
+    #[serde(rename = "canonicalizedBody")]
+    canonicalized_body: String,
+}
+
+/// signed entry timestamp, log's promise to include entry, older bundles have only it
+#[derive(Debug, Deserialize)]
+struct InclusionPromise {
+    #[serde(rename = "signedEntryTimestamp")]
+    signed_entry_timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InclusionProof {
+    #[serde(rename = "logIndex", deserialize_with = "int64")]
+    log_index: u64,
+    #[serde(rename = "rootHash")]
+    root_hash: String,
+    #[serde(rename = "treeSize", deserialize_with = "int64")]
+    tree_size: u64,
+    hashes: Vec<String>,
+    checkpoint: Option<Checkpoint>,
+}
+
+/// signed note with size and root hash of the log
+#[derive(Debug, Deserialize)]
+struct Checkpoint {
+    envelope: String,
+}
+
+impl TrustedRoot {
+    pub(super) fn sigstore() -> &'static TrustedRoot {
+        static ROOT: OnceLock<TrustedRoot> = OnceLock::new();
+
+        return ROOT.get_or_init(|| {
+            serde_json::from_str(TRUSTED_ROOT).expect("invalid pinned sigstore trusted root")
+        });
+    }
+}
+
+/// public key (base64 of SPKI) of the certificate, that envelope is signed with. Envelope
+/// must be in trusted transparency log, and certificate must chain up to trusted
+/// certificate authority at the time it was logged, otherwise anybody could sign it
+pub(super) fn certificate_key(
+    certificate: &Certificate,
+    entries: &[TlogEntry],
+    envelope: &Envelope,
+    root: &TrustedRoot,
+) -> Result<String, String> {
+    let der = STANDARD
+        .decode(&certificate.raw_bytes)
+        .map_err(|error| error.to_string())?;
+    let certificate = X509::from_der(&der).map_err(|error| error.to_string())?;
+    let entry = entries
+        .first()
+        .ok_or("attestation is not in transparency log")?;
+
+    check_tlog_entry(entry, envelope, root)?;
+    check_chain(&certificate, entry.integrated_time, root)?;
+
+    let key = certificate
+        .public_key()
+        .and_then(|key| key.public_key_to_der())
+        .map_err(|error| error.to_string())?;
+
+    return Ok(STANDARD.encode(key));
+}
+
+/// entry is logged by trusted log (proven by inclusion proof with signed checkpoint, or by
+/// signed entry timestamp) and it is about this envelope
+fn check_tlog_entry(
+    entry: &TlogEntry,
+    envelope: &Envelope,
+    root: &TrustedRoot,
+) -> Result<(), String> {
+    let log = root
+        .tlogs
+        .iter()
+        .find(|log| log.log_id.key_id == entry.log_id.key_id)
+        .ok_or("attestation is in unknown transparency log")?;
+    let body = STANDARD
+        .decode(&entry.canonicalized_body)
+        .map_err(|error| error.to_string())?;
+
+    match (&entry.inclusion_proof, &entry.inclusion_promise) {
+        (Some(proof), _) if proof.checkpoint.is_some() => check_inclusion(&body, proof, log)?,
+        (_, Some(promise)) => check_promise(entry, promise, log)?,
+        _ => return Err("transparency log entry has no inclusion proof".to_string()),
+    }
+
+    return check_entry_body(&body, envelope);
+}
+
+fn check_inclusion(
+    body: &[u8],
+    proof: &InclusionProof,
+    log: &TransparencyLog,
+) -> Result<(), String> {
+    let invalid = || "inclusion proof of transparency log entry is invalid".to_string();
+    let root_hash = STANDARD.decode(&proof.root_hash).map_err(|_| invalid())?;
+    let hashes = proof
+        .hashes
+        .iter()
+        .map(|hash| STANDARD.decode(hash).ok()?.try_into().ok())
+        .collect::<Option<Vec<[u8; 32]>>>()
+        .ok_or_else(invalid)?;
+    let computed = merkle_root(proof.log_index, proof.tree_size, hash_leaf(body), &hashes)
+        .ok_or_else(invalid)?;
+
+    if computed[..] != root_hash[..] {
+        return Err(invalid());
+    }
+
+    let checkpoint = proof.checkpoint.as_ref().ok_or_else(invalid)?;
+
+    return check_checkpoint(&checkpoint.envelope, proof.tree_size, &root_hash, log);
+}
+
+/// RFC 6962 root of tree of `size` leaves, that has `leaf_hash` at `index`, where
+/// `hashes` are inner nodes of the proof (from the leaf up) and then nodes of right border
+fn merkle_root(
+    index: u64,
+    size: u64,
+    leaf_hash: [u8; 32],
+    hashes: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    if index >= size {
+        return None;
+    }
+
+    let inner = (u64::BITS - (index ^ (size - 1)).leading_zeros()) as usize;
+    let border = index.checked_shr(inner as u32).unwrap_or(0).count_ones() as usize;
+
+    if hashes.len() != inner + border {
+        return None;
+    }
+
+    let (inner_hashes, border_hashes) = hashes.split_at(inner);
+    let mut hash = leaf_hash;
+
+    for (level, sibling) in inner_hashes.iter().enumerate() {
+        hash = match (index >> level) & 1 {
+            1 => hash_children(sibling, &hash),
+            _ => hash_children(&hash, sibling),
+        };
+    }
+
+    for sibling in border_hashes {
+        hash = hash_children(sibling, &hash);
+    }
+
+    return Some(hash);
+}
+
+fn hash_leaf(leaf: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+
+    hasher.update(&[0x00]);
+    hasher.update(leaf);
+
+    return hasher.finish();
+}
+
+fn hash_children(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+
+    hasher.update(&[0x01]);
+    hasher.update(left);
+    hasher.update(right);
+
+    return hasher.finish();
+}
+
+/// checkpoint is `<origin>\n<size>\n<base64 root hash>\n` note, followed by blank line
+/// and `— <name> <base64 of key hint and signature>` lines
+fn check_checkpoint(
+    checkpoint: &str,
+    tree_size: u64,
+    root_hash: &[u8],
+    log: &TransparencyLog,
+) -> Result<(), String> {
+    let invalid = || "checkpoint of transparency log is invalid".to_string();
+    let (note, signatures) = checkpoint.split_once("\n\n").ok_or_else(invalid)?;
+    let note = format!("{note}\n");
+    let mut lines = note.lines().skip(1);
+    let size = lines.next().and_then(|size| size.parse::<u64>().ok());
+    let hash = lines.next().and_then(|hash| STANDARD.decode(hash).ok());
+
+    if size != Some(tree_size) || hash.as_deref() != Some(root_hash) {
+        return Err(invalid());
+    }
+
+    let is_signed = signatures
+        .lines()
+        .filter_map(|line| line.strip_prefix("\u{2014} "))
+        .filter_map(|line| line.split_once(" "))
+        .filter_map(|(_, signature)| STANDARD.decode(signature).ok())
+        .filter(|signature| signature.len() > 4)
+        .any(|signature| {
+            verify_signature(
+                &log.public_key.raw_bytes,
+                &STANDARD.encode(&signature[4..]),
+                note.as_bytes(),
+            )
+        });
+
+    if !is_signed {
+        return Err("checkpoint is not signed by transparency log".to_string());
+    }
+
+    return Ok(());
+}
+
+/// signed entry timestamp is made over canonical JSON of the entry
+fn check_promise(
+    entry: &TlogEntry,
+    promise: &InclusionPromise,
+    log: &TransparencyLog,
+) -> Result<(), String> {
+    let log_id = STANDARD
+        .decode(&entry.log_id.key_id)
+        .map_err(|error| error.to_string())?;
+    let payload = format!(
+        r#"{{"body":"{}","integratedTime":{},"logID":"{}","logIndex":{}}}"#,
+        entry.canonicalized_body,
+        entry.integrated_time,
+        log_id
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>(),
+        entry.log_index
+    );
+
+    if !verify_signature(
+        &log.public_key.raw_bytes,
+        &promise.signed_entry_timestamp,
+        payload.as_bytes(),
+    ) {
+        return Err("inclusion promise of transparency log entry is invalid".to_string());
+    }
+
+    return Ok(());
+}
+
+/// logged `intoto` (v0.0.2) or `dsse` (v0.0.1) entry has the same signature and payload
+/// digest, as envelope
+fn check_entry_body(body: &[u8], envelope: &Envelope) -> Result<(), String> {
+    let body: serde_json::Value =
+        serde_json::from_slice(body).map_err(|error| error.to_string())?;
+    let spec = &body["spec"];
+
+    let (signatures, payload_hash) = match body["kind"].as_str() {
+        // signatures of intoto entries are base64 of base64
+        Some("intoto") => (
+            spec.pointer("/content/envelope/signatures")
+                .and_then(|signatures| signatures.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|signature| signature["sig"].as_str())
+                .filter_map(|sig| String::from_utf8(STANDARD.decode(sig).ok()?).ok())
+                .collect::<Vec<_>>(),
+            spec.pointer("/content/payloadHash/value"),
+        ),
+        Some("dsse") => (
+            spec["signatures"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|signature| signature["signature"].as_str())
+                .map(String::from)
+                .collect(),
+            spec.pointer("/payloadHash/value"),
+        ),
+        kind => return Err(format!("unsupported transparency log entry kind {kind:?}")),
+    };
+
+    let payload = STANDARD
+        .decode(&envelope.payload)
+        .map_err(|error| error.to_string())?;
+    let digest: String = sha256(&payload)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+
+    if payload_hash.and_then(|hash| hash.as_str()) != Some(digest.as_str()) {
+        return Err("transparency log entry is about other payload".to_string());
+    }
+
+    let is_same_signature = envelope.signatures.iter().any(|signature| {
+        signatures
+            .iter()
+            .any(|logged| STANDARD.decode(logged).ok() == STANDARD.decode(&signature.sig).ok())
+    });
+
+    if !is_same_signature {
+        return Err("transparency log entry has other signature".to_string());
+    }
+
+    return Ok(());
+}
+
+/// certificate chains up to root of authority, that was valid when entry was logged,
+/// short lived certificates are checked at that time too
+fn check_chain(certificate: &X509, signed_at: u64, root: &TrustedRoot) -> Result<(), String> {
+    let signed_at = signed_at as i64;
+
+    for authority in root.certificate_authorities.iter() {
+        let timestamp =
+            |time: &str| DateTime::parse_from_rfc3339(time).map(|time| time.timestamp());
+        let started = timestamp(&authority.valid_for.start).is_ok_and(|start| start <= signed_at);
+        let not_ended = authority
+            .valid_for
+            .end
+            .as_deref()
+            .is_none_or(|end| timestamp(end).is_ok_and(|end| signed_at <= end));
+
+        if started && not_ended && issues(authority, certificate, signed_at).unwrap_or(false) {
+            return Ok(());
+        }
+    }
+
+    return Err("certificate of attestation is not issued by sigstore".to_string());
+}
+
+fn issues(
+    authority: &CertificateAuthority,
+    certificate: &X509,
+    signed_at: i64,
+) -> Result<bool, openssl::error::ErrorStack> {
+    let mut certificates = authority
+        .cert_chain
+        .certificates
+        .iter()
+        .filter_map(|certificate| STANDARD.decode(&certificate.raw_bytes).ok())
+        .filter_map(|der| X509::from_der(&der).ok())
+        .collect::<Vec<_>>();
+    let Some(root_certificate) = certificates.pop() else {
+        return Ok(false);
+    };
+
+    let mut store = X509StoreBuilder::new()?;
+    let mut param = X509VerifyParam::new()?;
+    let mut intermediates = Stack::new()?;
+
+    param.set_time(signed_at as _);
+    store.set_param(&param)?;
+    store.add_cert(root_certificate)?;
+
+    for intermediate in certificates {
+        intermediates.push(intermediate)?;
+    }
+
+    let store = store.build();
+
+    return X509StoreContext::new()?.init(&store, certificate, &intermediates, |context| {
+        return context.verify_cert();
+    });
+}
+
+fn int64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Int64 {
+        Number(u64),
+        Text(String),
+    }
+
+    return match Int64::deserialize(deserializer)? {
+        Int64::Number(number) => Ok(number),
+        Int64::Text(text) => text.parse().map_err(de::Error::custom),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::{
+        asn1::Asn1Time,
+        bn::BigNum,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        pkey::{PKey, Private},
+        sign::Signer,
+        x509::{
+            extension::{BasicConstraints, KeyUsage},
+            X509Builder, X509NameBuilder,
+        },
+    };
+
+    use super::*;
+
+    const SIGNED_AT: i64 = 1_700_000_000;
+
+    fn generate_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+
+        return PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    }
+
+    fn sign(key: &PKey<Private>, message: &[u8]) -> String {
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+
+        return STANDARD.encode(signer.sign_oneshot_to_vec(message).unwrap());
+    }
+
+    /// certificate for `key`, valid for 10 minutes around `SIGNED_AT`, like Fulcio ones
+    fn certificate(key: &PKey<Private>, issuer: Option<(&X509, &PKey<Private>)>) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+
+        name.append_entry_by_text("CN", if issuer.is_some() { "leaf" } else { "ca" })
+            .unwrap();
+
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+
+        builder.set_version(2).unwrap();
+        builder
+            .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+
+        let (not_before, not_after) = match issuer {
+            Some(_) => (SIGNED_AT - 300, SIGNED_AT + 300),
+            None => (SIGNED_AT - 86400, SIGNED_AT + 86400),
+        };
+
+        builder
+            .set_not_before(&Asn1Time::from_unix(not_before).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::from_unix(not_after).unwrap())
+            .unwrap();
+
+        match issuer {
+            Some((issuer, issuer_key)) => {
+                builder.set_issuer_name(issuer.subject_name()).unwrap();
+                builder.sign(issuer_key, MessageDigest::sha256()).unwrap();
+            }
+            None => {
+                builder.set_issuer_name(&name).unwrap();
+                builder
+                    .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                    .unwrap();
+                builder
+                    .append_extension(KeyUsage::new().critical().key_cert_sign().build().unwrap())
+                    .unwrap();
+                builder.sign(key, MessageDigest::sha256()).unwrap();
+            }
+        }
+
+        return builder.build();
+    }
+
+    fn der(certificate: &X509) -> String {
+        return STANDARD.encode(certificate.to_der().unwrap());
+    }
+
+    fn trusted_root(ca: &X509, log_key: &PKey<Private>) -> TrustedRoot {
+        let log_key = log_key.public_key_to_der().unwrap();
+
+        return serde_json::from_value(serde_json::json!({
+            "tlogs": [{
+                "publicKey": { "rawBytes": STANDARD.encode(&log_key) },
+                "logId": { "keyId": STANDARD.encode(sha256(&log_key)) }
+            }],
+            "certificateAuthorities": [{
+                "certChain": { "certificates": [{ "rawBytes": der(ca) }] },
+                "validFor": { "start": "2021-01-01T00:00:00.000Z" }
+            }]
+        }))
+        .unwrap();
+    }
+
+    /// envelope signed with `key` and its `dsse` entry, promised by log of `log_key`
+    fn logged_envelope(key: &PKey<Private>, log_key: &PKey<Private>) -> (Envelope, TlogEntry) {
+        let payload = br#"{"subject":[]}"#;
+        let signature = sign(key, payload);
+        let envelope: Envelope = serde_json::from_value(serde_json::json!({
+            "payload": STANDARD.encode(payload),
+            "payloadType": "application/vnd.in-toto+json",
+            "signatures": [{ "sig": signature }]
+        }))
+        .unwrap();
+
+        let digest: String = sha256(payload)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let body = serde_json::json!({
+            "apiVersion": "0.0.1",
+            "kind": "dsse",
+            "spec": {
+                "payloadHash": { "algorithm": "sha256", "value": digest },
+                "signatures": [{ "signature": signature, "verifier": "" }]
+            }
+        });
+        let body = STANDARD.encode(body.to_string());
+        let log_id = sha256(&log_key.public_key_to_der().unwrap());
+        let hex: String = log_id.iter().map(|byte| format!("{byte:02x}")).collect();
+        let promise = format!(
+            r#"{{"body":"{body}","integratedTime":{SIGNED_AT},"logID":"{hex}","logIndex":7}}"#
+        );
+        let entry = serde_json::from_value(serde_json::json!({
+            "logIndex": "7",
+            "logId": { "keyId": STANDARD.encode(log_id) },
+            "integratedTime": SIGNED_AT.to_string(),
+            "inclusionPromise": { "signedEntryTimestamp": sign(log_key, promise.as_bytes()) },
+            "canonicalizedBody": body
+        }))
+        .unwrap();
+
+        return (envelope, entry);
+    }
+
+    #[test]
+    fn certificate_must_chain_up_to_trusted_root() {
+        let (ca_key, log_key, key) = (generate_key(), generate_key(), generate_key());
+        let ca = certificate(&ca_key, None);
+        let root = trusted_root(&ca, &log_key);
+        let (envelope, entry) = logged_envelope(&key, &log_key);
+        let entries = [entry];
+
+        let issued: Certificate = serde_json::from_value(
+            serde_json::json!({ "rawBytes": der(&certificate(&key, Some((&ca, &ca_key)))) }),
+        )
+        .unwrap();
+
+        // leaf has long expired, but it was valid when envelope was logged
+        assert_eq!(
+            certificate_key(&issued, &entries, &envelope, &root),
+            Ok(STANDARD.encode(key.public_key_to_der().unwrap()))
+        );
+
+        let self_signed: Certificate = serde_json::from_value(
+            serde_json::json!({ "rawBytes": der(&certificate(&key, None)) }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            certificate_key(&self_signed, &entries, &envelope, &root),
+            Err("certificate of attestation is not issued by sigstore".to_string())
+        );
+        assert_eq!(
+            certificate_key(&issued, &entries, &envelope, TrustedRoot::sigstore()),
+            Err("attestation is in unknown transparency log".to_string())
+        );
+        assert_eq!(
+            certificate_key(&issued, &[], &envelope, &root),
+            Err("attestation is not in transparency log".to_string())
+        );
+    }
+
+    #[test]
+    fn tlog_entry_must_be_promised_and_about_envelope() {
+        let (ca_key, log_key, key) = (generate_key(), generate_key(), generate_key());
+        let ca = certificate(&ca_key, None);
+        let root = trusted_root(&ca, &log_key);
+        let (envelope, entry) = logged_envelope(&key, &log_key);
+        let (other_envelope, _) = logged_envelope(&generate_key(), &log_key);
+        let (_, forged_entry) = logged_envelope(&key, &generate_key());
+
+        assert_eq!(check_tlog_entry(&entry, &envelope, &root), Ok(()));
+        assert_eq!(
+            check_tlog_entry(&entry, &other_envelope, &root),
+            Err("transparency log entry has other signature".to_string())
+        );
+        assert!(check_tlog_entry(&forged_entry, &envelope, &root).is_err());
+    }
+
+    /// RFC 6962 tree head and inclusion path, straight from definitions
+    fn tree_hash(leaves: &[[u8; 32]]) -> [u8; 32] {
+        if leaves.len() == 1 {
+            return leaves[0];
+        }
+
+        let split = leaves.len().next_power_of_two() / 2;
+
+        return hash_children(&tree_hash(&leaves[..split]), &tree_hash(&leaves[split..]));
+    }
+
+    fn path(index: usize, leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+        if leaves.len() == 1 {
+            return vec![];
+        }
+
+        let split = leaves.len().next_power_of_two() / 2;
+
+        let (mut path, sibling) = match index < split {
+            true => (path(index, &leaves[..split]), tree_hash(&leaves[split..])),
+            false => (
+                path(index - split, &leaves[split..]),
+                tree_hash(&leaves[..split]),
+            ),
+        };
+
+        path.push(sibling);
+
+        return path;
+    }
+
+    #[test]
+    fn merkle_inclusion() {
+        for size in 1..=9 {
+            let leaves: Vec<[u8; 32]> = (0..size).map(|leaf| hash_leaf(&[leaf as u8])).collect();
+            let root = tree_hash(&leaves);
+
+            for index in 0..size {
+                let proof = path(index, &leaves);
+
+                assert_eq!(
+                    merkle_root(index as u64, size as u64, leaves[index], &proof),
+                    Some(root)
+                );
+                assert_ne!(
+                    merkle_root(index as u64, size as u64, hash_leaf(b"other"), &proof),
+                    Some(root)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use bytes::Bytes;
+use futures::future::{join_all, BoxFuture};
+use reqwest::{Client, StatusCode};
+use std::{env, sync::Mutex, time::Duration};
+use tokio::task::JoinHandle;
+
+use crate::{local_tarball, metadata_cache::MetadataCache, plan, registry};
+
+mod local;
+mod reflink;
+
+pub use local::{LocalStore, PruneReport, VerifyReport};
+
+/// `razee store prune [--older-than <days>]` and `razee store verify`
+pub fn store(args: &[String]) {
+    let usage = "usage: razee store prune [--older-than <days>] | verify";
+    let store = LocalStore::from_env()
+        .expect("store is disabled (RAZEE_STORE=false) or its dir is unknown, set RAZEE_STORE_DIR");
+
+    match args.first().map(|command| command.as_str()) {
+        Some("prune") => {
+            let older_than = args
+                .iter()
+                .position(|arg| arg == "--older-than")
+                .map(|index| {
+                    let days: u64 = args
+                        .get(index + 1)
+                        .and_then(|days| days.parse().ok())
+                        .expect(usage);
+
+                    return Duration::from_secs(days * 24 * 60 * 60);
+                });
+
+            let report = store.prune(older_than);
+
+            println!(
+                "Removed {} packages from {}, reclaimed {}",
+                report.removed,
+                store.dir().display(),
+                plan::format_size(report.reclaimed)
+            );
+        }
+        Some("verify") => {
+            let report = store.verify();
+
+            println!(
+                "Verified {} packages in {}, removed {} corrupted",
+                report.checked,
+                store.dir().display(),
+                report.removed
+            );
+
+            if report.unverified > 0 {
+                println!(
+                    "{} packages were stored without file hashes and cannot be verified",
+                    report.unverified
+                );
+            }
+
+            if let Some(cache) = MetadataCache::from_env() {
+                let (checked, removed) = cache.verify();
+
+                println!(
+                    "Verified {checked} metadata entries in {}, removed {removed} corrupted",
+                    cache.dir().display()
+                );
+            }
+        }
+        _ => panic!("{usage}"),
+    }
+}
+
+/// where tarballs are looked up (by integrity) before they are downloaded from registry,
+/// e.g. package store shared between CI runners
+pub trait Store: Send + Sync {
+    /// tarball with given integrity, `None` when store doesn't have it
+    fn get<'a>(&'a self, integrity: &'a str) -> BoxFuture<'a, Option<Bytes>>;
+
+    /// saves tarball, can be done in background
+    fn put(&self, integrity: &str, tarball: Bytes);
+
+    /// waits for saves started by `put`
+    fn flush(&self) -> BoxFuture<'_, ()>;
+}
+
+/// `RAZEE_REMOTE_STORE=<url>` enables remote store, `RAZEE_REMOTE_STORE_TOKEN` is sent
+/// as bearer token, `RAZEE_REMOTE_STORE_READONLY=true` disables uploads (e.g. for forks)
+pub fn from_env() -> Option<Box<dyn Store>> {
+    let url = env::var("RAZEE_REMOTE_STORE").ok()?;
+    let mut store = RemoteStore::new(&url);
+
+    store.token = env::var("RAZEE_REMOTE_STORE_TOKEN").ok();
+    store.readonly = env::var("RAZEE_REMOTE_STORE_READONLY")
+        .is_ok_and(|readonly| readonly == "true" || readonly == "1");
+
+    return Some(Box::new(store));
+}
+
+/// store over plain http: `GET` and `PUT` of `<url>/<algorithm>/<digest>.tgz`, which works
+/// with S3-compatible buckets (and their presigning proxies), nginx with webdav,
+/// or any other static file server
+pub struct RemoteStore {
+    url: String,
+    token: Option<String>,
+    readonly: bool,
+    client: Client,
+    uploads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl RemoteStore {
+    pub fn new(url: &str) -> RemoteStore {
+        registry::ensure_https(url, "remote store");
+
+        return RemoteStore {
+            url: url.trim_end_matches("/").to_string(),
+            token: None,
+            readonly: false,
+            client: Client::new(),
+            uploads: Mutex::new(vec![]),
+        };
+    }
+
+    fn object_url(&self, integrity: &str) -> Option<String> {
+        let (algorithm, digest) = integrity.split_once("-")?;
+
+        // base64 digest can contain `/` and `+`, which are not welcome in urls
+        let digest = digest.replace("/", "_").replace("+", "-");
+
+        return Some(format!("{}/{algorithm}/{digest}.tgz", self.url));
+    }
+}
+
+impl Store for RemoteStore {
+    fn get<'a>(&'a self, integrity: &'a str) -> BoxFuture<'a, Option<Bytes>> {
+        return Box::pin(async move {
+            let url = self.object_url(integrity)?;
+            let mut request = self.client.get(&url);
+
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(error) => {
+                    eprintln!("warning: remote store is unreachable: {error}");
+                    return None;
+                }
+            };
+
+            match response.status() {
+                status if status.is_success() => {}
+                StatusCode::NOT_FOUND => return None,
+                status => {
+                    eprintln!("warning: remote store responded with {status} for {url}");
+                    return None;
+                }
+            }
+
+            let tarball = response.bytes().await.ok()?;
+
+            // store is shared, so it is trusted no more than registry
+            if integrity.starts_with("sha512-") && local_tarball::integrity(&tarball) != integrity {
+                eprintln!("warning: remote store has corrupted {url}, ignoring it");
+                return None;
+            }
+
+            return Some(tarball);
+        });
+    }
+
+    fn put(&self, integrity: &str, tarball: Bytes) {
+        if self.readonly {
+            return;
+        }
+
+        let Some(url) = self.object_url(integrity) else {
+            return;
+        };
+
+        let mut request = self.client.put(&url).body(tarball);
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let upload = tokio::spawn(async move {
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => eprintln!(
+                    "warning: cannot upload {url} to remote store: {}",
+                    response.status()
+                ),
+                Err(error) => eprintln!("warning: cannot upload {url} to remote store: {error}"),
+            }
+        });
+
+        self.uploads
+            .lock()
+            .expect("uploads lock is poisoned")
+            .push(upload);
+    }
+
+    fn flush(&self) -> BoxFuture<'_, ()> {
+        let uploads: Vec<JoinHandle<()>> = self
+            .uploads
+            .lock()
+            .expect("uploads lock is poisoned")
+            .drain(..)
+            .collect();
+
+        return Box::pin(async move {
+            join_all(uploads).await;
+        });
+    }
+}
@@ -0,0 +1,449 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    collections::{BTreeMap, HashSet},
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime},
+};
+use walkdir::WalkDir;
+
+use super::reflink::reflink;
+use crate::{
+    fs_retry::retry,
+    local_tarball,
+    lock::{DirLock, LOCK_FILE},
+    lockfile::{Lockfile, LOCKFILE},
+};
+
+const STAGING_DIR: &str = ".staging";
+/// projects, that were installed from store, one dir per line
+const PROJECTS_FILE: &str = "projects";
+
+static STAGED: AtomicUsize = AtomicUsize::new(0);
+
+/// extracted packages shared between projects (like pnpm's store), every package is
+/// extracted once into `<dir>/<algorithm>/<hex digest>` and installed by cloning or
+/// hardlinking its files, so second install of the same package is just linking
+#[derive(Debug, Clone)]
+pub struct LocalStore {
+    dir: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(dir: PathBuf) -> LocalStore {
+        return LocalStore { dir };
+    }
+
+    /// `RAZEE_STORE_DIR`, defaults to `~/.razee/store`, `RAZEE_STORE=false` disables store,
+    /// packages are extracted right into node_modules then
+    pub fn from_env() -> Option<LocalStore> {
+        if env::var("RAZEE_STORE").is_ok_and(|store| store == "false" || store == "0") {
+            return None;
+        }
+
+        if let Some(dir) = env::var_os("RAZEE_STORE_DIR") {
+            return Some(LocalStore::new(PathBuf::from(dir)));
+        }
+
+        let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+
+        return Some(LocalStore::new(
+            PathBuf::from(home).join(".razee").join("store"),
+        ));
+    }
+
+    pub fn dir(&self) -> &Path {
+        return &self.dir;
+    }
+
+    /// dir of package with `integrity`, whether it is in store or not, `None` for
+    /// packages without (valid) integrity, they can't be stored
+    pub fn package_dir(&self, integrity: &str) -> Option<PathBuf> {
+        // integrity can list several hashes, first one is the strongest
+        let (algorithm, digest) = integrity.split_whitespace().next()?.split_once("-")?;
+        let digest = STANDARD.decode(digest).ok()?;
+
+        // base64 is case sensitive, file systems are not always
+        let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+
+        return Some(self.dir.join(algorithm).join(hex));
+    }
+
+    /// stored package dir, `extract` is called to fill it when package is not stored yet
+    pub fn add(
+        &self,
+        integrity: &str,
+        extract: impl FnOnce(&Path) -> io::Result<()>,
+    ) -> io::Result<PathBuf> {
+        let package_dir = self.package_dir(integrity).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid integrity {integrity}"),
+            )
+        })?;
+
+        if package_dir.is_dir() {
+            return Ok(package_dir);
+        }
+
+        // package is extracted aside and moved in place at once, so other processes
+        // never see half of it
+        let staging_dir = self.dir.join(STAGING_DIR).join(format!(
+            "{}-{}",
+            process::id(),
+            STAGED.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        retry(&staging_dir, || fs::create_dir_all(&staging_dir))?;
+
+        if let Err(error) = extract(&staging_dir) {
+            let _ = fs::remove_dir_all(&staging_dir);
+
+            return Err(error);
+        }
+
+        if let Some(parent) = package_dir.parent() {
+            retry(parent, || fs::create_dir_all(parent))?;
+        }
+
+        let index = hash_files(&staging_dir)?;
+        let renamed = retry(&package_dir, || fs::rename(&staging_dir, &package_dir));
+
+        // other process could store the same package in the meantime
+        if renamed.is_err() && package_dir.is_dir() {
+            let _ = fs::remove_dir_all(&staging_dir);
+
+            return Ok(package_dir);
+        }
+
+        renamed?;
+
+        // package without index is kept, it just can't be verified
+        let index_path = index_path(&package_dir);
+        let index = serde_json::to_string(&index).expect("index is serializable");
+
+        if let Err(error) = retry(&index_path, || fs::write(&index_path, &index)) {
+            eprintln!("warning: cannot write {}: {error}", index_path.display());
+        }
+
+        return Ok(package_dir);
+    }
+
+    /// held by installs, so prune doesn't remove packages, while they are linked
+    pub(crate) fn lock_shared(&self) -> DirLock {
+        return DirLock::acquire_shared(&self.dir);
+    }
+
+    /// remembers that `project_dir` uses store, so its packages are kept by prune
+    pub fn add_project(&self, project_dir: &Path) -> io::Result<()> {
+        let project_dir = project_dir.to_string_lossy();
+
+        if self
+            .projects()
+            .iter()
+            .any(|project| **project == *project_dir)
+        {
+            return Ok(());
+        }
+
+        let path = self.dir.join(PROJECTS_FILE);
+        let mut file = retry(&path, || {
+            OpenOptions::new().create(true).append(true).open(&path)
+        })?;
+
+        return writeln!(file, "{project_dir}");
+    }
+
+    fn projects(&self) -> Vec<String> {
+        let projects = fs::read_to_string(self.dir.join(PROJECTS_FILE)).unwrap_or_default();
+
+        return projects
+            .lines()
+            .filter(|project| !project.is_empty())
+            .map(|project| project.to_string())
+            .collect();
+    }
+
+    /// removes stored packages, that are not in lockfile of any known project, or were
+    /// not installed for longer than `older_than`, projects that don't exist anymore are
+    /// forgotten
+    pub fn prune(&self, older_than: Option<Duration>) -> PruneReport {
+        let _lock = DirLock::acquire(&self.dir);
+
+        let mut report = PruneReport::default();
+        let mut referenced = HashSet::new();
+        let mut projects = vec![];
+
+        for project in self.projects() {
+            let Some(lockfile) = Lockfile::read(&Path::new(&project).join(LOCKFILE)) else {
+                continue;
+            };
+
+            let integrities = lockfile
+                .packages
+                .values()
+                .filter_map(|package| package.integrity.as_deref());
+
+            referenced.extend(integrities.filter_map(|integrity| self.package_dir(integrity)));
+            projects.push(project);
+        }
+
+        let projects_path = self.dir.join(PROJECTS_FILE);
+        let content: String = projects
+            .iter()
+            .map(|project| format!("{project}\n"))
+            .collect();
+
+        if let Err(error) = retry(&projects_path, || fs::write(&projects_path, &content)) {
+            eprintln!("warning: cannot write {}: {error}", projects_path.display());
+        }
+
+        report.reclaimed += self.remove_staging();
+
+        for package_dir in self.package_dirs() {
+            let is_stale = older_than.is_some_and(|older_than| {
+                let used_at = fs::metadata(&package_dir).and_then(|metadata| metadata.modified());
+
+                return used_at.is_ok_and(|used_at| {
+                    used_at.elapsed().is_ok_and(|elapsed| elapsed > older_than)
+                });
+            });
+
+            if referenced.contains(&package_dir) && !is_stale {
+                continue;
+            }
+
+            let size = dir_size(&package_dir);
+
+            match remove_package(&package_dir) {
+                Ok(()) => {
+                    report.removed += 1;
+                    report.reclaimed += size;
+                }
+                Err(error) => eprintln!("warning: cannot remove package: {error}"),
+            }
+        }
+
+        return report;
+    }
+
+    /// checks files of every stored package against hashes recorded, when it was stored,
+    /// corrupted packages (changed through hardlink in node_modules, damaged by disk
+    /// issues or left by interrupted install) are removed, next install stores them again
+    pub fn verify(&self) -> VerifyReport {
+        let _lock = DirLock::acquire(&self.dir);
+
+        let mut report = VerifyReport::default();
+
+        self.remove_staging();
+
+        for package_dir in self.package_dirs() {
+            let index = fs::read_to_string(index_path(&package_dir))
+                .ok()
+                .and_then(|index| serde_json::from_str::<BTreeMap<String, String>>(&index).ok());
+
+            let Some(index) = index else {
+                report.unverified += 1;
+                continue;
+            };
+
+            let problem = match hash_files(&package_dir) {
+                Ok(files) if files == index => None,
+                Ok(files) => index
+                    .iter()
+                    .find(|(path, integrity)| files.get(*path) != Some(integrity))
+                    .map(|(path, _)| format!("{path} is changed or missing"))
+                    .or_else(|| {
+                        let extra = files.keys().find(|path| !index.contains_key(*path))?;
+
+                        return Some(format!("{extra} is not a part of package"));
+                    }),
+                Err(error) => Some(error.to_string()),
+            };
+
+            report.checked += 1;
+
+            let Some(problem) = problem else {
+                continue;
+            };
+
+            match remove_package(&package_dir) {
+                Ok(()) => println!("{}: {problem}, removed", package_dir.display()),
+                Err(error) => eprintln!("warning: cannot remove corrupted package: {error}"),
+            }
+
+            report.removed += 1;
+        }
+
+        return report;
+    }
+
+    /// `<dir>/<algorithm>/<hex digest>` of every stored package
+    fn package_dirs(&self) -> Vec<PathBuf> {
+        return fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|algorithm| {
+                let name = algorithm.file_name();
+                let name = name.to_string_lossy();
+
+                return !name.starts_with(".") && name != LOCK_FILE && name != PROJECTS_FILE;
+            })
+            .flat_map(|algorithm| {
+                fs::read_dir(algorithm.path())
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+            })
+            .map(|package_dir| package_dir.path())
+            .filter(|package_dir| package_dir.is_dir())
+            .collect();
+    }
+
+    /// removes packages, that were half extracted by interrupted installs, should only
+    /// be called while store is locked, so nothing is being stored, returns freed bytes
+    fn remove_staging(&self) -> u64 {
+        let staging_dir = self.dir.join(STAGING_DIR);
+
+        if !staging_dir.is_dir() {
+            return 0;
+        }
+
+        let size = dir_size(&staging_dir);
+
+        if let Err(error) = retry(&staging_dir, || fs::remove_dir_all(&staging_dir)) {
+            eprintln!("warning: cannot remove {}: {error}", staging_dir.display());
+
+            return 0;
+        }
+
+        return size;
+    }
+
+    /// clones (copy-on-write) files of stored package into `dep_dir`, where file system
+    /// supports it, otherwise files are hardlinked. Files are copied when `copy` is set
+    /// (package is going to be changed, e.g. patched, clones are fine for that) or when
+    /// hardlinks are not possible (store is on other device)
+    pub fn link(&self, package_dir: &Path, dep_dir: &Path, copy: bool) -> io::Result<()> {
+        // modification time of package dir is when it was installed last, see `prune`
+        let _ = File::open(package_dir).and_then(|dir| dir.set_modified(SystemTime::now()));
+
+        for entry in WalkDir::new(package_dir) {
+            let entry = entry.map_err(io::Error::from)?;
+            let relative = entry
+                .path()
+                .strip_prefix(package_dir)
+                .expect("walked path is inside package dir");
+            let target = dep_dir.join(relative);
+
+            if entry.file_type().is_dir() {
+                retry(&target, || fs::create_dir_all(&target))?;
+                continue;
+            }
+
+            if target.symlink_metadata().is_ok() {
+                retry(&target, || fs::remove_file(&target))?;
+            }
+
+            let linked = reflink(entry.path(), &target).or_else(|error| match copy {
+                true => Err(error),
+                false => fs::hard_link(entry.path(), &target),
+            });
+
+            if linked.is_err() {
+                retry(&target, || fs::copy(entry.path(), &target).map(|_| ()))?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// packages, that were checked against their index
+    pub checked: usize,
+    /// corrupted packages, that were removed
+    pub removed: usize,
+    /// packages stored without index
+    pub unverified: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    /// removed packages
+    pub removed: usize,
+    /// bytes, that were freed on disk
+    pub reclaimed: u64,
+}
+
+/// hashes of files stored along with package, as `<hex digest>.json`
+fn index_path(package_dir: &Path) -> PathBuf {
+    return package_dir.with_extension("json");
+}
+
+/// integrity of every file in `dir`, keyed by its relative path
+fn hash_files(dir: &Path) -> io::Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+
+    for entry in WalkDir::new(dir) {
+        let entry = entry.map_err(io::Error::from)?;
+
+        if entry.file_type().is_dir() {
+            continue;
+        }
+
+        let relative = entry
+            .path()
+            .strip_prefix(dir)
+            .expect("walked path is inside dir")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let content = retry(entry.path(), || fs::read(entry.path()))?;
+
+        files.insert(relative, local_tarball::integrity(&content));
+    }
+
+    return Ok(files);
+}
+
+fn remove_package(package_dir: &Path) -> io::Result<()> {
+    let index_path = index_path(package_dir);
+
+    if index_path.is_file() {
+        retry(&index_path, || fs::remove_file(&index_path))?;
+    }
+
+    return retry(package_dir, || fs::remove_dir_all(package_dir));
+}
+
+/// size of files in `dir`, that are not hardlinked anywhere else, so removing them
+/// actually frees space
+fn dir_size(dir: &Path) -> u64 {
+    return WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file() && is_last_link(metadata))
+        .map(|metadata| metadata.len())
+        .sum();
+}
+
+#[cfg(unix)]
+fn is_last_link(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    return metadata.nlink() <= 1;
+}
+
+#[cfg(not(unix))]
+fn is_last_link(_metadata: &fs::Metadata) -> bool {
+    return true;
+}
@@ -0,0 +1,99 @@
+use std::{
+    fs, io,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// set after the first clone fails because file system can't do it, so it is not tried
+/// for every other file
+static UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
+/// copy-on-write clone of `from` (btrfs, xfs with reflink, apfs), files share their
+/// blocks until one of them is changed, so clone is as fast as hardlink, but it is
+/// a separate file
+pub(crate) fn reflink(from: &Path, to: &Path) -> io::Result<()> {
+    if UNSUPPORTED.load(Ordering::Relaxed) {
+        return Err(unsupported());
+    }
+
+    let cloned = clone_file(from, to);
+
+    if let Err(error) = &cloned {
+        if is_unsupported(error) {
+            UNSUPPORTED.store(true, Ordering::Relaxed);
+        }
+    }
+
+    return cloned;
+}
+
+#[cfg(target_os = "linux")]
+fn clone_file(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let source = fs::File::open(from)?;
+    let target = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)?;
+
+    // SAFETY: both descriptors are open for the whole call
+    let result = unsafe { libc::ioctl(target.as_raw_fd(), libc::FICLONE as _, source.as_raw_fd()) };
+
+    if result != 0 {
+        let error = io::Error::last_os_error();
+        let _ = fs::remove_file(to);
+
+        return Err(error);
+    }
+
+    // clone has default mode, exec bits of bins must be kept
+    return fs::set_permissions(to, source.metadata()?.permissions());
+}
+
+#[cfg(target_os = "macos")]
+fn clone_file(from: &Path, to: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let from = CString::new(from.as_os_str().as_bytes())?;
+    let to = CString::new(to.as_os_str().as_bytes())?;
+
+    // SAFETY: both paths are nul terminated, clonefile doesn't keep them
+    let result = unsafe { libc::clonefile(from.as_ptr(), to.as_ptr(), 0) };
+
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    return Ok(());
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn clone_file(_from: &Path, _to: &Path) -> io::Result<()> {
+    return Err(unsupported());
+}
+
+fn unsupported() -> io::Error {
+    return io::Error::new(io::ErrorKind::Unsupported, "reflinks are not supported");
+}
+
+fn is_unsupported(error: &io::Error) -> bool {
+    if error.kind() == io::ErrorKind::Unsupported {
+        return true;
+    }
+
+    let Some(code) = error.raw_os_error() else {
+        return false;
+    };
+
+    // store and node_modules are on different devices, or file system can't clone
+    #[cfg(unix)]
+    return code == libc::EXDEV
+        || code == libc::EOPNOTSUPP
+        || code == libc::ENOTSUP
+        || code == libc::EINVAL
+        || code == libc::ENOTTY;
+
+    #[cfg(not(unix))]
+    return false;
+}
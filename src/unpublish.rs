@@ -0,0 +1,159 @@
+use node_semver::Version;
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+
+use crate::{
+    registry::Registry,
+    registry_api::{encode_name, RegistryApi},
+};
+
+const POLICY: &str = "registry allows unpublishing only within 72 hours after publish, \
+    or later when no other packages depend on it, it had less than 300 downloads last week \
+    and has a single owner, consider `razee deprecate` instead";
+
+/// `razee unpublish <pkg>@<version>`, whole package can be unpublished only with
+/// `razee unpublish <pkg> --force`
+pub async fn unpublish(args: &[String]) {
+    let usage = "usage: razee unpublish <package>@<version> | <package> --force";
+    let force = args.iter().any(|arg| arg == "--force" || arg == "-f");
+    let spec = args.iter().find(|arg| !arg.starts_with("-")).expect(usage);
+
+    let (package_name, version) = match spec.rsplit_once("@") {
+        Some((name, version)) if !name.is_empty() => (name, Some(version)),
+        _ => (spec.as_str(), None),
+    };
+
+    let api = RegistryApi::new(&Registry::from_env());
+    let mut packument = api.packument(package_name).await;
+
+    let Some(version) = version else {
+        if !force {
+            panic!("refusing to unpublish every version of {package_name}, rerun with --force");
+        }
+
+        unpublish_package(&api, package_name, &packument).await;
+
+        return;
+    };
+
+    let remaining_versions = {
+        let versions = packument
+            .get_mut("versions")
+            .and_then(Value::as_object_mut)
+            .expect(format!("{package_name} has no published versions").as_str());
+
+        if !versions.contains_key(version) {
+            panic!("{package_name}@{version} is not published");
+        }
+
+        if versions.len() == 1 {
+            if !force {
+                panic!(
+                    "{version} is the only version of {package_name}, unpublishing it removes \
+                    whole package, rerun with --force"
+                );
+            }
+
+            unpublish_package(&api, package_name, &packument).await;
+
+            return;
+        }
+
+        versions
+            .keys()
+            .filter(|v| *v != version)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    let tarball = packument["versions"][version]["dist"]["tarball"]
+        .as_str()
+        .map(String::from);
+
+    packument["versions"]
+        .as_object_mut()
+        .expect("versions are checked above")
+        .remove(version);
+
+    if let Some(tags) = packument
+        .get_mut("dist-tags")
+        .and_then(Value::as_object_mut)
+    {
+        tags.retain(|_, tagged| tagged.as_str() != Some(version));
+
+        // latest must always point somewhere
+        if !tags.contains_key("latest") {
+            let latest = remaining_versions
+                .iter()
+                .filter_map(|v| Version::parse(v).ok())
+                .max();
+
+            if let Some(latest) = latest {
+                tags.insert("latest".to_string(), Value::from(latest.to_string()));
+            }
+        }
+    }
+
+    let path = format!(
+        "{}/-rev/{}",
+        encode_name(package_name),
+        revision(&packument)
+    );
+    let request = api.request(Method::PUT, &path).json(&packument);
+
+    if let Err((status, reason)) = api.try_send(request).await {
+        explain(&format!("{package_name}@{version}"), status, reason);
+    }
+
+    if let Some(tarball) = tarball {
+        let file_name = tarball.rsplit("/").next().unwrap_or(&tarball).to_string();
+
+        // revision has changed after versions were updated
+        let packument = api.packument(package_name).await;
+        let path = format!(
+            "{}/-/{file_name}/-rev/{}",
+            encode_name(package_name),
+            revision(&packument)
+        );
+
+        if let Err((status, reason)) = api.try_send(api.request(Method::DELETE, &path)).await {
+            explain(&format!("{package_name}@{version} tarball"), status, reason);
+        }
+    }
+
+    println!("- {package_name}@{version}");
+    println!("note: {package_name}@{version} can never be published again");
+}
+
+async fn unpublish_package(api: &RegistryApi, package_name: &str, packument: &Value) {
+    let path = format!("{}/-rev/{}", encode_name(package_name), revision(packument));
+
+    if let Err((status, reason)) = api.try_send(api.request(Method::DELETE, &path)).await {
+        explain(package_name, status, reason);
+    }
+
+    println!("- {package_name}");
+    println!("note: {package_name} cannot be published again for 24 hours");
+}
+
+fn revision(packument: &Value) -> &str {
+    return packument
+        .get("_rev")
+        .and_then(Value::as_str)
+        .expect("registry did not return package revision");
+}
+
+fn explain(target: &str, status: StatusCode, reason: String) -> ! {
+    match status {
+        StatusCode::UNAUTHORIZED => {
+            panic!("cannot unpublish {target}: not logged in (E401), set RAZEE_AUTH_TOKEN")
+        }
+        StatusCode::FORBIDDEN
+        | StatusCode::METHOD_NOT_ALLOWED
+        | StatusCode::UNPROCESSABLE_ENTITY => {
+            panic!("cannot unpublish {target} ({status}): {reason}\n{POLICY}")
+        }
+        StatusCode::NOT_FOUND => panic!("cannot unpublish {target}: not found (E404): {reason}"),
+        _ => panic!("cannot unpublish {target}: registry responded with {status}: {reason}"),
+    }
+}
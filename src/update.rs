@@ -0,0 +1,207 @@
+use node_semver::Version;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    env,
+    io::{self, IsTerminal},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    http_client::HttpClient,
+    install,
+    lockfile::{Lockfile, LOCKFILE},
+    manifest::{self, PACKAGE_JSON},
+    parse_root_package, workspaces, InstallOptions, NODE_MODULES,
+};
+
+/// `razee update [<package>...] [--latest|-L] [-w <workspace>]`: re-resolves packages (or
+/// every package) to the highest versions their ranges allow, ignoring lockfile, other
+/// packages keep locked versions. `--latest` also bumps ranges in package.json to latest
+/// dist tag, keeping their kind (`^`, `~` or exact), other ranges and protocols (e.g.
+/// `>=1 <3`, `workspace:*`) are left as they are
+pub async fn update(args: &[String]) {
+    let usage = "usage: razee update [<package>...] [--latest|-L] [--workspace|-w <workspace>]";
+    let latest = args.iter().any(|arg| arg == "--latest" || arg == "-L");
+
+    let workspace_flag = args
+        .iter()
+        .position(|arg| arg == "--workspace" || arg == "-w");
+    let workspace = workspace_flag.map(|index| args.get(index + 1).expect(usage));
+
+    let names: Vec<String> = args
+        .iter()
+        .enumerate()
+        .filter(|(index, arg)| {
+            !arg.starts_with("-") && workspace_flag.map(|flag| flag + 1) != Some(*index)
+        })
+        .map(|(_, arg)| arg.clone())
+        .collect();
+
+    let current_dir = env::current_dir().expect("cannot get current dir");
+    let root = workspaces::find_root(&current_dir).unwrap_or(current_dir.clone());
+
+    let target_dir = match workspace {
+        Some(workspace) => workspaces::find_workspace(&root, workspace)
+            .expect(format!("no workspace {workspace} in {}", root.display()).as_str()),
+        None => current_dir,
+    };
+
+    env::set_current_dir(&root).expect("cannot enter workspace root");
+
+    // shared with install, so packuments fetched for `--latest` are reused
+    let client = Arc::new(HttpClient::new());
+
+    if latest {
+        bump_ranges(&target_dir.join(PACKAGE_JSON), &names, &client).await;
+    }
+
+    let locked_before = top_level_versions();
+
+    let report = install(InstallOptions {
+        interactive: io::stdin().is_terminal(),
+        client: Some(client),
+        update: Some(names.clone()),
+        ..Default::default()
+    })
+    .await;
+
+    let mut updated = 0;
+
+    for (install_path, package) in report.graph.packages.iter() {
+        let is_top_level = !install_path.contains(&format!("/{NODE_MODULES}/"));
+        let is_selected = names.is_empty() || names.contains(&package.name);
+
+        if !is_top_level || !is_selected {
+            continue;
+        }
+
+        match locked_before.get(install_path) {
+            Some(version) if version != &package.version => {
+                println!("{} {version} -> {}", package.name, package.version);
+                updated += 1;
+            }
+            _ => {}
+        }
+    }
+
+    for name in names.iter() {
+        if !report.graph.packages.contains_key(name) {
+            eprintln!("warning: {name} is not installed");
+        }
+    }
+
+    if updated == 0 {
+        println!("everything is up to date");
+    }
+}
+
+/// sets ranges of `names` (every dependency, when empty) to latest versions
+async fn bump_ranges(manifest_path: &Path, names: &[String], client: &HttpClient) {
+    let mut manifest = manifest::read_raw(manifest_path);
+    let root = parse_root_package();
+    let workspace_names: Vec<String> = workspaces::manifests(Path::new("."), &root)
+        .into_iter()
+        .map(|(_, workspace)| workspace.name)
+        .collect();
+
+    let mut is_bumped = false;
+
+    for section in ["dependencies", "devDependencies"] {
+        let deps: Vec<(String, String)> = manifest
+            .get(section)
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, range)| Some((name.clone(), range.as_str()?.to_string())))
+            .filter(|(name, _)| names.is_empty() || names.contains(name))
+            .filter(|(name, _)| !workspace_names.contains(name))
+            .collect();
+
+        for (name, range) in deps {
+            let Some(prefix) = range_prefix(&range) else {
+                continue;
+            };
+
+            let packument = client.fetch_packument(&name).await;
+            let Some(latest) = latest_version(&packument) else {
+                eprintln!("warning: {name} has no versions");
+                continue;
+            };
+
+            let bumped = format!("{prefix}{latest}");
+
+            if bumped != range {
+                manifest::set_entry(&mut manifest, section, &name, Value::from(bumped));
+                is_bumped = true;
+            }
+        }
+    }
+
+    if !is_bumped {
+        return;
+    }
+
+    manifest::write_raw(manifest_path, &manifest);
+}
+
+/// latest dist tag, packuments without dist tags still have versions
+fn latest_version(packument: &Value) -> Option<String> {
+    if let Some(latest) = packument
+        .pointer("/dist-tags/latest")
+        .and_then(Value::as_str)
+    {
+        return Some(latest.to_string());
+    }
+
+    return packument
+        .get("versions")
+        .and_then(Value::as_object)?
+        .keys()
+        .filter_map(|version| Version::parse(version).ok())
+        .filter(|version| version.pre_release.is_empty())
+        .max()
+        .map(|version| version.to_string());
+}
+
+/// `^` or `~` of simple range (or nothing for exact version), `None` for everything else
+fn range_prefix(range: &str) -> Option<&str> {
+    let (prefix, version) = match range.chars().next() {
+        Some('^') | Some('~') => range.split_at(1),
+        _ => ("", range),
+    };
+
+    if Version::parse(version).is_ok() {
+        return Some(prefix);
+    }
+
+    // partial versions, like `^1` or `~1.2`, are still caret and tilde ranges, but `1`
+    // alone is not an exact one
+    let is_partial = version
+        .split('.')
+        .all(|part| !part.is_empty() && part.chars().all(|char| char.is_ascii_digit()));
+
+    if is_partial && !prefix.is_empty() && version.split('.').count() < 3 {
+        return Some(prefix);
+    }
+
+    return None;
+}
+
+/// install path -> version of packages at the top of node_modules in lockfile
+fn top_level_versions() -> BTreeMap<String, String> {
+    let Some(lockfile) = Lockfile::read(Path::new(LOCKFILE)) else {
+        return BTreeMap::new();
+    };
+
+    return lockfile
+        .packages
+        .into_iter()
+        .filter_map(|(path, locked)| {
+            let install_path = path.strip_prefix(&format!("{NODE_MODULES}/"))?.to_string();
+
+            Some((install_path, locked.version?))
+        })
+        .collect();
+}
@@ -0,0 +1,83 @@
+use futures::FutureExt;
+use serde_json::Value;
+use std::{fs, future::Future, panic::AssertUnwindSafe, path::Path, time::Duration};
+
+use crate::{manifest::PACKAGE_JSON, workspaces};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// sections of package.json that change what gets installed
+const DECLARATIONS: [&str; 8] = [
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+    "overrides",
+    "resolutions",
+    "patchedDependencies",
+    "workspaces",
+];
+
+/// runs `install` every time dependencies declared in package.json (or in workspace
+/// manifests) change, failed install doesn't stop watching
+pub async fn watch<F, Fut>(install: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut last_declarations = None;
+
+    loop {
+        let declarations = read_declarations();
+
+        if last_declarations.as_ref() != Some(&declarations) {
+            if last_declarations.is_some() {
+                println!("dependencies changed, installing");
+            }
+
+            if AssertUnwindSafe(install()).catch_unwind().await.is_err() {
+                println!("install failed, waiting for next change");
+            }
+
+            println!("watching {PACKAGE_JSON} for dependency changes");
+
+            last_declarations = Some(declarations);
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// dependency sections of root and workspace manifests, manifests that can't be
+/// read (e.g. are being saved right now) are `null`
+fn read_declarations() -> Vec<(String, Value)> {
+    let root = Path::new(".");
+    let root_manifest = read_manifest(&root.join(PACKAGE_JSON));
+
+    let mut declarations = vec![(PACKAGE_JSON.to_string(), pick_declarations(&root_manifest))];
+
+    for dir in workspaces::find(root, &root_manifest) {
+        let path = dir.join(PACKAGE_JSON);
+        let manifest = read_manifest(&path);
+
+        declarations.push((path.display().to_string(), pick_declarations(&manifest)));
+    }
+
+    return declarations;
+}
+
+fn read_manifest(path: &Path) -> Value {
+    return fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or(Value::Null);
+}
+
+fn pick_declarations(manifest: &Value) -> Value {
+    let picked = DECLARATIONS
+        .iter()
+        .filter_map(|section| Some((section.to_string(), manifest.get(section)?.clone())))
+        .collect();
+
+    return Value::Object(picked);
+}
@@ -0,0 +1,253 @@
+use node_semver::{Range, Version};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::manifest::{self, Manifest, PACKAGE_JSON};
+
+/// `workspaces` patterns of root package.json, either `["packages/*"]`
+/// or yarn's `{ "packages": ["packages/*"] }`
+pub fn patterns(manifest: &Value) -> Vec<String> {
+    let workspaces = match manifest.get("workspaces") {
+        Some(Value::Object(workspaces)) => workspaces.get("packages"),
+        workspaces => workspaces,
+    };
+
+    return workspaces
+        .and_then(Value::as_array)
+        .map(|patterns| {
+            patterns
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+}
+
+/// dirs of workspace packages (ones with package.json), sorted, patterns can be
+/// plain paths, `dir/*` for direct children and `dir/**` for any depth
+pub fn find(root: &Path, manifest: &Value) -> Vec<PathBuf> {
+    let mut dirs = vec![];
+
+    for pattern in patterns(manifest) {
+        let pattern = pattern.trim_start_matches("./");
+
+        if let Some(parent) = pattern.strip_suffix("/**") {
+            collect_packages(&root.join(parent), true, &mut dirs);
+        } else if let Some(parent) = pattern.strip_suffix("/*") {
+            collect_packages(&root.join(parent), false, &mut dirs);
+        } else if root.join(pattern).join(PACKAGE_JSON).is_file() {
+            dirs.push(root.join(pattern));
+        }
+    }
+
+    dirs.sort();
+    dirs.dedup();
+
+    return dirs;
+}
+
+/// workspace packages of `manifest` in `root` dir, keyed by their path relative to root,
+/// with forward slashes, same as in lockfile
+pub fn manifests(root: &Path, manifest: &Manifest) -> Vec<(String, Manifest)> {
+    let Some(workspaces) = &manifest.workspaces else {
+        return vec![];
+    };
+
+    return find(root, &json!({ "workspaces": workspaces }))
+        .into_iter()
+        .map(|dir| {
+            let manifest_path = dir.join(PACKAGE_JSON);
+            let manifest = serde_json::from_value(manifest::read_raw(&manifest_path))
+                .expect(format!("invalid {}", manifest_path.display()).as_str());
+
+            let path = dir
+                .strip_prefix(root)
+                .unwrap_or(&dir)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            return (path, manifest);
+        })
+        .collect();
+}
+
+/// closest dir above (or at) `dir`, which package.json has `dir` as workspace
+pub fn find_root(dir: &Path) -> Option<PathBuf> {
+    for candidate in dir.ancestors() {
+        let Ok(content) = fs::read_to_string(candidate.join(PACKAGE_JSON)) else {
+            continue;
+        };
+
+        let Ok(manifest) = serde_json::from_str::<Value>(&content) else {
+            continue;
+        };
+
+        if manifest.get("workspaces").is_none() {
+            continue;
+        }
+
+        let is_workspace = candidate == dir
+            || find(candidate, &manifest)
+                .iter()
+                .any(|workspace| workspace == dir);
+
+        if is_workspace {
+            return Some(candidate.to_path_buf());
+        }
+    }
+
+    return None;
+}
+
+/// workspace dir by its path relative to root or by package name
+pub fn find_workspace(root: &Path, workspace: &str) -> Option<PathBuf> {
+    let root_manifest = manifest::read_raw(&root.join(PACKAGE_JSON));
+    let workspace_path = root.join(workspace.trim_start_matches("./"));
+
+    return find(root, &root_manifest).into_iter().find(|dir| {
+        let name = manifest::read_raw(&dir.join(PACKAGE_JSON))
+            .get("name")
+            .and_then(Value::as_str)
+            .map(String::from);
+
+        dir == &workspace_path || name.as_deref() == Some(workspace)
+    });
+}
+
+fn collect_packages(dir: &Path, recursive: bool, dirs: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name();
+
+        if !path.is_dir()
+            || file_name == "node_modules"
+            || file_name.to_string_lossy().starts_with(".")
+        {
+            continue;
+        }
+
+        if path.join(PACKAGE_JSON).is_file() {
+            dirs.push(path.clone());
+        }
+
+        if recursive {
+            collect_packages(&path, recursive, dirs);
+        }
+    }
+}
+
+/// sections of package.json, where workspace packages can depend on each other
+const DEPENDENCY_SECTIONS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+/// indexes of `packages` ordered so that every package comes after workspace packages
+/// it depends on, cycles are broken in declaration order
+pub fn topological_order(packages: &[(PathBuf, Value)]) -> Vec<usize> {
+    let names: HashMap<&str, usize> = packages
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (_, manifest))| Some((manifest.get("name")?.as_str()?, index)))
+        .collect();
+
+    let mut order = vec![];
+    let mut visited = vec![false; packages.len()];
+
+    fn visit(
+        index: usize,
+        packages: &[(PathBuf, Value)],
+        names: &HashMap<&str, usize>,
+        visited: &mut Vec<bool>,
+        order: &mut Vec<usize>,
+    ) {
+        if visited[index] {
+            return;
+        }
+
+        visited[index] = true;
+
+        let (_, manifest) = &packages[index];
+
+        for section in DEPENDENCY_SECTIONS {
+            let Some(deps) = manifest.get(section).and_then(Value::as_object) else {
+                continue;
+            };
+
+            for name in deps.keys() {
+                if let Some(dependency) = names.get(name.as_str()) {
+                    visit(*dependency, packages, names, visited, order);
+                }
+            }
+        }
+
+        order.push(index);
+    }
+
+    for index in 0..packages.len() {
+        visit(index, packages, &names, &mut visited, &mut order);
+    }
+
+    return order;
+}
+
+/// whether `spec` of dependency on workspace package allows its `version`, `workspace:*`,
+/// `workspace:^` and `workspace:~` allow any version, `workspace:<range>` and plain ranges
+/// have to be satisfied
+pub fn allows(spec: &str, version: &str) -> bool {
+    let range = spec.strip_prefix("workspace:").unwrap_or(spec);
+
+    if matches!(range, "*" | "" | "^" | "~") {
+        return true;
+    }
+
+    return match (Range::parse(range), Version::parse(version)) {
+        (Ok(range), Ok(version)) => range.satisfies(&version),
+        _ => false,
+    };
+}
+
+/// replaces `workspace:` specifiers with real ranges before publish: `workspace:*` becomes
+/// exact version, `workspace:^` and `workspace:~` become `^version` and `~version`,
+/// and `workspace:<range>` becomes `<range>`
+pub fn replace_workspace_specs(manifest: &mut Value, versions: &HashMap<String, String>) {
+    for section in DEPENDENCY_SECTIONS {
+        let Some(deps) = manifest.get_mut(section).and_then(Value::as_object_mut) else {
+            continue;
+        };
+
+        for (name, spec) in deps.iter_mut() {
+            let Some(range) = spec
+                .as_str()
+                .and_then(|spec| spec.strip_prefix("workspace:"))
+            else {
+                continue;
+            };
+
+            let version = versions
+                .get(name)
+                .expect(format!("{name} is not a workspace package").as_str());
+
+            let replaced = match range {
+                "*" | "" => version.to_string(),
+                "^" | "~" => format!("{range}{version}"),
+                range => range.to_string(),
+            };
+
+            *spec = Value::from(replaced);
+        }
+    }
+}